@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use std::sync::RwLock;
+
+// https://openjdk.org/jeps/220, the format is the one read by jdk.internal.jimage.BasicImageReader
+const IMAGE_MAGIC: u32 = 0xCAFEDADA;
+const HEADER_SIZE: usize = 28;
+const HASH_MULTIPLIER: u32 = 0x01000193;
+
+const ATTRIBUTE_END: u8 = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+const ATTRIBUTE_COUNT: usize = 8;
+
+const COMPRESSED_HEADER_MAGIC: u32 = 0xCAFEFAFA;
+const COMPRESSED_HEADER_SIZE: usize = 29;
+const ZIP_DECOMPRESSOR: &str = "zip";
+
+/// The `lib/modules` image of the JDK. Opening it only reads the index, the resources are read
+/// when asked for, by their full name like `/java.base/java/lang/Object.class`.
+/// Resources compressed with anything but the `zip` plugin of jlink aren't supported.
+#[derive(Debug)]
+pub(super) struct JImage {
+    file: File,
+    big_endian: bool,
+    index_size: u64,
+    redirect: Vec<i32>,
+    offsets: Vec<u32>,
+    locations: Vec<u8>,
+    strings: Vec<u8>,
+    // offset in the strings of the module with the classes of each package looked up, `None`
+    // for packages the image doesn't have
+    package_modules: RwLock<HashMap<String, Option<usize>>>,
+}
+
+impl JImage {
+    pub(super) fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let mut header = vec![0; HEADER_SIZE];
+        file.read_exact_at(&mut header, 0)?;
+        // written in the byte order of the platform that built the JDK
+        let big_endian = match header[0..4].try_into().unwrap() {
+            bytes if u32::from_le_bytes(bytes) == IMAGE_MAGIC => false,
+            bytes if u32::from_be_bytes(bytes) == IMAGE_MAGIC => true,
+            _ => return Err(invalid_data("not a jimage file".to_string())),
+        };
+        let mut image = Self {
+            file,
+            big_endian,
+            index_size: 0,
+            redirect: Vec::new(),
+            offsets: Vec::new(),
+            locations: Vec::new(),
+            strings: Vec::new(),
+            package_modules: RwLock::new(HashMap::new()),
+        };
+        let table_length = image.u32_at(&header, 16) as usize;
+        let locations_size = image.u32_at(&header, 20) as usize;
+        let strings_size = image.u32_at(&header, 24) as usize;
+
+        let tables = image.read_at(HEADER_SIZE as u64, table_length * 8)?;
+        image.redirect = (0..table_length)
+            .map(|i| image.u32_at(&tables, i * 4) as i32)
+            .collect();
+        image.offsets = (0..table_length)
+            .map(|i| image.u32_at(&tables, (table_length + i) * 4))
+            .collect();
+        let locations_offset = (HEADER_SIZE + table_length * 8) as u64;
+        image.locations = image.read_at(locations_offset, locations_size)?;
+        image.strings = image.read_at(locations_offset + locations_size as u64, strings_size)?;
+        image.index_size = locations_offset + (locations_size + strings_size) as u64;
+        Ok(image)
+    }
+
+    /// The uncompressed content of the resource.
+    pub(super) fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let attributes = self
+            .find_location(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, name))?;
+        let offset = self.index_size + attributes[ATTRIBUTE_OFFSET];
+        let compressed_size = attributes[ATTRIBUTE_COMPRESSED] as usize;
+        let uncompressed_size = attributes[ATTRIBUTE_UNCOMPRESSED] as usize;
+        if compressed_size == 0 {
+            return self.read_at(offset, uncompressed_size);
+        }
+
+        // jlink plugins may stack compressions, each one adds a header to what it compressed
+        let mut content = self.read_at(offset, compressed_size)?;
+        while content.len() >= COMPRESSED_HEADER_SIZE
+            && self.u32_at(&content, 0) == COMPRESSED_HEADER_MAGIC
+        {
+            let size = self.u64_at(&content, 4) as usize;
+            let expected_size = self.u64_at(&content, 12) as usize;
+            let decompressor = self.string_at(self.u32_at(&content, 20) as usize);
+            let Some(data) = content.get(COMPRESSED_HEADER_SIZE..COMPRESSED_HEADER_SIZE + size)
+            else {
+                return Err(invalid_data(format!("{} is truncated", name)));
+            };
+            content = match decompressor {
+                Some(ZIP_DECOMPRESSOR) => {
+                    miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, expected_size)
+                        .map_err(|e| invalid_data(format!("can't inflate {}: {}", name, e)))?
+                }
+                _ => {
+                    return Err(invalid_data(format!(
+                        "{} uses the unsupported decompressor {:?}",
+                        name, decompressor
+                    )));
+                }
+            };
+        }
+        if content.len() != uncompressed_size {
+            return Err(invalid_data(format!("{} is truncated", name)));
+        }
+        Ok(content)
+    }
+
+    /// Module with the classes of the package, from the `/packages/<package>` directory of the
+    /// image. Other modules may list the package too, without classes in it. Every package is
+    /// read once, classes of the same package are looked up all the time.
+    pub(super) fn find_package_module(&self, package: &str) -> Option<&str> {
+        let cached = self.package_modules.read().unwrap().get(package).copied();
+        let module = match cached {
+            Some(module) => module,
+            None => {
+                let module = self.read_package_module(package);
+                self.package_modules
+                    .write()
+                    .unwrap()
+                    .insert(package.to_string(), module);
+                module
+            }
+        };
+        self.string_at(module?)
+    }
+
+    fn read_package_module(&self, package: &str) -> Option<usize> {
+        let entries = self
+            .read(&format!("/packages/{}", package.replace('/', ".")))
+            .ok()?;
+        entries
+            .chunks_exact(8)
+            .find(|entry| self.u32_at(entry, 0) == 0)
+            .map(|entry| self.u32_at(entry, 4) as usize)
+    }
+
+    /// Attributes of the resource, looked up in the perfect hash table of the image.
+    fn find_location(&self, name: &str) -> Option<[u64; ATTRIBUTE_COUNT]> {
+        let table_length = self.redirect.len() as u32;
+        if table_length == 0 {
+            return None;
+        }
+        let index = match self.redirect[(hash_code(name, HASH_MULTIPLIER) % table_length) as usize]
+        {
+            0 => return None,
+            redirect if redirect < 0 => (-1 - redirect) as usize,
+            seed => (hash_code(name, seed as u32) % table_length) as usize,
+        };
+        let attributes = self.read_location(*self.offsets.get(index)? as usize)?;
+        // a name that isn't in the image still hashes to some location
+        if self.location_name(&attributes)? != name {
+            return None;
+        }
+        Some(attributes)
+    }
+
+    fn read_location(&self, mut pos: usize) -> Option<[u64; ATTRIBUTE_COUNT]> {
+        let mut attributes = [0; ATTRIBUTE_COUNT];
+        loop {
+            let byte = *self.locations.get(pos)?;
+            let kind = byte >> 3;
+            if kind == ATTRIBUTE_END {
+                return Some(attributes);
+            }
+            let length = (byte & 0x7) as usize + 1;
+            let value = self
+                .locations
+                .get(pos + 1..pos + 1 + length)?
+                .iter()
+                .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+            *attributes.get_mut(kind as usize)? = value;
+            pos += 1 + length;
+        }
+    }
+
+    fn location_name(&self, attributes: &[u64; ATTRIBUTE_COUNT]) -> Option<String> {
+        let mut name = String::new();
+        if attributes[ATTRIBUTE_MODULE] != 0 {
+            name.push('/');
+            name.push_str(self.string_at(attributes[ATTRIBUTE_MODULE] as usize)?);
+            name.push('/');
+        }
+        if attributes[ATTRIBUTE_PARENT] != 0 {
+            name.push_str(self.string_at(attributes[ATTRIBUTE_PARENT] as usize)?);
+            name.push('/');
+        }
+        name.push_str(self.string_at(attributes[ATTRIBUTE_BASE] as usize)?);
+        if attributes[ATTRIBUTE_EXTENSION] != 0 {
+            name.push('.');
+            name.push_str(self.string_at(attributes[ATTRIBUTE_EXTENSION] as usize)?);
+        }
+        Some(name)
+    }
+
+    fn string_at(&self, offset: usize) -> Option<&str> {
+        let bytes = self.strings.get(offset..)?;
+        let end = bytes.iter().position(|byte| *byte == 0)?;
+        std::str::from_utf8(&bytes[..end]).ok()
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        self.file.read_exact_at(&mut buf, offset)?;
+        Ok(buf)
+    }
+
+    fn u32_at(&self, bytes: &[u8], pos: usize) -> u32 {
+        let bytes = bytes[pos..pos + 4].try_into().unwrap();
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    fn u64_at(&self, bytes: &[u8], pos: usize) -> u64 {
+        let bytes = bytes[pos..pos + 8].try_into().unwrap();
+        if self.big_endian {
+            u64::from_be_bytes(bytes)
+        } else {
+            u64::from_le_bytes(bytes)
+        }
+    }
+}
+
+/// `ImageStringsReader.hashCode`, names are ASCII in practice so the UTF-8 bytes are the same as
+/// the modified UTF-8 ones the JDK hashes.
+fn hash_code(name: &str, seed: u32) -> u32 {
+    name.bytes().fold(seed, |hash, byte| {
+        hash.wrapping_mul(HASH_MULTIPLIER) ^ byte as u32
+    }) & 0x7FFF_FFFF
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,146 @@
+use crate::class_loader::zip::ZipArchive;
+use crate::debug_log;
+use std::io;
+use std::path::Path;
+
+const MANIFEST_NAME: &str = "META-INF/MANIFEST.MF";
+
+/// Launcher attributes of the main section of a JAR manifest, for `-jar`.
+/// https://docs.oracle.com/en/java/javase/25/docs/specs/jar/jar.html#jar-manifest
+#[derive(Debug, Default)]
+pub struct JarManifest {
+    pub main_class: Option<String>,
+    // Class-Path entries, the relative ones resolved against the directory of the JAR
+    pub class_path: Vec<String>,
+}
+
+impl JarManifest {
+    /// A JAR without a manifest has no attributes.
+    pub fn read(jar_path: &Path) -> io::Result<Self> {
+        let archive = ZipArchive::open(jar_path)?;
+        let bytes = match archive.read(MANIFEST_NAME) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let jar_dir = jar_path.parent().unwrap_or(Path::new(""));
+        let mut manifest = Self::default();
+        for (name, value) in main_attributes(&String::from_utf8_lossy(&bytes)) {
+            if name.eq_ignore_ascii_case("Main-Class") {
+                manifest.main_class = Some(value.trim().to_string());
+            } else if name.eq_ignore_ascii_case("Class-Path") {
+                manifest.class_path = class_path_entries(jar_dir, &value);
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// Class-Path is a space separated list of URLs, the relative ones are resolved against the
+/// directory of the JAR. Like in the JDK, entries that aren't local files are ignored.
+fn class_path_entries(jar_dir: &Path, value: &str) -> Vec<String> {
+    value
+        .split_whitespace()
+        .filter_map(|entry| match entry_path(entry) {
+            Some(path) => Some(jar_dir.join(path).to_string_lossy().into_owned()),
+            None => {
+                debug_log!("Ignoring Class-Path entry {}, not a local file", entry);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Percent-decoded path of a relative URL or a `file:` one, `None` for other schemes, remote
+/// hosts and malformed escapes.
+fn entry_path(entry: &str) -> Option<String> {
+    let path = match entry.split_once(':') {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("file") => {
+            match rest.strip_prefix("//") {
+                Some(authority_and_path) => {
+                    let (authority, path) =
+                        authority_and_path.split_at(authority_and_path.find('/')?);
+                    if !authority.is_empty() && !authority.eq_ignore_ascii_case("localhost") {
+                        return None;
+                    }
+                    path
+                }
+                None => rest,
+            }
+        }
+        // a colon before any slash ends a scheme, a relative path can't have one there
+        Some((scheme, _)) if !scheme.contains('/') => return None,
+        _ => entry,
+    };
+    percent_decode(path)
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// `Name: value` pairs up to the first empty line, long values continue on lines starting
+/// with a space.
+fn main_attributes(manifest: &str) -> Vec<(String, String)> {
+    let mut attributes: Vec<(String, String)> = Vec::new();
+    for line in manifest.lines() {
+        if line.is_empty() {
+            break;
+        }
+        match line.strip_prefix(' ') {
+            Some(continuation) => {
+                if let Some((_, value)) = attributes.last_mut() {
+                    value.push_str(continuation);
+                }
+            }
+            None => {
+                if let Some((name, value)) = line.split_once(':') {
+                    attributes.push((name.to_string(), value.trim_start().to_string()));
+                }
+            }
+        }
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_path_entries_are_decoded_urls() {
+        let jar_dir = Path::new("/apps/demo");
+        let entries = class_path_entries(
+            jar_dir,
+            "lib/my%20dep.jar file:/opt/libs/shared.jar file:///opt/libs/other%2Bdep.jar \
+             file://localhost/opt/local.jar http://example.com/remote.jar \
+             file://example.com/opt/remote.jar lib/bad%2.jar",
+        );
+
+        assert_eq!(
+            entries,
+            vec![
+                "/apps/demo/lib/my dep.jar",
+                "/opt/libs/shared.jar",
+                "/opt/libs/other+dep.jar",
+                "/opt/local.jar",
+            ]
+        );
+    }
+}
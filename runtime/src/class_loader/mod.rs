@@ -1,20 +1,46 @@
+use crate::class_loader::jimage::JImage;
 use crate::class_loader::system::SystemClassLoader;
 use crate::error::JvmError;
-use crate::{VmConfig, debug_log};
-use lagertha_image::JImage;
-use std::path::PathBuf;
+use crate::{VmConfig, debug_log, throw_exception};
+use std::io;
 //use toml::Value;
 //use toml_edit::Document;
 
+mod jimage;
+mod manifest;
 mod system;
+mod zip;
 
-// TODO: It is more like a stub for now, need to respect the doc
+pub use manifest::JarManifest;
 
-#[derive(Debug, Clone)]
-struct ClassSource {
-    jmod_path: PathBuf,
-    entry_name: String,
-}
+/// Modules of the image the JDK defines to the boot loader, the others belong to the platform
+/// and app loaders (make/conf/module-loader-map.conf in the JDK sources).
+const BOOT_MODULES: &[&str] = &[
+    "java.base",
+    "java.datatransfer",
+    "java.desktop",
+    "java.instrument",
+    "java.logging",
+    "java.management",
+    "java.management.rmi",
+    "java.naming",
+    "java.prefs",
+    "java.rmi",
+    "java.security.sasl",
+    "java.xml",
+    "jdk.incubator.vector",
+    "jdk.internal.vm.ci",
+    "jdk.jfr",
+    "jdk.management",
+    "jdk.management.agent",
+    "jdk.management.jfr",
+    "jdk.net",
+    "jdk.nio.mapmode",
+    "jdk.sctp",
+    "jdk.unsupported",
+];
+
+// TODO: It is more like a stub for now, need to respect the doc
 
 /// https://docs.oracle.com/javase/specs/jvms/se25/html/jvms-5.html#jvms-5.3.1
 
@@ -29,7 +55,9 @@ impl ClassLoader {
         debug_log!("Creating ClassLoader...");
         let modules_path = &vm_config.home.join("lib").join("modules");
         debug_log!("Loading JImage from path: {:?}", modules_path);
-        let jimage = JImage::new(modules_path);
+        let jimage = JImage::open(modules_path).map_err(|e| {
+            JvmError::Todo(format!("Error opening jimage {:?}: {}", modules_path, e))
+        })?;
         debug_log!(
             "Loading SystemClassLoader from classpath: {:?}",
             vm_config.class_path
@@ -45,17 +73,54 @@ impl ClassLoader {
         })
     }
 
+    /// Bytecode of a class of the boot loader, from the boot modules of the image. The classpath
+    /// belongs to the app loader.
     #[hotpath::measure]
     pub fn load(&self, name: &str) -> Result<Vec<u8>, JvmError> {
-        if let Some(bytes) = self.jimage.open_java_base_class(name) {
-            debug_log!("Bytecode of \"{name}\" found using JImage.");
-            //self.add_tested_class(name)?;
-            Ok(bytes.to_vec())
-        } else {
-            let bytes = self.system.find_class(name)?;
-            debug_log!("Bytecode of \"{name}\" found using SystemClassLoader.");
-            Ok(bytes)
+        self.load_from_image(name, true)
+    }
+
+    /// Bytecode of a class of the platform loader, from the other modules of the image, like
+    /// `java.sql`. The tool modules the JDK gives to the app loader are the platform loader's here.
+    pub fn load_from_platform_modules(&self, name: &str) -> Result<Vec<u8>, JvmError> {
+        self.load_from_image(name, false)
+    }
+
+    /// Bytecode of a class of the app loader, from the first classpath entry that has it.
+    pub fn load_from_class_path(&self, name: &str) -> Result<Vec<u8>, JvmError> {
+        let bytes = self.system.find_class(name)?;
+        debug_log!("Bytecode of \"{name}\" found using SystemClassLoader.");
+        Ok(bytes)
+    }
+
+    fn load_from_image(&self, name: &str, boot: bool) -> Result<Vec<u8>, JvmError> {
+        if let Some(module) = self
+            .find_module(name)
+            .filter(|module| BOOT_MODULES.contains(module) == boot)
+        {
+            match self.jimage.read(&format!("/{}/{}.class", module, name)) {
+                Ok(bytes) => {
+                    debug_log!("Bytecode of \"{name}\" found in module {module} using JImage.");
+                    //self.add_tested_class(name)?;
+                    return Ok(bytes);
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(JvmError::Todo(format!(
+                        "Error reading {} from jimage: {}",
+                        name, e
+                    )));
+                }
+            }
         }
+        throw_exception!(ClassNotFoundException, name.replace('/', "."))
+    }
+
+    /// Module of the image with the package of the class, classes of the unnamed package are
+    /// only on the classpath.
+    fn find_module(&self, name: &str) -> Option<&str> {
+        let (package, _) = name.rsplit_once('/')?;
+        self.jimage.find_package_module(package)
     }
 
     /*
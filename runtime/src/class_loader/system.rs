@@ -1,78 +1,100 @@
-use crate::class_loader::ClassSource;
+use crate::class_loader::zip::ZipArchive;
 use crate::error::JvmError;
 use crate::{build_exception, debug_log};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Read;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::RwLock;
 
 #[derive(Debug)]
 pub(super) struct SystemClassLoader {
-    index: HashMap<String, ClassSource>,
+    // searched in classpath order, the first entry containing the class wins
+    entries: Vec<ClasspathEntry>,
+    // entry each class looked up was found in, `None` if none has it, so every class name is
+    // searched for once
+    index: RwLock<HashMap<String, Option<usize>>>,
+}
+
+#[derive(Debug)]
+enum ClasspathEntry {
+    // class files are looked up by their relative path when asked for, nothing is walked
+    Directory(PathBuf),
+    // JAR or ZIP, its central directory is the index of the classes it contains
+    Archive(ZipArchive),
 }
 
 impl SystemClassLoader {
     pub fn new(path: &Vec<String>) -> Result<Self, JvmError> {
         debug_log!("Creating SystemClassLoader from classpath entries: {path:?}");
-        let mut index = HashMap::new();
+        let mut entries = Vec::with_capacity(path.len());
 
         for entry in path {
-            let files_and_folders = WalkDir::new(entry);
-            let files_and_folders = files_and_folders.into_iter().collect::<Vec<_>>();
-            let java_classes: Vec<_> = files_and_folders
-                .into_iter()
-                .filter_map(Result::ok)
-                .map(|e| e.into_path())
-                .filter(|path| {
-                    path.is_file() && path.extension().map(|ext| ext == "class").unwrap_or(false)
-                })
-                .collect();
-            for class in java_classes {
-                let rel = class.strip_prefix(entry).unwrap_or(&class);
-                let rel_str = Self::path_to_forward_slash(rel);
-                if let Some(key) = Self::binary_name_from_rel(&rel_str) {
-                    index.entry(key).or_insert_with(|| ClassSource {
-                        jmod_path: PathBuf::from(entry),
-                        entry_name: rel.to_string_lossy().into_owned(),
-                    });
-                }
+            if Self::is_archive(entry) {
+                let archive = ZipArchive::open(Path::new(entry)).map_err(|e| {
+                    JvmError::Todo(format!("Error opening zip file {}: {}", entry, e))
+                })?;
+                entries.push(ClasspathEntry::Archive(archive));
+            } else {
+                entries.push(ClasspathEntry::Directory(PathBuf::from(entry)));
             }
         }
 
-        debug_log!(
-            "System classpath index prepared. Found {} classes.",
-            index.len()
-        );
-        Ok(Self { index })
+        debug_log!("System classpath prepared with {} entries.", entries.len());
+        Ok(Self {
+            entries,
+            index: RwLock::new(HashMap::new()),
+        })
     }
 
     #[hotpath::measure]
     pub(crate) fn find_class(&self, name: &str) -> Result<Vec<u8>, JvmError> {
+        let not_found = || build_exception!(ClassNotFoundException, name.replace('/', "."));
         let key = Self::normalize_key(name);
-        let src = self
-            .index
-            .get(&key)
-            .ok_or_else(|| build_exception!(ClassNotFoundException, name.replace('/', ".")))?;
+        if !Self::is_valid_key(&key) {
+            return Err(not_found());
+        }
+        let entry_name = format!("{}.class", key);
+
+        let cached = self.index.read().unwrap().get(&key).copied();
+        let found = match cached {
+            Some(found) => found,
+            None => {
+                let found = self.find_entry(&entry_name);
+                self.index.write().unwrap().insert(key, found);
+                found
+            }
+        };
+        let Some(index) = found else {
+            return Err(not_found());
+        };
+        let bytes = match &self.entries[index] {
+            ClasspathEntry::Directory(dir) => std::fs::read(dir.join(&entry_name)),
+            ClasspathEntry::Archive(archive) => archive.read(&entry_name),
+        };
+        bytes.map_err(|_| not_found())
+    }
 
-        let abs_path = src.jmod_path.join(&src.entry_name);
-        let mut file = File::open(&abs_path)
-            .map_err(|_| build_exception!(ClassNotFoundException, name.replace('/', ".")))?;
-        let mut buf = Vec::new();
-        file.read_to_end(&mut buf)
-            .map_err(|_| build_exception!(ClassNotFoundException, name.replace('/', ".")))?;
-        Ok(buf)
+    /// Index of the first entry in classpath order with the class file, only its path is
+    /// checked in directories.
+    fn find_entry(&self, entry_name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| match entry {
+            ClasspathEntry::Directory(dir) => dir.join(entry_name).is_file(),
+            ClasspathEntry::Archive(archive) => archive.contains(entry_name),
+        })
     }
 
-    fn path_to_forward_slash(p: &Path) -> String {
-        p.components()
-            .map(|c| c.as_os_str().to_string_lossy())
-            .collect::<Vec<_>>()
-            .join("/")
+    /// Classpath entries ending with `.jar` or `.zip` are archives, if they exist.
+    fn is_archive(entry: &str) -> bool {
+        let path = Path::new(entry);
+        path.is_file()
+            && path.extension().is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("jar") || ext.eq_ignore_ascii_case("zip")
+            })
     }
 
-    fn binary_name_from_rel(rel: &str) -> Option<String> {
-        rel.strip_suffix(".class").map(|s| s.to_string())
+    /// The key becomes a path relative to the classpath entries, it mustn't escape them.
+    fn is_valid_key(key: &str) -> bool {
+        key.split('/')
+            .all(|c| !c.is_empty() && !c.contains(['\\', ':', '\0']))
     }
 
     fn normalize_key(name: &str) -> String {
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+// https://pkware.cachefly.net/webdocs/casestudies/APPNOTE.TXT
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const LOCAL_HEADER_SIZE: usize = 30;
+const CENTRAL_HEADER_SIZE: usize = 46;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+const ENCRYPTED_FLAG: u16 = 0x1;
+
+/// JAR or ZIP archive of the classpath. Opening it only reads the central directory, the
+/// entries are read and inflated when asked for.
+/// ZIP64 archives and encrypted entries aren't supported.
+#[derive(Debug)]
+pub(super) struct ZipArchive {
+    file: File,
+    entries: HashMap<String, ZipEntry>,
+}
+
+#[derive(Debug)]
+struct ZipEntry {
+    flags: u16,
+    method: u16,
+    compressed_size: usize,
+    uncompressed_size: usize,
+    local_header_offset: u64,
+}
+
+impl ZipArchive {
+    pub(super) fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let mut archive = Self {
+            file,
+            entries: HashMap::new(),
+        };
+        archive.read_central_directory()?;
+        Ok(archive)
+    }
+
+    pub(super) fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// The uncompressed content of the entry.
+    pub(super) fn read(&self, name: &str) -> io::Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .get(name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, name))?;
+        if entry.flags & ENCRYPTED_FLAG != 0 {
+            return Err(invalid_data(format!("{} is encrypted", name)));
+        }
+        let header = self.read_at(entry.local_header_offset, LOCAL_HEADER_SIZE)?;
+        if u32_at(&header, 0) != LOCAL_HEADER_SIGNATURE {
+            return Err(invalid_data(format!("bad local header of {}", name)));
+        }
+        // the local name and extra field may differ in length from the central directory ones
+        let data_offset = entry.local_header_offset
+            + (LOCAL_HEADER_SIZE + u16_at(&header, 26) as usize + u16_at(&header, 28) as usize)
+                as u64;
+        let data = self.read_at(data_offset, entry.compressed_size)?;
+        let content = match entry.method {
+            STORED => data,
+            DEFLATED => {
+                miniz_oxide::inflate::decompress_to_vec_with_limit(&data, entry.uncompressed_size)
+                    .map_err(|e| invalid_data(format!("can't inflate {}: {}", name, e)))?
+            }
+            method => {
+                return Err(invalid_data(format!(
+                    "{} uses the unsupported compression method {}",
+                    name, method
+                )));
+            }
+        };
+        if content.len() != entry.uncompressed_size {
+            return Err(invalid_data(format!("{} is truncated", name)));
+        }
+        Ok(content)
+    }
+
+    fn read_central_directory(&mut self) -> io::Result<()> {
+        // the end of central directory record is last, only followed by a comment of up to 64K
+        let file_size = self.file.metadata()?.len();
+        let tail_size = file_size.min((END_OF_CENTRAL_DIRECTORY_SIZE + u16::MAX as usize) as u64);
+        let tail = self.read_at(file_size - tail_size, tail_size as usize)?;
+        let end = (0..(tail.len() + 1).saturating_sub(END_OF_CENTRAL_DIRECTORY_SIZE))
+            .rev()
+            .find(|pos| u32_at(&tail, *pos) == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+            .ok_or_else(|| invalid_data("not a ZIP archive".to_string()))?;
+        let entry_count = u16_at(&tail, end + 10);
+        let directory_size = u32_at(&tail, end + 12);
+        let directory_offset = u32_at(&tail, end + 16);
+        if entry_count == u16::MAX || directory_offset == u32::MAX {
+            return Err(invalid_data("ZIP64 archives aren't supported".to_string()));
+        }
+
+        let directory = self.read_at(directory_offset as u64, directory_size as usize)?;
+        let mut pos = 0;
+        for _ in 0..entry_count {
+            if pos + CENTRAL_HEADER_SIZE > directory.len()
+                || u32_at(&directory, pos) != CENTRAL_HEADER_SIGNATURE
+            {
+                return Err(invalid_data("bad central directory".to_string()));
+            }
+            let name_length = u16_at(&directory, pos + 28) as usize;
+            let extra_length = u16_at(&directory, pos + 30) as usize;
+            let comment_length = u16_at(&directory, pos + 32) as usize;
+            let name_start = pos + CENTRAL_HEADER_SIZE;
+            let Some(name) = directory.get(name_start..name_start + name_length) else {
+                return Err(invalid_data("bad central directory".to_string()));
+            };
+            let entry = ZipEntry {
+                flags: u16_at(&directory, pos + 8),
+                method: u16_at(&directory, pos + 10),
+                compressed_size: u32_at(&directory, pos + 20) as usize,
+                uncompressed_size: u32_at(&directory, pos + 24) as usize,
+                local_header_offset: u32_at(&directory, pos + 42) as u64,
+            };
+            self.entries
+                .insert(String::from_utf8_lossy(name).into_owned(), entry);
+            pos = name_start + name_length + extra_length + comment_length;
+        }
+        Ok(())
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        self.file.read_exact_at(&mut buf, offset)?;
+        Ok(buf)
+    }
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -9,7 +9,6 @@ use std::fmt::Display;
 
 #[derive(Debug)]
 pub enum JvmError {
-    MainClassNotFound(String),
     Linkage(LinkageError),
     Cursor(CursorError),
     RuntimePool(RuntimePoolError),
@@ -80,6 +79,10 @@ impl Display for JvmError {
 }
 
 impl JvmError {
+    pub fn is_out_of_memory(&self) -> bool {
+        matches!(self, JvmError::JavaException(e) if e.kind == JavaExceptionKind::OutOfMemoryError)
+    }
+
     pub fn into_pretty_string(self, interner: &ThreadedRodeo) -> String {
         match self {
             JvmError::JavaException(ex) => {
@@ -117,6 +120,10 @@ pub enum ExceptionMessage {
         expected: RuntimeConstantType,
         actual: RuntimeConstantType,
     },
+    ClassCast {
+        from: Symbol,
+        to: Symbol,
+    },
 }
 
 impl ExceptionMessage {
@@ -141,6 +148,13 @@ impl ExceptionMessage {
                     pool_idx, expected, actual
                 )
             }
+            ExceptionMessage::ClassCast { from, to } => {
+                format!(
+                    "class {} cannot be cast to class {}",
+                    interner.resolve(&from).replace('/', "."),
+                    interner.resolve(&to).replace('/', ".")
+                )
+            }
         }
     }
 }
@@ -153,12 +167,20 @@ pub enum JavaExceptionKind {
     NegativeArraySizeException,
     NullPointerException,
     ArrayStoreException,
+    ClassCastException,
+    CloneNotSupportedException,
+    IllegalMonitorStateException,
+    InterruptedException,
     InternalError,
+    OutOfMemoryError,
     NoSuchMethodError,
+    NoSuchFieldError,
     ClassNotFoundException,
     UnsatisfiedLinkError,
     IncompatibleClassChangeError,
     ClassFormatError,
+    LinkageError,
+    NoClassDefFoundError,
     IOException,
 }
 
@@ -171,12 +193,20 @@ impl JavaExceptionKind {
             Self::NegativeArraySizeException => "java/lang/NegativeArraySizeException",
             Self::NullPointerException => "java/lang/NullPointerException",
             Self::ArrayStoreException => "java/lang/ArrayStoreException",
+            Self::ClassCastException => "java/lang/ClassCastException",
+            Self::CloneNotSupportedException => "java/lang/CloneNotSupportedException",
+            Self::IllegalMonitorStateException => "java/lang/IllegalMonitorStateException",
+            Self::InterruptedException => "java/lang/InterruptedException",
             Self::InternalError => "java/lang/InternalError",
+            Self::OutOfMemoryError => "java/lang/OutOfMemoryError",
             Self::NoSuchMethodError => "java/lang/NoSuchMethodError",
+            Self::NoSuchFieldError => "java/lang/NoSuchFieldError",
             Self::ClassNotFoundException => "java/lang/ClassNotFoundException",
             Self::UnsatisfiedLinkError => "java/lang/UnsatisfiedLinkError",
             Self::IncompatibleClassChangeError => "java/lang/IncompatibleClassChangeError",
             Self::ClassFormatError => "java/lang/ClassFormatError",
+            Self::LinkageError => "java/lang/LinkageError",
+            Self::NoClassDefFoundError => "java/lang/NoClassDefFoundError",
             Self::IOException => "java/io/IOException",
         }
     }
@@ -243,6 +273,14 @@ impl JavaExceptionFromJvm {
         }
     }
 
+    pub fn with_class_cast(kind: JavaExceptionKind, from: Symbol, to: Symbol) -> Self {
+        Self {
+            kind,
+            message: Some(ExceptionMessage::ClassCast { from, to }),
+            cause: None,
+        }
+    }
+
     pub fn as_reference(&self) -> JavaExceptionReference {
         JavaExceptionReference {
             class: self.kind.class_name(),
@@ -0,0 +1,64 @@
+use crate::heap::HeapRef;
+
+const CARD_SHIFT: usize = 9;
+const CARD_SIZE: usize = 1 << CARD_SHIFT;
+const NO_OBJECT: u16 = u16::MAX;
+
+/// Remembers which parts of the old space may reference nursery objects, so a minor collection
+/// only scans those. The old space is split in 512 byte cards, a card is dirtied when a nursery
+/// reference is stored into an object starting in it.
+pub(super) struct CardTable {
+    dirty: Vec<bool>,
+    // offset within the card of the first object starting in it, the table covers the whole
+    // reserved old space so it's kept small
+    first_object: Vec<u16>,
+}
+
+impl CardTable {
+    pub(super) fn new(old_space_size: usize) -> Self {
+        let cards = (old_space_size >> CARD_SHIFT) + 1;
+        Self {
+            dirty: vec![false; cards],
+            first_object: vec![NO_OBJECT; cards],
+        }
+    }
+
+    pub(super) fn dirty(&mut self, heap_ref: HeapRef) {
+        self.dirty[heap_ref >> CARD_SHIFT] = true;
+    }
+
+    /// Registers an object allocated in the old space, objects are registered in address order.
+    pub(super) fn record_object(&mut self, heap_ref: HeapRef) {
+        let first = &mut self.first_object[heap_ref >> CARD_SHIFT];
+        if *first == NO_OBJECT {
+            *first = (heap_ref & (CARD_SIZE - 1)) as u16;
+        }
+    }
+
+    /// Cleans the dirty cards below `end`, the end of the allocated old space, returns the
+    /// address range each of them covers.
+    pub(super) fn take_dirty(&mut self, end: HeapRef) -> Vec<(HeapRef, HeapRef)> {
+        let mut cards = Vec::new();
+        let used_cards = end.div_ceil(CARD_SIZE).min(self.dirty.len());
+        for (card, dirty) in self.dirty[..used_cards].iter_mut().enumerate() {
+            if *dirty {
+                *dirty = false;
+                cards.push((card << CARD_SHIFT, (card + 1) << CARD_SHIFT));
+            }
+        }
+        cards
+    }
+
+    /// First object starting in the card that covers `heap_ref`.
+    pub(super) fn first_object(&self, heap_ref: HeapRef) -> Option<HeapRef> {
+        let first = self.first_object[heap_ref >> CARD_SHIFT];
+        (first != NO_OBJECT).then(|| (heap_ref & !(CARD_SIZE - 1)) + first as usize)
+    }
+
+    /// Forgets all objects and dirty cards, the old space is registered again after it is
+    /// compacted.
+    pub(super) fn clear(&mut self) {
+        self.dirty.fill(false);
+        self.first_object.fill(NO_OBJECT);
+    }
+}
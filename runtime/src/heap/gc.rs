@@ -0,0 +1,348 @@
+use crate::Symbol;
+use crate::debug_log;
+use crate::error::JvmError;
+use crate::heap::method_area::MethodArea;
+use crate::heap::reference::ReferenceDiscovery;
+use crate::heap::{Heap, HeapRef, ObjectHeader};
+use crate::keys::ClassId;
+use crate::thread::JavaThreadState;
+use crate::vm::Value;
+use lagertha_common::jtype::AllocationType;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+
+// objects are 8 byte aligned, forwarding addresses are kept in 8 byte units so they fit the
+// u32 monitor id of the header (heaps up to 32G)
+const FORWARDING_UNIT: usize = 8;
+
+impl Heap {
+    /// Stop-the-world collection: a minor collection of the nursery, followed by a full one and
+    /// another minor one if the old space fills up or `full` is set. Must run at a safepoint: the given threads are
+    /// all the java threads, and none of them holds a reference the collector can't see, those
+    /// it sees are updated when objects move. Softly reachable objects are only collected by a
+    /// full collection with `clear_soft_references`.
+    pub fn collect(
+        &mut self,
+        method_area: &mut MethodArea,
+        threads: &mut [&mut JavaThreadState],
+        full: bool,
+        clear_soft_references: bool,
+    ) -> Result<(), JvmError> {
+        let young_collected = self.collect_young(method_area, threads)?;
+        if full || !young_collected || self.allocated >= self.gc_threshold {
+            self.collect_full(method_area, threads, clear_soft_references)?;
+            // the full collection leaves dead nursery objects in place, among them those the
+            // minor one kept alive through the dirty cards of dead old objects
+            self.collect_young(method_area, threads)?;
+        }
+        self.gc_requested.store(false, Ordering::Relaxed);
+        debug_log!(
+            "GC: {}K used of {}K",
+            self.used() / 1024,
+            self.total_memory() / 1024
+        );
+        Ok(())
+    }
+
+    /// Mark-compact collection (Lisp-2) of the old space: live objects slide down to its start
+    /// in their original order, so allocation stays a bump of `allocated`. Nursery objects are
+    /// marked and their references updated, but they stay where they are. The references of the
+    /// dead ones are cleared, their targets may be gone.
+    fn collect_full(
+        &mut self,
+        method_area: &mut MethodArea,
+        threads: &mut [&mut JavaThreadState],
+        clear_soft_references: bool,
+    ) -> Result<(), JvmError> {
+        let mut roots = Vec::new();
+        visit_roots(
+            &mut self.string_pool,
+            &mut self.reference_pending_list,
+            method_area,
+            threads,
+            &mut |heap_ref| roots.push(*heap_ref),
+        );
+        let mut references = ReferenceDiscovery::new(clear_soft_references);
+        self.mark(method_area, roots, &mut references)?;
+        references.process(self, method_area, |heap, referent| {
+            heap.get_header(referent).marked.then_some(referent)
+        })?;
+
+        let preserved_monitors = self.compute_forwarding();
+        self.sweep_monitors(|heap, heap_ref| {
+            heap.get_header(heap_ref)
+                .marked
+                .then(|| heap.forwardee(heap_ref))
+        });
+        self.update_references(method_area)?;
+        // the heap roots are taken out, so the visitor can read the forwarding addresses
+        let mut string_pool = std::mem::take(&mut self.string_pool);
+        let mut reference_pending_list = self.reference_pending_list;
+        visit_roots(
+            &mut string_pool,
+            &mut reference_pending_list,
+            method_area,
+            threads,
+            &mut |heap_ref| *heap_ref = self.forwardee(*heap_ref),
+        );
+        self.string_pool = string_pool;
+        self.reference_pending_list = reference_pending_list;
+        self.compact(preserved_monitors);
+        self.rebuild_card_table(method_area)?;
+
+        // grow the old space so at least 40% of it is free (hotspot's MinHeapFreeRatio), the next
+        // collection starts once half of the free space is used up
+        self.commit((self.allocated + self.allocated * 2 / 3).min(self.old_end));
+        self.gc_threshold = self.allocated + (self.committed - self.allocated) / 2;
+        Ok(())
+    }
+
+    /// Marks everything reachable from the roots, except the referents only reachable through
+    /// the references `references` discovers.
+    fn mark(
+        &mut self,
+        method_area: &MethodArea,
+        roots: Vec<HeapRef>,
+        references: &mut ReferenceDiscovery,
+    ) -> Result<(), JvmError> {
+        // offsets of the reference fields, per class of the visited instances
+        let mut ref_offsets: HashMap<ClassId, Vec<usize>> = HashMap::new();
+        let mut gray = roots;
+        while let Some(heap_ref) = gray.pop() {
+            if heap_ref == 0 {
+                continue;
+            }
+            let header = self.get_header_mut(heap_ref);
+            if header.marked {
+                continue;
+            }
+            header.marked = true;
+
+            let referent_slot = references.discover(self, heap_ref, method_area)?;
+            for slot in self.reference_slots(heap_ref, method_area, &mut ref_offsets)? {
+                if referent_slot == Some(slot) {
+                    continue;
+                }
+                if let Value::Ref(target) =
+                    self.read_field(heap_ref, slot, AllocationType::Reference)?
+                {
+                    gray.push(target);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Gives every marked object the address it gets once compacted, in heap order.
+    /// The forwarding address takes the place of the monitor id in the header, the ids of
+    /// inflated monitors are returned with the new addresses, to be restored after the move.
+    fn compute_forwarding(&mut self) -> Vec<(HeapRef, u32)> {
+        let mut preserved_monitors = Vec::new();
+        let mut free = ObjectHeader::SIZE;
+        let mut offset = ObjectHeader::SIZE;
+        while offset < self.allocated {
+            let header = self.get_header_mut(offset);
+            let size = header.size as usize;
+            if header.marked {
+                if header.monitor_id != 0 {
+                    preserved_monitors.push((free, header.monitor_id));
+                }
+                self.set_forwarding_address(offset, free);
+                free += size;
+            }
+            offset += size;
+        }
+        preserved_monitors
+    }
+
+    /// Address of the object after compaction, only valid between `compute_forwarding` and
+    /// `compact` and for marked objects.
+    fn forwardee(&self, heap_ref: HeapRef) -> HeapRef {
+        // nursery objects aren't moved by a full collection
+        if heap_ref == 0 || self.nursery.contains(heap_ref) {
+            return heap_ref;
+        }
+        self.forwarding_address(heap_ref)
+    }
+
+    /// Records the new address of a moved object in its old copy, in place of the monitor id.
+    pub(super) fn set_forwarding_address(&mut self, heap_ref: HeapRef, new_ref: HeapRef) {
+        self.get_header_mut(heap_ref).monitor_id = (new_ref / FORWARDING_UNIT) as u32;
+    }
+
+    pub(super) fn forwarding_address(&self, heap_ref: HeapRef) -> HeapRef {
+        self.get_header(heap_ref).monitor_id as usize * FORWARDING_UNIT
+    }
+
+    /// Points the reference fields and elements of live objects, nursery ones included, to the
+    /// new addresses, while the objects are still in place. Dead nursery objects stay in the
+    /// heap until the next minor collection, their references are cleared instead.
+    fn update_references(&mut self, method_area: &MethodArea) -> Result<(), JvmError> {
+        let mut ref_offsets: HashMap<ClassId, Vec<usize>> = HashMap::new();
+        let (nursery_start, nursery_top) = self.nursery.objects();
+        for (start, end, young) in [
+            (ObjectHeader::SIZE, self.allocated, false),
+            (nursery_start, nursery_top, true),
+        ] {
+            let mut offset = start;
+            while offset < end {
+                let header = self.get_header(offset);
+                let (size, marked) = (header.size as usize, header.marked);
+                if marked || young {
+                    for slot in self.reference_slots(offset, method_area, &mut ref_offsets)? {
+                        if let Value::Ref(target) =
+                            self.read_field(offset, slot, AllocationType::Reference)?
+                        {
+                            let value = if marked {
+                                Value::Ref(self.forwardee(target))
+                            } else {
+                                Value::Null
+                            };
+                            self.write_field(offset, slot, value, AllocationType::Reference)?;
+                        }
+                    }
+                }
+                offset += size;
+            }
+        }
+        Ok(())
+    }
+
+    /// Slides the marked objects to their forwarding addresses, clearing the collector state
+    /// in their headers.
+    fn compact(&mut self, preserved_monitors: Vec<(HeapRef, u32)>) {
+        let mut free = ObjectHeader::SIZE;
+        let mut offset = ObjectHeader::SIZE;
+        while offset < self.allocated {
+            let header = self.get_header_mut(offset);
+            let size = header.size as usize;
+            if header.marked {
+                header.marked = false;
+                header.monitor_id = 0;
+                // the destination is never above the source, but the two may overlap
+                unsafe {
+                    std::ptr::copy(self.memory.add(offset), self.memory.add(free), size);
+                }
+                free += size;
+            }
+            offset += size;
+        }
+        self.allocated = free;
+        for (heap_ref, monitor_id) in preserved_monitors {
+            self.get_header_mut(heap_ref).monitor_id = monitor_id;
+        }
+
+        let (mut offset, nursery_top) = self.nursery.objects();
+        while offset < nursery_top {
+            let header = self.get_header_mut(offset);
+            if !header.marked {
+                // dead, but left in place until the next minor collection, its monitor is freed
+                header.monitor_id = 0;
+            }
+            header.marked = false;
+            offset += header.size as usize;
+        }
+    }
+
+    /// Points the inflated monitors to the new addresses of their objects, `forwardee` returns
+    /// `None` for a dead object. The monitors of dead objects are left to the monitor table to
+    /// free, see `take_dead_monitors`.
+    pub(super) fn sweep_monitors(&mut self, forwardee: impl Fn(&Heap, HeapRef) -> Option<HeapRef>) {
+        for index in 0..self.monitor_objects.len() {
+            let heap_ref = self.monitor_objects[index];
+            if heap_ref == 0 {
+                continue;
+            }
+            match forwardee(self, heap_ref) {
+                Some(new_ref) => self.monitor_objects[index] = new_ref,
+                None => {
+                    self.monitor_objects[index] = 0;
+                    self.dead_monitors.push(index as u32 + 1);
+                }
+            }
+        }
+    }
+
+    /// Registers the compacted old objects with the card table, the cards of those referencing
+    /// nursery objects are dirty.
+    fn rebuild_card_table(&mut self, method_area: &MethodArea) -> Result<(), JvmError> {
+        self.card_table.clear();
+        let mut ref_offsets: HashMap<ClassId, Vec<usize>> = HashMap::new();
+        let mut offset = ObjectHeader::SIZE;
+        while offset < self.allocated {
+            self.card_table.record_object(offset);
+            let slots = self.reference_slots(offset, method_area, &mut ref_offsets)?;
+            let references_young = slots.iter().any(|slot| {
+                matches!(
+                    self.read_field(offset, *slot, AllocationType::Reference),
+                    Ok(Value::Ref(target)) if self.nursery.contains(target)
+                )
+            });
+            if references_young {
+                self.card_table.dirty(offset);
+            }
+            offset += self.get_header(offset).size as usize;
+        }
+        Ok(())
+    }
+
+    /// Data offsets of the reference slots of the object: the reference fields of an instance
+    /// or the elements of an object array.
+    pub(super) fn reference_slots(
+        &self,
+        heap_ref: HeapRef,
+        method_area: &MethodArea,
+        ref_offsets: &mut HashMap<ClassId, Vec<usize>>,
+    ) -> Result<Vec<usize>, JvmError> {
+        if self.is_array(heap_ref)? {
+            if !self.is_object_array(heap_ref)? {
+                return Ok(Vec::new());
+            }
+            let element_size = AllocationType::Reference.byte_size();
+            return Ok((0..self.get_array_length(heap_ref)? as usize)
+                .map(|i| Self::ARRAY_ELEMENTS_OFFSET + i * element_size)
+                .collect());
+        }
+        let class_id = self.get_class_id(heap_ref)?;
+        Ok(ref_offsets
+            .entry(class_id)
+            .or_insert_with(|| reference_field_offsets(method_area, &class_id))
+            .clone())
+    }
+}
+
+/// Every reference held outside the heap: threads (frames, handles), classes (mirrors, statics,
+/// linked call sites), interned strings and the references pending for the Reference Handler.
+pub(super) fn visit_roots(
+    string_pool: &mut HashMap<Symbol, HeapRef>,
+    reference_pending_list: &mut HeapRef,
+    method_area: &mut MethodArea,
+    threads: &mut [&mut JavaThreadState],
+    visitor: &mut dyn FnMut(&mut HeapRef),
+) {
+    for thread in threads.iter_mut() {
+        thread.visit_refs(visitor);
+    }
+    method_area.visit_refs(visitor);
+    for string_ref in string_pool.values_mut() {
+        visitor(string_ref);
+    }
+    if *reference_pending_list != 0 {
+        visitor(reference_pending_list);
+    }
+}
+
+fn reference_field_offsets(method_area: &MethodArea, class_id: &ClassId) -> Vec<usize> {
+    method_area
+        .get_class(class_id)
+        .get_instance_fields()
+        .iter()
+        .filter(|field| {
+            method_area
+                .get_field_descriptor(&field.descriptor_id)
+                .as_allocation_type()
+                == AllocationType::Reference
+        })
+        .map(|field| field.offset)
+        .collect()
+}
@@ -0,0 +1,78 @@
+use crate::error::JvmError;
+use crate::heap::Heap;
+use crate::heap::method_area::MethodArea;
+use crate::keys::ClassId;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Instances of a class and the bytes they take on the heap, headers included.
+pub struct ClassHistogramEntry {
+    pub class_name: String,
+    pub instances: usize,
+    pub bytes: usize,
+}
+
+/// Per class heap usage, the classes taking the most bytes first. Displayed like `jmap -histo`.
+pub struct ClassHistogram {
+    pub entries: Vec<ClassHistogramEntry>,
+}
+
+impl Heap {
+    /// Walks the heap and counts the objects per class, dead ones included until the next
+    /// collection.
+    pub fn class_histogram(&self, method_area: &MethodArea) -> Result<ClassHistogram, JvmError> {
+        // object arrays keep the element class in the header, so they are told apart from the
+        // instances of that class by the flag
+        let mut counts: HashMap<(ClassId, bool), (usize, usize)> = HashMap::new();
+        for heap_ref in self.objects() {
+            let class_id = self.get_class_id(heap_ref)?;
+            let is_object_array = self.is_object_array(heap_ref)?;
+            let (instances, bytes) = counts.entry((class_id, is_object_array)).or_default();
+            *instances += 1;
+            *bytes += self.get_header(heap_ref).size as usize;
+        }
+
+        let mut entries = counts
+            .into_iter()
+            .map(|((class_id, is_object_array), (instances, bytes))| {
+                let class_name = if is_object_array {
+                    method_area.get_object_array_class_name(class_id)
+                } else {
+                    let name = method_area.get_class(&class_id).get_name();
+                    method_area.interner().resolve(&name).to_string()
+                };
+                ClassHistogramEntry {
+                    class_name: class_name.replace('/', "."),
+                    instances,
+                    bytes,
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then_with(|| a.class_name.cmp(&b.class_name))
+        });
+        Ok(ClassHistogram { entries })
+    }
+}
+
+impl Display for ClassHistogram {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, " num     #instances         #bytes  class name")?;
+        writeln!(f, "----------------------------------------------")?;
+        for (index, entry) in self.entries.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4}: {:>13} {:>14}  {}",
+                index + 1,
+                entry.instances,
+                entry.bytes,
+                entry.class_name
+            )?;
+        }
+        let instances: usize = self.entries.iter().map(|entry| entry.instances).sum();
+        let bytes: usize = self.entries.iter().map(|entry| entry.bytes).sum();
+        writeln!(f, "Total{:>14} {:>14}", instances, bytes)
+    }
+}
@@ -0,0 +1,454 @@
+use crate::build_exception;
+use crate::error::JvmError;
+use crate::heap::gc::visit_roots;
+use crate::heap::method_area::MethodArea;
+use crate::heap::{Heap, HeapRef};
+use crate::keys::{ClassId, Symbol, ThreadId};
+use crate::rt::JvmClass;
+use crate::thread::JavaThreadState;
+use crate::vm::Value;
+use lagertha_common::jtype::AllocationType;
+use lasso::Key;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HPROF_HEADER: &[u8] = b"JAVA PROFILE 1.0.2\0";
+const ID_SIZE: usize = 8;
+
+const TAG_STRING: u8 = 0x01;
+const TAG_LOAD_CLASS: u8 = 0x02;
+const TAG_STACK_TRACE: u8 = 0x05;
+const TAG_HEAP_DUMP_SEGMENT: u8 = 0x1C;
+const TAG_HEAP_DUMP_END: u8 = 0x2C;
+
+// sub-records of a heap dump segment
+const ROOT_UNKNOWN: u8 = 0xFF;
+const ROOT_STICKY_CLASS: u8 = 0x05;
+const CLASS_DUMP: u8 = 0x20;
+const INSTANCE_DUMP: u8 = 0x21;
+const OBJ_ARRAY_DUMP: u8 = 0x22;
+const PRIM_ARRAY_DUMP: u8 = 0x23;
+
+// allocation sites aren't tracked, every object refers to the same empty stack trace
+const STACK_TRACE_SERIAL: u32 = 1;
+// a heap dump segment is written out once it grows past this size
+const SEGMENT_SIZE: usize = 1024 * 1024;
+
+impl Heap {
+    /// Writes the objects and the loaded classes in the HPROF 1.0.2 format of hotspot's heap
+    /// dumps, for Eclipse MAT, VisualVM and the like. Must run at a safepoint, like a collection,
+    /// the dump holds dead objects too unless one just ran. Returns the size of the file.
+    pub fn dump(
+        &mut self,
+        method_area: &mut MethodArea,
+        threads: &mut [&mut JavaThreadState],
+        thread_id: ThreadId,
+        path: &Path,
+    ) -> Result<u64, JvmError> {
+        // object arrays keep the element class in the header, but are dumped with the array
+        // class, which must be loaded to get a class dump
+        let mut array_class_ids = HashMap::new();
+        for heap_ref in self.objects() {
+            if !self.is_object_array(heap_ref)? {
+                continue;
+            }
+            let element_class_id = self.get_class_id(heap_ref)?;
+            if let Entry::Vacant(entry) = array_class_ids.entry(element_class_id) {
+                entry.insert(method_area.get_object_array_class_id(element_class_id, thread_id)?);
+            }
+        }
+
+        let mut roots = Vec::new();
+        visit_roots(
+            &mut self.string_pool,
+            &mut self.reference_pending_list,
+            method_area,
+            threads,
+            &mut |heap_ref| roots.push(*heap_ref),
+        );
+        roots.sort_unstable();
+        roots.dedup();
+
+        let file = File::create_new(path).map_err(io_error)?;
+        let mut dumper = HprofDumper {
+            heap: self,
+            method_area,
+            out: BufWriter::new(file),
+            array_class_ids,
+            segment: Vec::new(),
+            strings: HashSet::new(),
+        };
+        dumper.write_header()?;
+        dumper.write_classes()?;
+        dumper.write_heap(&roots)?;
+        let file = dumper
+            .out
+            .into_inner()
+            .map_err(|e| io_error(e.into_error()))?;
+        Ok(file.metadata().map_err(io_error)?.len())
+    }
+}
+
+struct HprofDumper<'a> {
+    heap: &'a Heap,
+    method_area: &'a MethodArea,
+    out: BufWriter<File>,
+    // array class of the element class in the header of object arrays
+    array_class_ids: HashMap<ClassId, ClassId>,
+    // sub-records not written out yet
+    segment: Vec<u8>,
+    // symbols already written as string records
+    strings: HashSet<Symbol>,
+}
+
+impl HprofDumper<'_> {
+    fn write_header(&mut self) -> Result<(), JvmError> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as u64);
+        let mut header = HPROF_HEADER.to_vec();
+        header.put_u4(ID_SIZE as u32);
+        header.put_u8(millis);
+        self.out.write_all(&header).map_err(io_error)?;
+
+        let mut stack_trace = Vec::new();
+        stack_trace.put_u4(STACK_TRACE_SERIAL);
+        stack_trace.put_u4(0); // thread serial
+        stack_trace.put_u4(0); // frames
+        self.write_record(TAG_STACK_TRACE, &stack_trace)
+    }
+
+    /// Names of the classes and their fields, then a load class record per class.
+    fn write_classes(&mut self) -> Result<(), JvmError> {
+        let method_area = self.method_area;
+        for (index, class) in method_area.classes().iter().enumerate() {
+            if class.is_primitive() {
+                continue;
+            }
+            let class_id = ClassId::from_usize(index + 1);
+            self.write_string(class.get_name())?;
+            for (name, _) in self.declared_fields(class_id) {
+                self.write_string(name)?;
+            }
+            for (name, _, _) in self.static_fields(class) {
+                self.write_string(name)?;
+            }
+
+            let mut load_class = Vec::new();
+            load_class.put_u4(index as u32 + 1); // class serial
+            load_class.put_id(self.class_object_id(class_id));
+            load_class.put_u4(STACK_TRACE_SERIAL);
+            load_class.put_id(symbol_id(class.get_name()));
+            self.write_record(TAG_LOAD_CLASS, &load_class)?;
+        }
+        Ok(())
+    }
+
+    fn write_heap(&mut self, roots: &[HeapRef]) -> Result<(), JvmError> {
+        for &root in roots.iter().filter(|root| **root != 0) {
+            self.segment.put_u1(ROOT_UNKNOWN);
+            self.segment.put_id(root as u64);
+        }
+        let (heap, method_area) = (self.heap, self.method_area);
+        for (index, class) in method_area.classes().iter().enumerate() {
+            if class.is_primitive() {
+                continue;
+            }
+            let class_id = ClassId::from_usize(index + 1);
+            self.segment.put_u1(ROOT_STICKY_CLASS);
+            self.segment.put_id(self.class_object_id(class_id));
+            self.write_class_dump(class_id, class);
+            self.end_sub_record()?;
+        }
+
+        let class_class_id = method_area.br().get_java_lang_class_id()?;
+        for heap_ref in heap.objects() {
+            let class_id = heap.get_class_id(heap_ref)?;
+            if heap.is_array(heap_ref)? {
+                self.write_array_dump(heap_ref, class_id)?;
+            } else if class_id != class_class_id || !self.is_dumped_mirror(heap_ref) {
+                self.write_instance_dump(heap_ref, class_id)?;
+            }
+            self.end_sub_record()?;
+        }
+
+        self.flush_segment()?;
+        self.write_record(TAG_HEAP_DUMP_END, &[])?;
+        self.out.flush().map_err(io_error)
+    }
+
+    fn write_class_dump(&mut self, class_id: ClassId, class: &JvmClass) {
+        let declared_fields = self.declared_fields(class_id);
+        let static_fields = self.static_fields(class);
+        let instance_size = self
+            .method_area
+            .get_instance_class(&class_id)
+            .and_then(|class| class.get_instance_size())
+            .unwrap_or(0);
+        let super_id = class
+            .get_super_id()
+            .map_or(0, |super_id| self.class_object_id(super_id));
+        let class_object_id = self.class_object_id(class_id);
+
+        let segment = &mut self.segment;
+        segment.put_u1(CLASS_DUMP);
+        segment.put_id(class_object_id);
+        segment.put_u4(STACK_TRACE_SERIAL);
+        segment.put_id(super_id);
+        // class loader, signers, protection domain and two reserved ids
+        // TODO: the class loader once classes can be defined by user class loaders
+        for _ in 0..5 {
+            segment.put_id(0);
+        }
+        segment.put_u4(instance_size as u32);
+        segment.put_u2(0); // constant pool entries
+        segment.put_u2(static_fields.len() as u16);
+        for (name, field_type, value) in static_fields {
+            segment.put_id(symbol_id(name));
+            segment.put_u1(basic_type(field_type));
+            segment.put_value(value, field_type);
+        }
+        segment.put_u2(declared_fields.len() as u16);
+        for (name, (field_type, _)) in declared_fields {
+            segment.put_id(symbol_id(name));
+            segment.put_u1(basic_type(field_type));
+        }
+    }
+
+    /// The field values of the class come first, then those of each superclass in turn, in the
+    /// order of their class dumps.
+    fn write_instance_dump(
+        &mut self,
+        heap_ref: HeapRef,
+        class_id: ClassId,
+    ) -> Result<(), JvmError> {
+        let mut values = Vec::new();
+        let mut current = Some(class_id);
+        while let Some(current_id) = current {
+            for (_, (field_type, offset)) in self.declared_fields(current_id) {
+                let value = self.heap.read_field(heap_ref, offset, field_type)?;
+                values.put_value(value, field_type);
+            }
+            current = self.method_area.get_class(&current_id).get_super_id();
+        }
+
+        self.segment.put_u1(INSTANCE_DUMP);
+        self.segment.put_id(heap_ref as u64);
+        self.segment.put_u4(STACK_TRACE_SERIAL);
+        self.segment.put_id(self.class_object_id(class_id));
+        self.segment.put_u4(values.len() as u32);
+        self.segment.extend_from_slice(&values);
+        Ok(())
+    }
+
+    fn write_array_dump(&mut self, heap_ref: HeapRef, class_id: ClassId) -> Result<(), JvmError> {
+        let length = self.heap.get_array_length(heap_ref)?;
+        let element_type = self.heap.get_allocation_type(heap_ref)?;
+        if element_type == AllocationType::Reference {
+            self.segment.put_u1(OBJ_ARRAY_DUMP);
+            self.segment.put_id(heap_ref as u64);
+            self.segment.put_u4(STACK_TRACE_SERIAL);
+            self.segment.put_u4(length as u32);
+            self.segment
+                .put_id(self.class_object_id(self.array_class_ids[&class_id]));
+            for index in 0..length {
+                let element = self.heap.read_array_element(heap_ref, index)?;
+                self.segment.put_value(element, AllocationType::Reference);
+            }
+        } else {
+            self.segment.put_u1(PRIM_ARRAY_DUMP);
+            self.segment.put_id(heap_ref as u64);
+            self.segment.put_u4(STACK_TRACE_SERIAL);
+            self.segment.put_u4(length as u32);
+            self.segment.put_u1(basic_type(element_type));
+            // the heap keeps the elements in native byte order, hprof wants big endian
+            for element in self
+                .heap
+                .get_array_bytes(heap_ref)?
+                .chunks(element_type.byte_size())
+            {
+                if cfg!(target_endian = "little") {
+                    self.segment.extend(element.iter().rev());
+                } else {
+                    self.segment.extend_from_slice(element);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Instance fields declared by the class itself, not inherited, with their type and offset.
+    fn declared_fields(&self, class_id: ClassId) -> Vec<(Symbol, (AllocationType, usize))> {
+        let Ok(class) = self.method_area.get_instance_class(&class_id) else {
+            return Vec::new();
+        };
+        let (Some(fields), Some(positions)) = (
+            class.instance_fields.get(),
+            class.instance_fields_offset_map.get(),
+        ) else {
+            return Vec::new();
+        };
+        let mut declared_fields = positions
+            .iter()
+            .map(|(field_key, position)| (field_key.name, &fields[*position]))
+            .filter(|(_, field)| field.declaring_class == class_id)
+            .map(|(name, field)| {
+                let field_type = self
+                    .method_area
+                    .get_field_descriptor(&field.descriptor_id)
+                    .as_allocation_type();
+                (name, (field_type, field.offset))
+            })
+            .collect::<Vec<_>>();
+        declared_fields.sort_by_key(|(_, (_, offset))| *offset);
+        declared_fields
+    }
+
+    fn static_fields(&self, class: &JvmClass) -> Vec<(Symbol, AllocationType, Value)> {
+        let Ok(static_fields) = class
+            .as_class_like()
+            .and_then(|class| class.get_static_fields())
+        else {
+            return Vec::new();
+        };
+        static_fields
+            .iter()
+            .map(|(field_key, field)| {
+                let field_type = self
+                    .method_area
+                    .get_field_descriptor(&field.descriptor)
+                    .as_allocation_type();
+                (field_key.name, field_type, *field.value.read().unwrap())
+            })
+            .collect()
+    }
+
+    /// The class mirror stands for the class, classes without one yet get an id past the end of
+    /// the heap.
+    fn class_object_id(&self, class_id: ClassId) -> u64 {
+        match self.method_area.get_class(&class_id).get_mirror_ref() {
+            Some(mirror_ref) => mirror_ref as u64,
+            None => (self.heap.max_memory() + (class_id.to_index() + 1) * ID_SIZE) as u64,
+        }
+    }
+
+    /// Mirrors of the dumped classes are written as class dumps, not as instances.
+    fn is_dumped_mirror(&self, heap_ref: HeapRef) -> bool {
+        self.method_area
+            .get_class_id_by_mirror(&heap_ref)
+            .is_ok_and(|class_id| !self.method_area.get_class(&class_id).is_primitive())
+    }
+
+    fn write_string(&mut self, symbol: Symbol) -> Result<(), JvmError> {
+        if !self.strings.insert(symbol) {
+            return Ok(());
+        }
+        let mut record = Vec::new();
+        record.put_id(symbol_id(symbol));
+        record.extend_from_slice(self.method_area.interner().resolve(&symbol).as_bytes());
+        self.write_record(TAG_STRING, &record)
+    }
+
+    fn end_sub_record(&mut self) -> Result<(), JvmError> {
+        if self.segment.len() >= SEGMENT_SIZE {
+            self.flush_segment()?;
+        }
+        Ok(())
+    }
+
+    fn flush_segment(&mut self) -> Result<(), JvmError> {
+        if self.segment.is_empty() {
+            return Ok(());
+        }
+        let segment = std::mem::take(&mut self.segment);
+        self.write_record(TAG_HEAP_DUMP_SEGMENT, &segment)
+    }
+
+    fn write_record(&mut self, tag: u8, body: &[u8]) -> Result<(), JvmError> {
+        let mut header = Vec::with_capacity(9);
+        header.put_u1(tag);
+        header.put_u4(0); // microseconds since the header timestamp
+        header.put_u4(body.len() as u32);
+        self.out.write_all(&header).map_err(io_error)?;
+        self.out.write_all(body).map_err(io_error)
+    }
+}
+
+/// Big endian writes of hprof values.
+trait HprofBuffer {
+    fn put_u1(&mut self, value: u8);
+    fn put_u2(&mut self, value: u16);
+    fn put_u4(&mut self, value: u32);
+    fn put_u8(&mut self, value: u64);
+
+    fn put_id(&mut self, id: u64) {
+        self.put_u8(id);
+    }
+
+    fn put_value(&mut self, value: Value, value_type: AllocationType) {
+        match (value_type, value) {
+            (AllocationType::Reference, Value::Ref(heap_ref)) => self.put_id(heap_ref as u64),
+            (AllocationType::Reference, _) => self.put_id(0),
+            (AllocationType::Boolean | AllocationType::Byte, Value::Integer(v)) => {
+                self.put_u1(v as u8)
+            }
+            (AllocationType::Char | AllocationType::Short, Value::Integer(v)) => {
+                self.put_u2(v as u16)
+            }
+            (AllocationType::Int, Value::Integer(v)) => self.put_u4(v as u32),
+            (AllocationType::Long, Value::Long(v)) => self.put_u8(v as u64),
+            (AllocationType::Float, Value::Float(v)) => self.put_u4(v.to_bits()),
+            (AllocationType::Double, Value::Double(v)) => self.put_u8(v.to_bits()),
+            // a value that doesn't match its type is written as zero
+            (value_type, _) => {
+                for _ in 0..value_type.byte_size() {
+                    self.put_u1(0);
+                }
+            }
+        }
+    }
+}
+
+impl HprofBuffer for Vec<u8> {
+    fn put_u1(&mut self, value: u8) {
+        self.push(value);
+    }
+
+    fn put_u2(&mut self, value: u16) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_u4(&mut self, value: u32) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn put_u8(&mut self, value: u64) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn basic_type(allocation_type: AllocationType) -> u8 {
+    match allocation_type {
+        AllocationType::Reference => 2,
+        AllocationType::Boolean => 4,
+        AllocationType::Char => 5,
+        AllocationType::Float => 6,
+        AllocationType::Double => 7,
+        AllocationType::Byte => 8,
+        AllocationType::Short => 9,
+        AllocationType::Int => 10,
+        AllocationType::Long => 11,
+    }
+}
+
+fn symbol_id(symbol: Symbol) -> u64 {
+    symbol.into_usize() as u64 + 1
+}
+
+fn io_error(e: std::io::Error) -> JvmError {
+    build_exception!(IOException, e.to_string())
+}
@@ -3,26 +3,27 @@ use crate::error::JvmError;
 use crate::heap::{Heap, HeapRef};
 use crate::jdwp::{ClassPrepareInfo, ClassStatus, DebugEvent, DebugState, TypeTag};
 use crate::keys::{
-    ClassId, FieldDescriptorId, FieldKey, FullyQualifiedMethodKey, MethodDescriptorId, MethodKey,
-    ThreadId,
+    ClassId, ClassLoaderId, FieldDescriptorId, FieldKey, FullyQualifiedMethodKey,
+    MethodDescriptorId, MethodKey, ThreadId,
 };
 use crate::rt::array::{ObjectArrayClass, PrimitiveArrayClass};
 use crate::rt::class::InstanceClass;
 use crate::rt::constant_pool::RuntimeConstantPool;
-use crate::rt::field::InstanceField;
+use crate::rt::field::{InstanceField, StaticField};
 use crate::rt::interface::InterfaceClass;
 use crate::rt::method::Method;
 use crate::rt::{ClassLike, JvmClass, PrimitiveClass};
 use crate::vm::Value;
 use crate::vm::bootstrap_registry::BootstrapRegistry;
 use crate::{MethodId, Symbol, VmConfig, debug_log, throw_exception};
+use lagertha_classfile::ClassFile;
+use lagertha_classfile::constant::ConstantInfo;
 use lagertha_common::descriptor::MethodDescriptor;
 use lagertha_common::error::{LinkageError, MethodDescriptorErr};
 use lagertha_common::jtype::{AllocationType, JavaType, PrimitiveType};
-use lagertha_classfile::ClassFile;
 use lasso::{Spur, ThreadedRodeo};
 use once_cell::sync::OnceCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 
 pub struct MethodArea {
@@ -30,6 +31,20 @@ pub struct MethodArea {
     bootstrap_class_loader: ClassLoader,
     class_name_to_index: HashMap<Spur, ClassId>,
     mirror_to_class_index: HashMap<HeapRef, ClassId>,
+
+    // user-defined loaders are never unloaded, a class is identified by its defining loader and
+    // name, the index also has the classes a loader initiated the loading of (JVMS 5.3)
+    class_loaders: Vec<HeapRef>,
+    class_loader_index: HashMap<HeapRef, ClassLoaderId>,
+    loader_class_index: HashMap<(ClassLoaderId, Symbol), ClassId>,
+    defining_loaders: HashMap<ClassId, ClassLoaderId>,
+    loader_constraints: HashMap<Symbol, Vec<(Option<ClassLoaderId>, Option<ClassLoaderId>)>>,
+    // descriptors whose loader constraints are already added for the pair of loaders
+    constrained_descriptors: HashSet<(Option<ClassLoaderId>, Option<ClassLoaderId>, Symbol)>,
+    // the loaders of the platform modules of the image and of the classpath classes, the VM
+    // loads their classes itself instead of calling loadClass
+    platform_class_loader: Option<ClassLoaderId>,
+    app_class_loader: Option<ClassLoaderId>,
     classes: Vec<JvmClass>,
     methods: Vec<Method>,
 
@@ -58,6 +73,14 @@ impl MethodArea {
             bootstrap_class_loader,
             class_name_to_index: HashMap::new(),
             mirror_to_class_index: HashMap::new(),
+            class_loaders: Vec::new(),
+            class_loader_index: HashMap::new(),
+            loader_class_index: HashMap::new(),
+            defining_loaders: HashMap::new(),
+            loader_constraints: HashMap::new(),
+            constrained_descriptors: HashSet::new(),
+            platform_class_loader: None,
+            app_class_loader: None,
             classes: Vec::with_capacity(1024),
             methods: Vec::with_capacity(16384),
             field_descriptors: Vec::with_capacity(2048),
@@ -121,6 +144,10 @@ impl MethodArea {
             let class_id = self.push_class(primitive_class);
             self.class_name_to_index.insert(name_sym, class_id);
         }
+        let void_sym = self.br().void_sym;
+        let void_class_id =
+            self.push_class(JvmClass::Primitive(PrimitiveClass::new_void(void_sym)));
+        self.class_name_to_index.insert(void_sym, void_class_id);
 
         Ok(())
     }
@@ -214,6 +241,10 @@ impl MethodArea {
         &self.classes[class_id.to_index()]
     }
 
+    pub fn contains_class(&self, class_id: &ClassId) -> bool {
+        class_id.to_index() < self.classes.len()
+    }
+
     pub fn is_instance_class(&self, class_id: &ClassId) -> bool {
         matches!(self.get_class(class_id), JvmClass::Instance(_))
     }
@@ -289,6 +320,35 @@ impl MethodArea {
         self.get_class(class_id).as_class_like()
     }
 
+    /// The static field as resolved from the class, with the class declaring it: a superclass or
+    /// a superinterface may.
+    pub fn resolve_static_field(
+        &self,
+        class_id: ClassId,
+        field_key: &FieldKey,
+    ) -> Result<(ClassId, &StaticField), JvmError> {
+        let holder_id = self.resolve_static_field_actual_class_id(class_id, field_key)?;
+        let field = self
+            .get_class_like(&holder_id)?
+            .get_static_fields()?
+            .get(field_key)
+            .ok_or(JvmError::Todo("No such field".to_string()))?;
+        Ok((holder_id, field))
+    }
+
+    /// The static field of the class in the given slot, see `StaticField::unsafe_offset`.
+    pub fn get_static_field_by_slot(
+        &self,
+        class_id: &ClassId,
+        slot: usize,
+    ) -> Result<&StaticField, JvmError> {
+        self.get_class_like(class_id)?
+            .get_static_fields()?
+            .values()
+            .find(|field| field.slot == slot)
+            .ok_or(JvmError::Todo(format!("No static field in slot {}", slot)))
+    }
+
     pub fn get_cp(&self, class_id: &ClassId) -> Result<&RuntimeConstantPool, JvmError> {
         self.get_class(class_id).get_cp()
     }
@@ -361,11 +421,44 @@ impl MethodArea {
         name_sym: Symbol,
         thread_id: ThreadId,
     ) -> Result<ClassId, JvmError> {
-        if let Some(class_id) = self.class_name_to_index.get(&name_sym) {
-            return Ok(*class_id);
+        self.load_array_class_with(None, name_sym, thread_id)
+    }
+
+    /// Array classes are defined by the loader of their element class (JVMS 5.3.3), the
+    /// requesting loader is recorded as initiating loader.
+    pub(crate) fn load_array_class_with(
+        &mut self,
+        loader: Option<ClassLoaderId>,
+        name_sym: Symbol,
+        thread_id: ThreadId,
+    ) -> Result<ClassId, JvmError> {
+        if let Some(class_id) = self.find_loaded_class(loader, name_sym) {
+            return Ok(class_id);
         }
         let type_descriptor_id = self.get_or_new_field_descriptor_id(name_sym)?;
         let type_descriptor = self.get_field_descriptor(&type_descriptor_id);
+        let primitive_type = type_descriptor.get_primitive_array_element_type();
+        let element_sym = type_descriptor
+            .get_instance_array_element_type()
+            .map(|instance_type| self.interner.get_or_intern(instance_type));
+        let element_class_id = match element_sym {
+            Some(element_sym) => {
+                Some(self.get_class_id_or_load_with(loader, element_sym, thread_id)?)
+            }
+            None => None,
+        };
+        let defining_loader =
+            element_class_id.and_then(|class_id| self.get_class_loader_id(&class_id));
+        let defined = if defining_loader != loader {
+            self.find_loaded_class(defining_loader, name_sym)
+        } else {
+            None
+        };
+        if let Some(class_id) = defined {
+            self.record_loaded_class(loader, name_sym, class_id)?;
+            return Ok(class_id);
+        }
+
         let obj_class_id = self.br().get_java_lang_object_id()?;
         let vtable = self
             .get_instance_class(&obj_class_id)?
@@ -376,33 +469,35 @@ impl MethodArea {
             .get_vtable_index()?
             .clone();
 
-        let class = if let Some(primitive_type) = type_descriptor.get_primitive_array_element_type()
-        {
-            JvmClass::PrimitiveArray(PrimitiveArrayClass {
+        let class = match (primitive_type, element_class_id) {
+            (Some(primitive_type), _) => JvmClass::PrimitiveArray(PrimitiveArrayClass {
                 name: name_sym,
-                super_id: self.br().get_java_lang_object_id()?,
+                super_id: obj_class_id,
                 element_type: primitive_type,
                 vtable,
                 vtable_index,
                 mirror_ref: OnceCell::new(),
-            })
-        } else if let Some(instance_type) = type_descriptor.get_instance_array_element_type() {
-            JvmClass::InstanceArray(ObjectArrayClass {
+            }),
+            (None, Some(element_class_id)) => JvmClass::InstanceArray(ObjectArrayClass {
                 name: name_sym,
-                super_id: self.br().get_java_lang_object_id()?,
-                element_class_id: self
-                    .get_class_id_or_load(self.interner.get_or_intern(instance_type), thread_id)?,
+                super_id: obj_class_id,
+                element_class_id,
                 vtable,
                 vtable_index,
                 mirror_ref: OnceCell::new(),
-            })
-        } else {
-            Err(JvmError::Todo(
+            }),
+            (None, None) => Err(JvmError::Todo(
                 "Array class with non-array or non-primitive type descriptor".to_string(),
-            ))?
+            ))?,
         };
         let class_id = self.push_class(class);
-        self.class_name_to_index.insert(name_sym, class_id);
+        if let Some(loader_id) = defining_loader {
+            self.defining_loaders.insert(class_id, loader_id);
+        }
+        self.record_loaded_class(defining_loader, name_sym, class_id)?;
+        if defining_loader != loader {
+            self.record_loaded_class(loader, name_sym, class_id)?;
+        }
         Ok(class_id)
     }
 
@@ -439,6 +534,18 @@ impl MethodArea {
             }
         }
 
+        if this.is_array() {
+            // JLS 10.8: besides Object, arrays only implement Cloneable and Serializable
+            let target_name = target.get_name();
+            return this.get_super_id() == Some(target_class)
+                || target_name == self.br().java_lang_cloneable_sym
+                || target_name == self.br().java_io_serializable_sym;
+        }
+
+        if this.is_primitive() {
+            return false;
+        }
+
         if let Some(super_id) = this.get_super_id() {
             if self.is_subclass_of(super_id, target_class) {
                 return true;
@@ -454,6 +561,31 @@ impl MethodArea {
         false
     }
 
+    /// Object arrays keep the element class in the heap header, this gives the array class itself.
+    pub fn get_object_array_class_id(
+        &mut self,
+        element_class_id: ClassId,
+        thread_id: ThreadId,
+    ) -> Result<ClassId, JvmError> {
+        let array_name_sym = self
+            .interner
+            .get_or_intern(self.get_object_array_class_name(element_class_id));
+        let loader = self.get_class_loader_id(&element_class_id);
+        self.load_array_class_with(loader, array_name_sym, thread_id)
+    }
+
+    /// Name of the array class with the given element class, without loading it.
+    pub fn get_object_array_class_name(&self, element_class_id: ClassId) -> String {
+        let element_name = self
+            .interner
+            .resolve(&self.get_class(&element_class_id).get_name());
+        if element_name.starts_with('[') {
+            format!("[{}", element_name)
+        } else {
+            format!("[L{};", element_name)
+        }
+    }
+
     //TODO: probably need try to load?
     pub fn instance_of(&self, this_class_id: ClassId, other_sym: Symbol) -> bool {
         if let Some(&other_class_id) = self.class_name_to_index.get(&other_sym) {
@@ -478,22 +610,125 @@ impl MethodArea {
             "load_class::parse_class_file",
             ClassFile::try_from(data).map_err(LinkageError::from)?
         );
+        let class_id = self.link_class_file(cf, None, thread_id)?;
+        self.record_loaded_class(None, name_sym, class_id)?;
+        Ok(class_id)
+    }
+
+    /// Bytecode of a class of the image the boot loader doesn't define, for the platform loader.
+    pub fn load_from_platform_modules(&self, name: &str) -> Result<Vec<u8>, JvmError> {
+        self.bootstrap_class_loader.load_from_platform_modules(name)
+    }
+
+    /// Bytecode of a classpath class, for the app loader.
+    pub fn load_from_class_path(&self, name: &str) -> Result<Vec<u8>, JvmError> {
+        self.bootstrap_class_loader.load_from_class_path(name)
+    }
+
+    /// Parses a class file given to `ClassLoader.defineClass0` and friends.
+    pub fn parse_class_file(data: Vec<u8>) -> Result<ClassFile, JvmError> {
+        ClassFile::try_from(data).map_err(|err| JvmError::from(LinkageError::from(err)))
+    }
+
+    /// Internal names of the superclass and the superinterfaces, a user-defined loader has to
+    /// load them before the class can be defined.
+    pub fn get_super_type_names(cf: &ClassFile) -> Result<Vec<String>, JvmError> {
+        let mut names = Vec::with_capacity(cf.interfaces.len() + 1);
+        if let Some(super_name) = cf.get_super_class_name() {
+            names.push(Self::super_class_name(super_name)?.to_string());
+        }
+        for interface in &cf.interfaces {
+            names.push(Self::get_class_file_class_name(cf, *interface)?.to_string());
+        }
+        Ok(names)
+    }
+
+    /// A super_class index that isn't a class constant is a format error of the class file, not
+    /// a bug of the VM.
+    fn super_class_name<T, E>(super_name: Result<T, E>) -> Result<T, JvmError> {
+        super_name.or_else(|_| throw_exception!(ClassFormatError, "Invalid super_class index"))
+    }
+
+    pub fn get_class_file_name(cf: &ClassFile) -> Result<&str, JvmError> {
+        Self::get_class_file_class_name(cf, cf.this_class)
+    }
+
+    fn get_class_file_class_name(cf: &ClassFile, class_idx: u16) -> Result<&str, JvmError> {
+        let name_idx = match cf.cp.inner.get(class_idx as usize) {
+            Some(ConstantInfo::Class(name_idx)) => *name_idx as usize,
+            _ => throw_exception!(ClassFormatError, "Invalid class index: {}", class_idx)?,
+        };
+        match cf.cp.inner.get(name_idx) {
+            Some(ConstantInfo::Utf8(name)) => Ok(name.as_str()),
+            _ => throw_exception!(ClassFormatError, "Invalid class name index: {}", name_idx),
+        }
+    }
+
+    /// Defines a class for the given loader, `None` being the bootstrap loader.
+    /// Hidden classes aren't registered by name and get a `/0x<id>` suffix, like in hotspot,
+    /// so they never clash with the class they were spun from.
+    pub fn define_class(
+        &mut self,
+        mut cf: ClassFile,
+        loader: Option<ClassLoaderId>,
+        is_hidden: bool,
+        thread_id: ThreadId,
+    ) -> Result<ClassId, JvmError> {
+        let name_idx = match cf.cp.inner.get(cf.this_class as usize) {
+            Some(ConstantInfo::Class(name_idx)) => *name_idx as usize,
+            _ => throw_exception!(
+                ClassFormatError,
+                "Invalid this_class index: {}",
+                cf.this_class
+            )?,
+        };
+        let Some(ConstantInfo::Utf8(name)) = cf.cp.inner.get_mut(name_idx) else {
+            return throw_exception!(ClassFormatError, "Invalid class name index: {}", name_idx);
+        };
+        if is_hidden {
+            name.push_str(&format!("/0x{:016x}", self.classes.len() + 1));
+        }
+        let name_sym = self.interner.get_or_intern(name.as_str());
+        if self.find_loaded_class(loader, name_sym).is_some() {
+            throw_exception!(
+                LinkageError,
+                "attempted duplicate class definition for {}",
+                self.interner.resolve(&name_sym).replace('/', ".")
+            )?
+        }
+        let class_id = self.link_class_file(cf, loader, thread_id)?;
+        if !is_hidden {
+            self.record_loaded_class(loader, name_sym, class_id)?;
+            // the classpath classes are defined here, by the app loader
+            self.send_class_prepare_events(name_sym, class_id, thread_id);
+        }
+        Ok(class_id)
+    }
+
+    fn link_class_file(
+        &mut self,
+        cf: ClassFile,
+        loader: Option<ClassLoaderId>,
+        thread_id: ThreadId,
+    ) -> Result<ClassId, JvmError> {
         let super_id = match cf.get_super_class_name() {
             Some(super_name) => {
-                let super_name = super_name.unwrap();
+                let super_name = Self::super_class_name(super_name)?;
                 let super_name_sym = self.interner.get_or_intern(super_name);
-                Some(self.get_class_id_or_load(super_name_sym, thread_id)?)
+                Some(self.get_class_id_or_load_with(loader, super_name_sym, thread_id)?)
             }
             None => None,
         };
         let class_id = hotpath::measure_block!("load_class::load_and_link_class", {
             if cf.access_flags.is_interface() {
-                InterfaceClass::load_and_link(cf, self, super_id, thread_id)?
+                InterfaceClass::load_and_link(cf, self, super_id, loader, thread_id)?
             } else {
-                InstanceClass::load_and_link(cf, self, super_id, thread_id)?
+                InstanceClass::load_and_link(cf, self, super_id, loader, thread_id)?
             }
         });
-        self.class_name_to_index.insert(name_sym, class_id);
+        if let Some(loader_id) = loader {
+            self.defining_loaders.insert(class_id, loader_id);
+        }
         Ok(class_id)
     }
 
@@ -523,6 +758,11 @@ impl MethodArea {
             }
         });
         let class_id = self.load_class(name_sym, thread_id)?;
+        self.send_class_prepare_events(name_sym, class_id, thread_id);
+        Ok(class_id)
+    }
+
+    fn send_class_prepare_events(&self, name_sym: Symbol, class_id: ClassId, thread_id: ThreadId) {
         if self.debug_state.should_check() {
             let name_str = self.interner.resolve(&name_sym);
             if let Some(matched) = self.debug_state.matches_class_prepare(name_str) {
@@ -540,7 +780,235 @@ impl MethodArea {
                 }
             }
         }
-        Ok(class_id)
+    }
+
+    /// Like `get_class_id_or_load`, but for the classes seen by the given loader. A user-defined
+    /// loader only sees the classes it already defined or initiated the loading of, the VM asks
+    /// the loader for the others beforehand, only array classes are created here.
+    pub fn get_class_id_or_load_with(
+        &mut self,
+        loader: Option<ClassLoaderId>,
+        name_sym: Symbol,
+        thread_id: ThreadId,
+    ) -> Result<ClassId, JvmError> {
+        if loader.is_none() {
+            return self.get_class_id_or_load(name_sym, thread_id);
+        }
+        if let Some(class_id) = self.find_loaded_class(loader, name_sym) {
+            return Ok(class_id);
+        }
+        if self.interner.resolve(&name_sym).starts_with('[') {
+            return self.load_array_class_with(loader, name_sym, thread_id);
+        }
+        throw_exception!(NoClassDefFoundError, self.interner.resolve(&name_sym))
+    }
+
+    /// Id of a user-defined loader, assigned when it first defines or initiates the loading of
+    /// a class.
+    pub fn get_or_register_class_loader(&mut self, loader_ref: HeapRef) -> ClassLoaderId {
+        if let Some(loader_id) = self.class_loader_index.get(&loader_ref) {
+            return *loader_id;
+        }
+        self.class_loaders.push(loader_ref);
+        let loader_id = ClassLoaderId::from_usize(self.class_loaders.len());
+        self.class_loader_index.insert(loader_ref, loader_id);
+        loader_id
+    }
+
+    /// Registers `ClassLoader.getPlatformClassLoader()` as the loader of the classes of the image
+    /// outside the boot modules.
+    pub fn set_platform_class_loader(&mut self, loader_ref: HeapRef) {
+        self.platform_class_loader = Some(self.get_or_register_class_loader(loader_ref));
+    }
+
+    pub fn get_platform_class_loader_id(&self) -> Option<ClassLoaderId> {
+        self.platform_class_loader
+    }
+
+    /// Registers `ClassLoader.getSystemClassLoader()` as the loader of the classpath classes.
+    pub fn set_app_class_loader(&mut self, loader_ref: HeapRef) {
+        self.app_class_loader = Some(self.get_or_register_class_loader(loader_ref));
+    }
+
+    pub fn get_app_class_loader_id(&self) -> Option<ClassLoaderId> {
+        self.app_class_loader
+    }
+
+    /// `Some(true)` for the app loader, `Some(false)` for the platform loader, `None` for the
+    /// loaders whose `loadClass` is run.
+    pub fn is_built_in_class_loader(&self, loader_id: ClassLoaderId) -> Option<bool> {
+        let loader = Some(loader_id);
+        if loader == self.app_class_loader {
+            Some(true)
+        } else if loader == self.platform_class_loader {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    pub fn find_class_loader_id(&self, loader_ref: &HeapRef) -> Option<ClassLoaderId> {
+        self.class_loader_index.get(loader_ref).copied()
+    }
+
+    pub fn get_class_loader_ref(&self, loader_id: ClassLoaderId) -> HeapRef {
+        self.class_loaders[loader_id.to_index()]
+    }
+
+    /// Defining loader of the class, `None` for the bootstrap loader.
+    pub fn get_class_loader_id(&self, class_id: &ClassId) -> Option<ClassLoaderId> {
+        self.defining_loaders.get(class_id).copied()
+    }
+
+    /// Class the loader defined or initiated the loading of, nothing gets loaded.
+    pub fn find_loaded_class(
+        &self,
+        loader: Option<ClassLoaderId>,
+        name_sym: Symbol,
+    ) -> Option<ClassId> {
+        match loader {
+            None => self.class_name_to_index.get(&name_sym).copied(),
+            Some(loader_id) => self.loader_class_index.get(&(loader_id, name_sym)).copied(),
+        }
+    }
+
+    /// Records the loader as defining or initiating loader of the class, unless that breaks a
+    /// loader constraint.
+    pub fn record_loaded_class(
+        &mut self,
+        loader: Option<ClassLoaderId>,
+        name_sym: Symbol,
+        class_id: ClassId,
+    ) -> Result<(), JvmError> {
+        self.check_loader_constraints(loader, name_sym, class_id)?;
+        match loader {
+            None => self.class_name_to_index.insert(name_sym, class_id),
+            Some(loader_id) => self
+                .loader_class_index
+                .insert((loader_id, name_sym), class_id),
+        };
+        Ok(())
+    }
+
+    fn check_loader_constraints(
+        &self,
+        loader: Option<ClassLoaderId>,
+        name_sym: Symbol,
+        class_id: ClassId,
+    ) -> Result<(), JvmError> {
+        let Some(constraints) = self.loader_constraints.get(&name_sym) else {
+            return Ok(());
+        };
+        for (first, second) in constraints {
+            let other = if *first == loader {
+                *second
+            } else if *second == loader {
+                *first
+            } else {
+                continue;
+            };
+            match self.find_loaded_class(other, name_sym) {
+                Some(other_class_id) if other_class_id != class_id => throw_exception!(
+                    LinkageError,
+                    "loader constraint violation: a different class with name {} was already loaded by a constrained loader",
+                    self.interner.resolve(&name_sym).replace('/', ".")
+                )?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Loader constraints of a member referenced across loaders (JVMS 5.3.4), each class named
+    /// in its descriptor must be the same class for both loaders. A constraint on a class that
+    /// isn't loaded yet is checked when one of the loaders loads it.
+    pub fn add_loader_constraints(
+        &mut self,
+        referrer_class_id: ClassId,
+        member_class_id: ClassId,
+        descriptor_sym: Symbol,
+    ) -> Result<(), JvmError> {
+        let first = self.get_class_loader_id(&referrer_class_id);
+        let second = self.get_class_loader_id(&member_class_id);
+        if first == second {
+            return Ok(());
+        }
+        let descriptor = self.interner.resolve(&descriptor_sym);
+        let mut rest = descriptor;
+        while let Some(start) = rest.find('L') {
+            let Some(end) = rest[start..].find(';') else {
+                break;
+            };
+            let name_sym = self.interner.get_or_intern(&rest[start + 1..start + end]);
+            rest = &rest[start + end + 1..];
+            let first_class_id = self.find_loaded_class(first, name_sym);
+            let second_class_id = self.find_loaded_class(second, name_sym);
+            match (first_class_id, second_class_id) {
+                (Some(first_class_id), Some(second_class_id))
+                    if first_class_id != second_class_id =>
+                {
+                    throw_exception!(
+                        LinkageError,
+                        "loader constraint violation: {} and {} have different Class objects for the type {} used in {}",
+                        self.interner
+                            .resolve(&self.get_class(&referrer_class_id).get_name())
+                            .replace('/', "."),
+                        self.interner
+                            .resolve(&self.get_class(&member_class_id).get_name())
+                            .replace('/', "."),
+                        self.interner.resolve(&name_sym).replace('/', "."),
+                        descriptor
+                    )?
+                }
+                _ => {}
+            }
+            let constraints = self.loader_constraints.entry(name_sym).or_default();
+            if !constraints.contains(&(first, second)) {
+                constraints.push((first, second));
+            }
+        }
+        self.constrained_descriptors
+            .insert((first, second, descriptor_sym));
+        Ok(())
+    }
+
+    /// Whether `add_loader_constraints` already succeeded for the loaders and the descriptor, the
+    /// classes loaded since then were checked against the constraints it added.
+    pub fn has_loader_constraints(
+        &self,
+        first: Option<ClassLoaderId>,
+        second: Option<ClassLoaderId>,
+        descriptor_sym: Symbol,
+    ) -> bool {
+        self.constrained_descriptors
+            .contains(&(first, second, descriptor_sym))
+    }
+
+    /// References held by the loaded classes, the mirror index is rebuilt afterwards since the
+    /// visitor may move the mirrors.
+    pub fn visit_refs(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        for class in &mut self.classes {
+            class.visit_refs(visitor);
+        }
+        for loader_ref in &mut self.class_loaders {
+            visitor(loader_ref);
+        }
+        self.class_loader_index = self
+            .class_loaders
+            .iter()
+            .enumerate()
+            .map(|(index, loader_ref)| (*loader_ref, ClassLoaderId::from_usize(index + 1)))
+            .collect();
+        self.mirror_to_class_index = self
+            .classes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, class)| {
+                class
+                    .get_mirror_ref()
+                    .map(|mirror_ref| (mirror_ref, ClassId::from_usize(index + 1)))
+            })
+            .collect();
     }
 
     pub fn get_class_id_by_mirror(&self, mirror: &HeapRef) -> Result<ClassId, JvmError> {
@@ -568,6 +1036,17 @@ impl MethodArea {
             .write()
             .unwrap()
             .alloc_instance(class_instance_size, class_class_id)?;
+        if let Some(loader_id) = self.get_class_loader_id(&class_id) {
+            let class_loader_field = self
+                .get_instance_class(&class_class_id)?
+                .get_instance_field(&self.br().class_class_loader_fk)?;
+            heap.write().unwrap().write_field(
+                mirror_ref,
+                class_loader_field.offset,
+                Value::Ref(self.get_class_loader_ref(loader_id)),
+                AllocationType::Reference,
+            )?;
+        }
         if self.get_class(&class_id).is_primitive() {
             let primitive_field_key = self
                 .get_instance_class(&class_class_id)?
@@ -1,4 +1,6 @@
 use crate::error::JvmError;
+use crate::heap::card_table::CardTable;
+use crate::heap::nursery::Nursery;
 use crate::keys::ClassId;
 use crate::vm::Value;
 use crate::{Symbol, debug_error_log, throw_exception};
@@ -8,8 +10,16 @@ use lasso::ThreadedRodeo;
 use std::collections::HashMap;
 use std::num::NonZeroU32;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+mod card_table;
+pub mod gc;
+pub mod histogram;
+mod hprof;
 pub mod method_area;
+mod nursery;
+mod reference;
+mod verify;
 
 // TODO: use u32 or usize for HeapRef?
 // TODO: add specific struct for heap reference, and allow only heap create instance
@@ -20,9 +30,14 @@ pub struct ObjectHeader {
     size: u32, // total bytes (header + data)
     // be careful with arrays, because class_id for arrays isn't [ (problematic for mirrors)
     class_id: NonZeroU32,
-    marked: bool, // for GC in future
+    // index + 1 of the inflated monitor in the monitor table, 0 if the object was never locked
+    monitor_id: u32,
+    // identity hash, 0 until it is first asked for
+    hash: u32,
+    marked: bool, // reachable, set by the collector while marking
     is_array: bool,
-    _padding: [u8; 3],
+    age: u8, // minor collections survived in the nursery
+    _padding: [u8; 5],
 }
 
 impl ObjectHeader {
@@ -35,10 +50,29 @@ impl ObjectHeader {
 
 pub struct Heap {
     memory: *mut u8,
+    // reserved address space, only the committed part of it is backed by memory
     capacity: usize,
+    page_size: usize,
+    // the old space spans from the start of the heap to `old_end`, the nursery takes the rest
+    old_end: usize,
+    // the old space is committed up to here, it grows towards `old_end`
+    committed: usize,
+    // live old objects are compacted to the start of the heap, so everything below is taken
     allocated: usize,
+    nursery: Nursery,
+    card_table: CardTable,
+    // old space usage that requests the next full collection
+    gc_threshold: usize,
+    gc_requested: Arc<AtomicBool>,
     interner: Arc<ThreadedRodeo>,
     string_pool: HashMap<Symbol, HeapRef>,
+    // references cleared by the collector, linked through their `discovered` field, until the
+    // Reference Handler thread takes them; 0 if there are none
+    reference_pending_list: HeapRef,
+    // object of each inflated monitor, indexed by monitor id - 1; 0 for the free ids
+    monitor_objects: Vec<HeapRef>,
+    // monitors of the objects the collector found dead, until the monitor table frees them
+    dead_monitors: Vec<u32>,
     byte_array_class_id: ClassId,
     string_class_id: ClassId,
     string_instance_size: usize,
@@ -56,23 +90,35 @@ impl Heap {
     const LATIN1: i32 = 0;
     const UTF16: i32 = 1;
 
+    /// Reserves `max_size` bytes of address space, only `initial_size` of it is committed at
+    /// first, the nursery included. The old space grows as needed up to the reservation.
     pub fn new(
-        size_mb: usize,
+        initial_size: usize,
+        max_size: usize,
+        nursery_size: usize,
         interner: Arc<ThreadedRodeo>,
         string_class_id: ClassId,
         string_instance_size: usize,
         char_array_class_id: ClassId,
     ) -> Result<Self, JvmError> {
         // TODO: delete in the future
-        assert_eq!(size_of::<ObjectHeader>(), 16);
-        let capacity = size_mb * 1024 * 1024;
+        assert_eq!(size_of::<ObjectHeader>(), 24);
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let capacity = max_size.next_multiple_of(page_size);
+        let nursery_size = nursery_size.next_multiple_of(page_size);
+        if nursery_size >= capacity {
+            return Err(JvmError::Todo(
+                "Nursery doesn't fit the maximum heap size".to_string(),
+            ));
+        }
+        let old_end = capacity - nursery_size;
 
         let memory = unsafe {
             libc::mmap(
                 std::ptr::null_mut(),
                 capacity,
-                libc::PROT_READ | libc::PROT_WRITE,
-                libc::MAP_PRIVATE | libc::MAP_ANON,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANON | libc::MAP_NORESERVE,
                 -1,
                 0,
             )
@@ -82,31 +128,78 @@ impl Heap {
             return Err(JvmError::Todo("mmap failed".to_string()));
         }
 
-        Ok(Heap {
+        let mut heap = Heap {
             memory: memory as *mut u8,
             capacity,
+            page_size,
+            old_end,
+            committed: 0,
             allocated: ObjectHeader::SIZE,
+            nursery: Nursery::new(old_end, nursery_size),
+            card_table: CardTable::new(old_end),
+            gc_threshold: 0,
+            gc_requested: Arc::new(AtomicBool::new(false)),
             string_pool: HashMap::new(),
+            reference_pending_list: 0,
+            monitor_objects: Vec::new(),
+            dead_monitors: Vec::new(),
             interner,
             string_class_id,
             string_instance_size,
             byte_array_class_id: char_array_class_id,
-        })
+        };
+        let initial_old_size = initial_size
+            .saturating_sub(nursery_size)
+            .clamp(page_size, old_end);
+        if !heap.commit_memory(old_end, nursery_size) || !heap.commit(initial_old_size) {
+            return Err(JvmError::Todo("mprotect failed".to_string()));
+        }
+        heap.gc_threshold = heap.committed / 2;
+        Ok(heap)
     }
 
-    fn alloc_raw(&mut self, size: usize) -> Result<HeapRef, JvmError> {
+    fn alloc_raw(
+        &mut self,
+        size: usize,
+        class_id: ClassId,
+        is_array: bool,
+    ) -> Result<HeapRef, JvmError> {
         let total_needed = ObjectHeader::SIZE + size;
 
         // align to 8 bytes
         let aligned_total = (total_needed + 7) & !7;
 
-        if self.allocated + aligned_total > self.capacity {
-            // TODO: OOM
-            return Err(JvmError::Todo("Heap full".to_string()));
+        // the header keeps the size in a u32
+        if aligned_total > u32::MAX as usize {
+            throw_exception!(OutOfMemoryError, "Requested array size exceeds VM limit")?
         }
 
-        let offset = self.allocated;
-        self.allocated += aligned_total;
+        let offset = if aligned_total > self.nursery.max_object_size() {
+            self.alloc_old(aligned_total)?
+        } else {
+            match self.nursery.alloc(aligned_total) {
+                Some(offset) => offset,
+                None => {
+                    // the nursery is full, the object goes to the old space until the next
+                    // minor collection empties it
+                    self.gc_requested.store(true, Ordering::Relaxed);
+                    self.alloc_old(aligned_total)?
+                }
+            }
+        };
+
+        unsafe {
+            (self.memory.add(offset) as *mut ObjectHeader).write(ObjectHeader {
+                size: aligned_total as u32,
+                class_id: class_id.into_inner(),
+                monitor_id: 0,
+                hash: 0,
+                marked: false,
+                is_array,
+                age: 0,
+                _padding: [0; 5],
+            });
+        }
 
         // zero initialize
         let data_ptr = unsafe { self.get_data_ptr(offset) };
@@ -117,11 +210,140 @@ impl Heap {
         Ok(offset)
     }
 
+    fn alloc_old(&mut self, size: usize) -> Result<HeapRef, JvmError> {
+        if !self.commit(self.allocated + size) {
+            throw_exception!(OutOfMemoryError, "Java heap space")?
+        }
+
+        let offset = self.allocated;
+        self.allocated += size;
+        self.card_table.record_object(offset);
+        if self.allocated >= self.gc_threshold {
+            self.gc_requested.store(true, Ordering::Relaxed);
+        }
+        Ok(offset)
+    }
+
+    /// Makes sure the old space is committed up to `end`, growing it by at least a quarter at a
+    /// time. Returns false if it can't grow that far.
+    fn commit(&mut self, end: usize) -> bool {
+        if end <= self.committed {
+            return true;
+        }
+        if end > self.old_end {
+            return false;
+        }
+        let new_committed = end
+            .max(self.committed + self.committed / 4)
+            .next_multiple_of(self.page_size)
+            .min(self.old_end);
+        if !self.commit_memory(self.committed, new_committed - self.committed) {
+            return false;
+        }
+        self.committed = new_committed;
+        true
+    }
+
+    fn commit_memory(&self, start: usize, len: usize) -> bool {
+        let result = unsafe {
+            libc::mprotect(
+                self.memory.add(start) as *mut libc::c_void,
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+            )
+        };
+        result == 0
+    }
+
+    /// Bytes taken by objects, dead ones included until the next collection.
+    pub fn used(&self) -> usize {
+        self.allocated + self.nursery.used()
+    }
+
+    /// Committed bytes, `Runtime.totalMemory`.
+    pub fn total_memory(&self) -> usize {
+        self.committed + (self.capacity - self.old_end)
+    }
+
+    /// Bytes the heap may grow to, `Runtime.maxMemory`.
+    pub fn max_memory(&self) -> usize {
+        self.capacity
+    }
+
+    /// Committed bytes not taken by objects, `Runtime.freeMemory`.
+    pub fn free_memory(&self) -> usize {
+        self.total_memory() - self.used()
+    }
+
+    /// Addresses of the objects in the old space and the nursery, dead ones included until the
+    /// next collection.
+    pub(crate) fn objects(&self) -> impl Iterator<Item = HeapRef> + '_ {
+        let (nursery_start, nursery_top) = self.nursery.objects();
+        [
+            (ObjectHeader::SIZE, self.allocated),
+            (nursery_start, nursery_top),
+        ]
+        .into_iter()
+        .flat_map(move |(start, end)| {
+            std::iter::successors(Some(start), move |offset| {
+                Some(offset + self.get_header(*offset).size as usize)
+            })
+            .take_while(move |offset| *offset < end)
+        })
+    }
+
+    /// Card marking write barrier, remembers old objects that reference nursery objects.
+    fn write_barrier(&mut self, heap_ref: HeapRef, value_ref: HeapRef) {
+        if !self.nursery.contains(heap_ref) && self.nursery.contains(value_ref) {
+            self.card_table.dirty(heap_ref);
+        }
+    }
+
+    /// Set once the usage crosses the threshold, threads check it at safepoints.
+    pub fn gc_requested_flag(&self) -> Arc<AtomicBool> {
+        self.gc_requested.clone()
+    }
+
     pub fn is_array(&self, heap_ref: HeapRef) -> Result<bool, JvmError> {
         let header = self.get_header(heap_ref);
         Ok(header.is_array())
     }
 
+    pub fn get_monitor_id(&self, heap_ref: HeapRef) -> Option<u32> {
+        let monitor_id = self.get_header(heap_ref).monitor_id;
+        (monitor_id != 0).then_some(monitor_id)
+    }
+
+    pub fn set_monitor_id(&mut self, heap_ref: HeapRef, monitor_id: u32) {
+        self.get_header_mut(heap_ref).monitor_id = monitor_id;
+        let index = monitor_id as usize - 1;
+        if index >= self.monitor_objects.len() {
+            self.monitor_objects.resize(index + 1, 0);
+        }
+        self.monitor_objects[index] = heap_ref;
+    }
+
+    /// Ids of the monitors whose objects died since the last call, their ids aren't in any
+    /// header anymore.
+    pub fn take_dead_monitors(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.dead_monitors)
+    }
+
+    /// Identity hash of the object, `new_hash` generates it on the first request. It's kept in
+    /// the header, so it doesn't change when the collector moves the object.
+    pub fn identity_hash(&mut self, heap_ref: HeapRef, new_hash: impl FnOnce() -> u32) -> i32 {
+        let header = self.get_header_mut(heap_ref);
+        if header.hash == 0 {
+            header.hash = new_hash();
+        }
+        header.hash as i32
+    }
+
+    pub fn is_object_array(&self, heap_ref: HeapRef) -> Result<bool, JvmError> {
+        Ok(self.is_array(heap_ref)?
+            && self.get_allocation_type(heap_ref)? == AllocationType::Reference)
+    }
+
     fn get_header_mut(&mut self, heap_ref: HeapRef) -> &mut ObjectHeader {
         unsafe { &mut *(self.memory.add(heap_ref) as *mut ObjectHeader) }
     }
@@ -147,15 +369,7 @@ impl Heap {
         instance_size: usize,
         class_id: ClassId,
     ) -> Result<HeapRef, JvmError> {
-        let heap_ref = self.alloc_raw(instance_size)?;
-
-        let header = self.get_header_mut(heap_ref);
-        header.class_id = class_id.into_inner();
-        header.size = (ObjectHeader::SIZE + instance_size) as u32;
-        header.marked = false;
-        header.is_array = false;
-
-        Ok(heap_ref)
+        self.alloc_raw(instance_size, class_id, false)
     }
 
     fn alloc_array_internal(
@@ -170,13 +384,7 @@ impl Heap {
 
         let element_size = allocation_type.byte_size();
         let array_data_size = Self::ARRAY_ELEMENTS_OFFSET + (length as usize * element_size);
-        let heap_ref = self.alloc_raw(array_data_size)?;
-
-        let header = self.get_header_mut(heap_ref);
-        header.class_id = class_id.into_inner();
-        header.size = (ObjectHeader::SIZE + array_data_size) as u32;
-        header.marked = false;
-        header.is_array = true;
+        let heap_ref = self.alloc_raw(array_data_size, class_id, true)?;
 
         let data_ptr = unsafe { self.get_data_ptr(heap_ref) };
         unsafe {
@@ -331,6 +539,7 @@ impl Heap {
                 unsafe {
                     *(target_ptr as *mut HeapRef) = r;
                 }
+                self.write_barrier(heap_ref, r);
                 Ok(())
             }
             (Value::Null, AllocationType::Reference) => {
@@ -510,6 +719,44 @@ impl Heap {
             self.alloc_utf16_byte_array_internal(s, f)?
         };
 
+        self.alloc_string_instance(byte_array_ref, coder)
+    }
+
+    /// Allocates a string from UTF-16 code units, lone surrogates are kept as they are.
+    pub fn alloc_string_from_utf16(&mut self, units: &[u16]) -> Result<HeapRef, JvmError> {
+        let (byte_array_ref, coder) = if units.iter().all(|unit| *unit <= 0xFF) {
+            let byte_array = self.alloc_primitive_array(
+                self.byte_array_class_id,
+                ArrayType::Byte,
+                units.len() as i32,
+            )?;
+            let byte_slice = self.get_byte_array_slice_mut(byte_array)?;
+            for (i, unit) in units.iter().enumerate() {
+                byte_slice[i] = *unit as u8 as i8;
+            }
+            (byte_array, Self::LATIN1)
+        } else {
+            let byte_array = self.alloc_primitive_array(
+                self.byte_array_class_id,
+                ArrayType::Byte,
+                (units.len() * 2) as i32,
+            )?;
+            let byte_slice = self.get_byte_array_slice_mut(byte_array)?;
+            for (i, unit) in units.iter().enumerate() {
+                let [lo, hi] = unit.to_le_bytes();
+                byte_slice[2 * i] = lo as i8;
+                byte_slice[2 * i + 1] = hi as i8;
+            }
+            (byte_array, Self::UTF16)
+        };
+        self.alloc_string_instance(byte_array_ref, coder)
+    }
+
+    fn alloc_string_instance(
+        &mut self,
+        byte_array_ref: HeapRef,
+        coder: i32,
+    ) -> Result<HeapRef, JvmError> {
         let string_instance =
             self.alloc_instance(self.string_instance_size, self.string_class_id)?;
 
@@ -558,6 +805,12 @@ impl Heap {
 
     // TODO: just a stub right now
     pub fn get_rust_string_from_java_string(&self, h: HeapRef) -> Result<String, JvmError> {
+        let utf16_units = self.get_utf16_from_java_string(h)?;
+        Ok(String::from_utf16_lossy(&utf16_units))
+    }
+
+    /// UTF-16 code units of the string, whatever its coder is.
+    pub fn get_utf16_from_java_string(&self, h: HeapRef) -> Result<Vec<u16>, JvmError> {
         // Read byte[] value field (offset 0)
         let byte_array_ref = match self.read_field(h, 0, AllocationType::Reference)? {
             Value::Ref(r) => r,
@@ -578,10 +831,7 @@ impl Heap {
         let byte_slice = self.get_byte_array_slice(byte_array_ref)?;
 
         match coder {
-            Self::LATIN1 => {
-                let chars: String = byte_slice.iter().map(|&b| (b as u8) as char).collect();
-                Ok(chars)
-            }
+            Self::LATIN1 => Ok(byte_slice.iter().map(|&b| b as u8 as u16).collect()),
             Self::UTF16 => {
                 if byte_slice.len() % 2 != 0 {
                     return Err(JvmError::Todo(
@@ -595,7 +845,7 @@ impl Heap {
                     utf16_units.push(code_unit);
                 }
 
-                Ok(String::from_utf16_lossy(&utf16_units))
+                Ok(utf16_units)
             }
             _ => Err(JvmError::Todo(format!("Unknown String coder: {}", coder))),
         }
@@ -653,6 +903,10 @@ impl Heap {
         unsafe {
             std::ptr::copy(src_ptr, dest_ptr, length as usize * element_size);
         }
+        if allocation_type == AllocationType::Reference && !self.nursery.contains(dest) {
+            // the copied elements aren't checked one by one, the card is dirtied in any case
+            self.card_table.dirty(dest);
+        }
 
         Ok(())
     }
@@ -667,7 +921,7 @@ impl Heap {
             )
         };
 
-        let dest = self.alloc_raw(data_size)?;
+        let dest = self.alloc_raw(data_size, ClassId::new(class_id), is_array)?;
 
         let src_data_ptr = unsafe { self.get_data_ptr(src) };
         let dest_data_ptr = unsafe { self.get_data_ptr(dest) };
@@ -675,11 +929,10 @@ impl Heap {
         unsafe {
             std::ptr::copy_nonoverlapping(src_data_ptr, dest_data_ptr, data_size);
         }
-
-        let dest_header = self.get_header_mut(dest);
-        dest_header.class_id = class_id;
-        dest_header.marked = false;
-        dest_header.is_array = is_array;
+        if !self.nursery.contains(dest) {
+            // the clone may reference nursery objects, same as arraycopy
+            self.card_table.dirty(dest);
+        }
 
         Ok(dest)
     }
@@ -740,6 +993,30 @@ impl Heap {
         Ok(unsafe { std::slice::from_raw_parts_mut(elements_ptr as *mut i8, length as usize) })
     }
 
+    /// Copy of `length` bytes of the byte array from `offset`, the range comes from Java code
+    /// (`ClassLoader.defineClass` and the like) and is checked the way `Objects.checkFromIndexSize`
+    /// does it.
+    pub fn copy_byte_array_range(
+        &self,
+        heap_ref: HeapRef,
+        offset: i32,
+        length: i32,
+    ) -> Result<Vec<u8>, JvmError> {
+        let bytes = self.get_byte_array_slice(heap_ref)?;
+        if offset < 0 || length < 0 || offset as usize + length as usize > bytes.len() {
+            throw_exception!(
+                ArrayIndexOutOfBoundsException,
+                "Range [{}, {} + {}) out of bounds for length {}",
+                offset,
+                offset,
+                length,
+                bytes.len()
+            )?
+        }
+        let range = offset as usize..offset as usize + length as usize;
+        Ok(bytes[range].iter().map(|b| *b as u8).collect())
+    }
+
     pub fn get_int_array_slice(&self, heap_ref: HeapRef) -> Result<&[i32], JvmError> {
         let allocation_type = self.get_allocation_type(heap_ref)?;
         if allocation_type != AllocationType::Int {
@@ -0,0 +1,224 @@
+use crate::error::JvmError;
+use crate::heap::gc::visit_roots;
+use crate::heap::method_area::MethodArea;
+use crate::heap::reference::ReferenceDiscovery;
+use crate::heap::{Heap, HeapRef};
+use crate::keys::ClassId;
+use crate::thread::JavaThreadState;
+use crate::vm::Value;
+use lagertha_common::jtype::AllocationType;
+use std::collections::HashMap;
+
+// minor collections an object survives in the nursery before it's promoted to the old space
+const TENURING_THRESHOLD: u8 = 4;
+
+/// Young objects are bump allocated in one of two semispaces, a minor collection copies the
+/// live ones to the other semispace, or promotes them to the old space, and the two swap roles.
+pub(super) struct Nursery {
+    start: HeapRef,
+    semispace_size: usize,
+    // semispace the objects are allocated in
+    from: HeapRef,
+    top: HeapRef,
+}
+
+impl Nursery {
+    /// The nursery takes the end of the heap, from `start` on.
+    pub(super) fn new(start: HeapRef, size: usize) -> Self {
+        Self {
+            start,
+            semispace_size: (size / 2) & !7,
+            from: start,
+            top: start,
+        }
+    }
+
+    pub(super) fn contains(&self, heap_ref: HeapRef) -> bool {
+        heap_ref >= self.start
+    }
+
+    /// Bigger objects are allocated in the old space directly, copying them isn't worth it.
+    pub(super) fn max_object_size(&self) -> usize {
+        self.semispace_size / 2
+    }
+
+    pub(super) fn alloc(&mut self, size: usize) -> Option<HeapRef> {
+        if self.top + size > self.from + self.semispace_size {
+            return None;
+        }
+        let offset = self.top;
+        self.top += size;
+        Some(offset)
+    }
+
+    pub(super) fn used(&self) -> usize {
+        self.top - self.from
+    }
+
+    /// Address range of the allocated objects.
+    pub(super) fn objects(&self) -> (HeapRef, HeapRef) {
+        (self.from, self.top)
+    }
+
+    fn in_from_space(&self, heap_ref: HeapRef) -> bool {
+        heap_ref >= self.from && heap_ref < self.from + self.semispace_size
+    }
+
+    fn to_space(&self) -> HeapRef {
+        if self.from == self.start {
+            self.start + self.semispace_size
+        } else {
+            self.start
+        }
+    }
+}
+
+impl Heap {
+    /// Copying minor collection (Cheney): the nursery objects reachable from the roots or from
+    /// the dirty cards of the old space are evacuated, the rest of the old space isn't scanned.
+    /// Returns false without collecting if the old space might not fit the promoted objects.
+    pub(super) fn collect_young(
+        &mut self,
+        method_area: &mut MethodArea,
+        threads: &mut [&mut JavaThreadState],
+    ) -> Result<bool, JvmError> {
+        // in the worst case every nursery object is promoted
+        if !self.commit(self.allocated + self.nursery.used()) {
+            return Ok(false);
+        }
+        let to_space = self.nursery.to_space();
+        let mut survivor_top = to_space;
+        let promoted_start = self.allocated;
+
+        let mut string_pool = std::mem::take(&mut self.string_pool);
+        let mut reference_pending_list = self.reference_pending_list;
+        visit_roots(
+            &mut string_pool,
+            &mut reference_pending_list,
+            method_area,
+            threads,
+            &mut |heap_ref| {
+                if self.nursery.in_from_space(*heap_ref) {
+                    *heap_ref = self.evacuate(*heap_ref, &mut survivor_top);
+                }
+            },
+        );
+        self.string_pool = string_pool;
+        self.reference_pending_list = reference_pending_list;
+
+        // soft references are only cleared by full collections
+        let mut references = ReferenceDiscovery::new(false);
+
+        // offsets of the reference fields, per class of the scanned instances
+        let mut ref_offsets: HashMap<ClassId, Vec<usize>> = HashMap::new();
+        for (card_start, card_end) in self.card_table.take_dirty(promoted_start) {
+            let Some(mut offset) = self.card_table.first_object(card_start) else {
+                continue;
+            };
+            // objects promoted by this collection are scanned below
+            while offset < card_end && offset < promoted_start {
+                self.evacuate_referents(
+                    offset,
+                    method_area,
+                    &mut ref_offsets,
+                    &mut references,
+                    &mut survivor_top,
+                )?;
+                offset += self.get_header(offset).size as usize;
+            }
+        }
+
+        // the copied objects are scanned in turn, until no new ones are copied
+        let mut survivor_scan = to_space;
+        let mut promoted_scan = promoted_start;
+        while survivor_scan < survivor_top || promoted_scan < self.allocated {
+            let scan = if survivor_scan < survivor_top {
+                &mut survivor_scan
+            } else {
+                &mut promoted_scan
+            };
+            let heap_ref = *scan;
+            *scan += self.get_header(heap_ref).size as usize;
+            self.evacuate_referents(
+                heap_ref,
+                method_area,
+                &mut ref_offsets,
+                &mut references,
+                &mut survivor_top,
+            )?;
+        }
+        let forwardee = |heap: &Heap, heap_ref: HeapRef| {
+            if !heap.nursery.in_from_space(heap_ref) {
+                Some(heap_ref)
+            } else {
+                let header = heap.get_header(heap_ref);
+                header.marked.then(|| heap.forwarding_address(heap_ref))
+            }
+        };
+        references.process(self, method_area, forwardee)?;
+        self.sweep_monitors(forwardee);
+
+        self.nursery.from = to_space;
+        self.nursery.top = survivor_top;
+        Ok(true)
+    }
+
+    /// Evacuates the nursery objects the object references and points it to the copies, the
+    /// write barrier dirties the card again if it is an old object that still references the
+    /// nursery. The referent of a reference object is left to `references`.
+    fn evacuate_referents(
+        &mut self,
+        heap_ref: HeapRef,
+        method_area: &MethodArea,
+        ref_offsets: &mut HashMap<ClassId, Vec<usize>>,
+        references: &mut ReferenceDiscovery,
+        survivor_top: &mut HeapRef,
+    ) -> Result<(), JvmError> {
+        let referent_slot = references.discover(self, heap_ref, method_area)?;
+        for slot in self.reference_slots(heap_ref, method_area, ref_offsets)? {
+            if referent_slot == Some(slot) {
+                continue;
+            }
+            let target = self
+                .read_field(heap_ref, slot, AllocationType::Reference)?
+                .as_nullable_obj_ref()?;
+            if let Some(target) = target.filter(|target| self.nursery.in_from_space(*target)) {
+                let copy = Value::Ref(self.evacuate(target, survivor_top));
+                self.write_field(heap_ref, slot, copy, AllocationType::Reference)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the object to the survivor semispace, or to the old space once it is old enough
+    /// or the survivors don't fit, and returns the copy. An object is copied only once, its
+    /// old copy is marked and forwards to the new one.
+    fn evacuate(&mut self, heap_ref: HeapRef, survivor_top: &mut HeapRef) -> HeapRef {
+        let header = self.get_header(heap_ref);
+        if header.marked {
+            return self.forwarding_address(heap_ref);
+        }
+        let (size, age) = (header.size as usize, header.age);
+        let survivor_end = self.nursery.to_space() + self.nursery.semispace_size;
+        let copy = if age < TENURING_THRESHOLD && *survivor_top + size <= survivor_end {
+            let copy = *survivor_top;
+            *survivor_top += size;
+            copy
+        } else {
+            // collect_young committed room for all of them
+            let copy = self.allocated;
+            self.allocated += size;
+            self.card_table.record_object(copy);
+            copy
+        };
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.memory.add(heap_ref), self.memory.add(copy), size);
+        }
+        if self.nursery.contains(copy) {
+            self.get_header_mut(copy).age += 1;
+        }
+        self.get_header_mut(heap_ref).marked = true;
+        self.set_forwarding_address(heap_ref, copy);
+        copy
+    }
+}
@@ -0,0 +1,159 @@
+use crate::error::JvmError;
+use crate::heap::method_area::MethodArea;
+use crate::heap::{Heap, HeapRef};
+use crate::keys::ClassId;
+use crate::vm::Value;
+use lagertha_common::jtype::AllocationType;
+use std::collections::HashMap;
+
+/// How strongly a `java.lang.ref.Reference` subclass holds its referent.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ReferenceKind {
+    Soft,
+    Weak,
+    Phantom,
+}
+
+/// Reference objects a collection traced without tracing their referent. Once tracing is done,
+/// the referents nothing else reached are cleared and the references are handed over to the
+/// Reference Handler thread through the pending list.
+/// Finalization isn't supported, `FinalReference`s are never created and are traced like any
+/// other object.
+pub(super) struct ReferenceDiscovery {
+    // softly reachable referents are kept alive, unless the heap is about to run out
+    clear_soft_references: bool,
+    // kind of the instances of a class, none for the classes that aren't references
+    kinds: HashMap<ClassId, Option<ReferenceKind>>,
+    referent_offset: Option<usize>,
+    discovered: Vec<HeapRef>,
+}
+
+impl ReferenceDiscovery {
+    pub(super) fn new(clear_soft_references: bool) -> Self {
+        Self {
+            clear_soft_references,
+            kinds: HashMap::new(),
+            referent_offset: None,
+            discovered: Vec::new(),
+        }
+    }
+
+    /// Offset of the referent the tracing must skip, if the object is a reference that doesn't
+    /// keep it alive. The reference is remembered until `process`.
+    pub(super) fn discover(
+        &mut self,
+        heap: &Heap,
+        heap_ref: HeapRef,
+        method_area: &MethodArea,
+    ) -> Result<Option<usize>, JvmError> {
+        if heap.is_array(heap_ref)? {
+            return Ok(None);
+        }
+        let class_id = heap.get_class_id(heap_ref)?;
+        let kind = *self
+            .kinds
+            .entry(class_id)
+            .or_insert_with(|| reference_kind(method_area, class_id));
+        match kind {
+            None => return Ok(None),
+            Some(ReferenceKind::Soft) if !self.clear_soft_references => return Ok(None),
+            Some(_) => {}
+        }
+        // the field is declared by Reference, so it's at the same offset in all its subclasses
+        let referent_offset = match self.referent_offset {
+            Some(offset) => offset,
+            None => {
+                let offset = method_area
+                    .get_instance_field(&class_id, &method_area.br().reference_referent_fk)?
+                    .offset;
+                self.referent_offset = Some(offset);
+                offset
+            }
+        };
+        // cleared or enqueued references have no referent left to clear
+        if heap.read_field(heap_ref, referent_offset, AllocationType::Reference)? == Value::Null {
+            return Ok(None);
+        }
+        self.discovered.push(heap_ref);
+        Ok(Some(referent_offset))
+    }
+
+    /// Updates the referents that survived with `forward`, which returns the new address of a
+    /// live object and none for a dead one. The referents that didn't survive are cleared and
+    /// their references are pushed to the pending list.
+    pub(super) fn process(
+        self,
+        heap: &mut Heap,
+        method_area: &MethodArea,
+        forward: impl Fn(&Heap, HeapRef) -> Option<HeapRef>,
+    ) -> Result<(), JvmError> {
+        let Some(referent_offset) = self.referent_offset else {
+            return Ok(());
+        };
+        for reference in self.discovered {
+            let referent = heap
+                .read_field(reference, referent_offset, AllocationType::Reference)?
+                .as_obj_ref()?;
+            let value = match forward(heap, referent) {
+                Some(forwardee) if forwardee == referent => continue,
+                Some(forwardee) => Value::Ref(forwardee),
+                None => {
+                    heap.push_pending_reference(reference, method_area)?;
+                    Value::Null
+                }
+            };
+            heap.write_field(reference, referent_offset, value, AllocationType::Reference)?;
+        }
+        Ok(())
+    }
+}
+
+impl Heap {
+    /// Links the reference in front of the pending list through its `discovered` field.
+    fn push_pending_reference(
+        &mut self,
+        reference: HeapRef,
+        method_area: &MethodArea,
+    ) -> Result<(), JvmError> {
+        let class_id = self.get_class_id(reference)?;
+        let discovered_offset = method_area
+            .get_instance_field(&class_id, &method_area.br().reference_discovered_fk)?
+            .offset;
+        let next = match self.reference_pending_list {
+            0 => Value::Null,
+            next => Value::Ref(next),
+        };
+        self.write_field(
+            reference,
+            discovered_offset,
+            next,
+            AllocationType::Reference,
+        )?;
+        self.reference_pending_list = reference;
+        Ok(())
+    }
+
+    pub fn has_reference_pending_list(&self) -> bool {
+        self.reference_pending_list != 0
+    }
+
+    /// `Reference.getAndClearReferencePendingList`, the head of the list or 0 if it is empty.
+    pub fn take_reference_pending_list(&mut self) -> HeapRef {
+        std::mem::take(&mut self.reference_pending_list)
+    }
+}
+
+fn reference_kind(method_area: &MethodArea, class_id: ClassId) -> Option<ReferenceKind> {
+    let br = method_area.br();
+    [
+        (br.java_lang_ref_soft_reference_sym, ReferenceKind::Soft),
+        (br.java_lang_ref_weak_reference_sym, ReferenceKind::Weak),
+        (
+            br.java_lang_ref_phantom_reference_sym,
+            ReferenceKind::Phantom,
+        ),
+    ]
+    .into_iter()
+    .find(|(class_sym, _)| method_area.instance_of(class_id, *class_sym))
+    .map(|(_, kind)| kind)
+}
@@ -0,0 +1,370 @@
+use crate::error::JvmError;
+use crate::heap::gc::visit_roots;
+use crate::heap::method_area::MethodArea;
+use crate::heap::{Heap, HeapRef, ObjectHeader};
+use crate::keys::ClassId;
+use crate::rt::JvmClass;
+use crate::thread::JavaThreadState;
+use crate::vm::Value;
+use lagertha_common::jtype::{AllocationType, PrimitiveType};
+use std::collections::{HashMap, HashSet};
+
+impl Heap {
+    /// `-XX:+VerifyHeap`, walks the old space and the nursery and checks that the objects are
+    /// laid out back to back with sane headers, that their size fits their class and that every
+    /// reference, in the heap or in the roots, points at the start of an object of the expected
+    /// type. Must run at a safepoint, outside of a collection. The error describes the first
+    /// broken object and field, or the broken root.
+    pub(crate) fn verify(
+        &mut self,
+        method_area: &mut MethodArea,
+        threads: &mut [&mut JavaThreadState],
+    ) -> Result<(), String> {
+        let mut objects = Vec::new();
+        let (nursery_start, nursery_top) = self.nursery.objects();
+        for (start, end) in [
+            (ObjectHeader::SIZE, self.allocated),
+            (nursery_start, nursery_top),
+        ] {
+            let mut offset = start;
+            while offset < end {
+                let size = self.verify_object(offset, end, method_area)?;
+                objects.push(offset);
+                offset += size;
+            }
+        }
+
+        let starts = objects.iter().copied().collect::<HashSet<_>>();
+        let mut ref_offsets: HashMap<ClassId, Vec<usize>> = HashMap::new();
+        for heap_ref in objects {
+            let slots = self
+                .reference_slots(heap_ref, method_area, &mut ref_offsets)
+                .map_err(broken(heap_ref))?;
+            for slot in slots {
+                self.verify_reference(heap_ref, slot, &starts, method_area)?;
+            }
+        }
+
+        let mut roots = Vec::new();
+        visit_roots(
+            &mut self.string_pool,
+            &mut self.reference_pending_list,
+            method_area,
+            threads,
+            &mut |heap_ref| roots.push(*heap_ref),
+        );
+        match roots
+            .into_iter()
+            .find(|root| *root != 0 && !starts.contains(root))
+        {
+            Some(root) => Err(format!(
+                "a root points to {:#x}, which isn't the start of an object",
+                root
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the header of the object at `offset`, in the space ending at `end`, and returns
+    /// the object size to get to the next one.
+    fn verify_object(
+        &self,
+        offset: HeapRef,
+        end: HeapRef,
+        method_area: &MethodArea,
+    ) -> Result<usize, String> {
+        if offset % 8 != 0 {
+            return Err(format!("object at {:#x}: misaligned header", offset));
+        }
+        let header = self.get_header(offset);
+        let size = header.size as usize;
+        if size < ObjectHeader::SIZE || size % 8 != 0 || offset + size > end {
+            return Err(format!(
+                "object at {:#x}: size {} doesn't fit the space ending at {:#x}",
+                offset, size, end
+            ));
+        }
+        if header.marked {
+            return Err(format!(
+                "object at {:#x}: left marked by the collector",
+                offset
+            ));
+        }
+        let class_id = ClassId::new(header.class_id);
+        if !method_area.contains_class(&class_id) {
+            return Err(format!(
+                "object at {:#x}: unknown class id {}",
+                offset, header.class_id
+            ));
+        }
+
+        let expected_size = if header.is_array {
+            let allocation_type = self.get_allocation_type(offset).map_err(broken(offset))?;
+            let class_matches = match method_area.get_class(&class_id) {
+                JvmClass::PrimitiveArray(array_class) => {
+                    allocation_type == element_allocation_type(&array_class.element_type)
+                }
+                JvmClass::Primitive(_) => false,
+                // object arrays keep the element class in the header
+                _ => allocation_type == AllocationType::Reference,
+            };
+            if !class_matches {
+                return Err(format!(
+                    "{}: element type doesn't match the class",
+                    self.describe(offset, method_area)
+                ));
+            }
+            let length = self.get_array_length(offset).map_err(broken(offset))?;
+            if length < 0 {
+                return Err(format!(
+                    "{}: negative length {}",
+                    self.describe(offset, method_area),
+                    length
+                ));
+            }
+            Self::ARRAY_ELEMENTS_OFFSET + length as usize * allocation_type.byte_size()
+        } else {
+            let Ok(class) = method_area.get_instance_class(&class_id) else {
+                return Err(format!(
+                    "{}: not an instance class",
+                    self.describe(offset, method_area)
+                ));
+            };
+            class.get_instance_size().map_err(broken(offset))?
+        };
+        let expected_size = (ObjectHeader::SIZE + expected_size + 7) & !7;
+        // instances may carry fields the VM injects past the declared ones, like
+        // ResolvedMethodName.vmtarget
+        if size < expected_size || (header.is_array && size != expected_size) {
+            return Err(format!(
+                "{}: size {}, expected {}",
+                self.describe(offset, method_area),
+                size,
+                expected_size
+            ));
+        }
+        Ok(size)
+    }
+
+    fn verify_reference(
+        &self,
+        heap_ref: HeapRef,
+        slot: usize,
+        starts: &HashSet<HeapRef>,
+        method_area: &MethodArea,
+    ) -> Result<(), String> {
+        let value = self
+            .read_field(heap_ref, slot, AllocationType::Reference)
+            .map_err(broken(heap_ref))?;
+        let Value::Ref(target) = value else {
+            return Ok(());
+        };
+        let slot_name = || self.describe_slot(heap_ref, slot, method_area);
+        if !starts.contains(&target) {
+            return Err(format!(
+                "{}: {} points to {:#x}, which isn't the start of an object",
+                self.describe(heap_ref, method_area),
+                slot_name(),
+                target
+            ));
+        }
+        let is_object_array = self.is_object_array(heap_ref).map_err(broken(heap_ref))?;
+        if is_object_array && !self.is_array_element(heap_ref, target, method_area) {
+            return Err(format!(
+                "{}: {} holds the {}, which doesn't fit the element class",
+                self.describe(heap_ref, method_area),
+                slot_name(),
+                self.describe(target, method_area)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether `target` may be stored in the object array.
+    fn is_array_element(
+        &self,
+        array_ref: HeapRef,
+        target: HeapRef,
+        method_area: &MethodArea,
+    ) -> bool {
+        let element_class_id = ClassId::new(self.get_header(array_ref).class_id);
+        let target_class_id = ClassId::new(self.get_header(target).class_id);
+        if !matches!(self.is_object_array(target), Ok(true)) {
+            return method_area.is_assignable_from(element_class_id, target_class_id);
+        }
+        // the header of a nested object array keeps its element class too, its own class may not
+        // even be loaded
+        match method_area.get_class(&element_class_id) {
+            JvmClass::InstanceArray(array_class) => {
+                method_area.is_assignable_from(array_class.element_class_id, target_class_id)
+            }
+            JvmClass::PrimitiveArray(_) => false,
+            class => {
+                let br = method_area.br();
+                [
+                    br.java_lang_object_sym,
+                    br.java_lang_cloneable_sym,
+                    br.java_io_serializable_sym,
+                ]
+                .contains(&class.get_name())
+            }
+        }
+    }
+
+    /// Address and class of a verified object, for the error messages.
+    fn describe(&self, heap_ref: HeapRef, method_area: &MethodArea) -> String {
+        let class_id = ClassId::new(self.get_header(heap_ref).class_id);
+        let class_name = if matches!(self.is_object_array(heap_ref), Ok(true)) {
+            method_area.get_object_array_class_name(class_id)
+        } else {
+            let name = method_area.get_class(&class_id).get_name();
+            method_area.interner().resolve(&name).to_string()
+        };
+        format!(
+            "object at {:#x} ({})",
+            heap_ref,
+            class_name.replace('/', ".")
+        )
+    }
+
+    fn describe_slot(&self, heap_ref: HeapRef, slot: usize, method_area: &MethodArea) -> String {
+        if self.get_header(heap_ref).is_array {
+            let index =
+                (slot - Self::ARRAY_ELEMENTS_OFFSET) / AllocationType::Reference.byte_size();
+            return format!("element [{}]", index);
+        }
+        let class_id = ClassId::new(self.get_header(heap_ref).class_id);
+        let field_name = method_area
+            .get_instance_class(&class_id)
+            .ok()
+            .and_then(|class| {
+                let fields = class.instance_fields.get()?;
+                class
+                    .instance_fields_offset_map
+                    .get()?
+                    .iter()
+                    .find(|(_, position)| fields[**position].offset == slot)
+                    .map(|(field_key, _)| field_key.name)
+            });
+        match field_name {
+            Some(name) => format!("field {}", method_area.interner().resolve(&name)),
+            None => format!("field at offset {}", slot),
+        }
+    }
+}
+
+fn broken(heap_ref: HeapRef) -> impl Fn(JvmError) -> String {
+    move |e| format!("object at {:#x}: {:?}", heap_ref, e)
+}
+
+fn element_allocation_type(element_type: &PrimitiveType) -> AllocationType {
+    match element_type {
+        PrimitiveType::Boolean => AllocationType::Boolean,
+        PrimitiveType::Byte => AllocationType::Byte,
+        PrimitiveType::Short => AllocationType::Short,
+        PrimitiveType::Char => AllocationType::Char,
+        PrimitiveType::Int => AllocationType::Int,
+        PrimitiveType::Long => AllocationType::Long,
+        PrimitiveType::Float => AllocationType::Float,
+        PrimitiveType::Double => AllocationType::Double,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jdwp::DebugState;
+    use crate::{VirtualMachine, VmConfig};
+    use lasso::ThreadedRodeo;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    // the bootstrap classes come from the JDK the integration tests run with
+    fn method_area_and_heap() -> (MethodArea, Heap) {
+        let config = VmConfig {
+            home: PathBuf::from(std::env::var("JAVA_HOME").expect("JAVA_HOME not set")),
+            version: "25.0.1".to_string(),
+            main_class: String::new(),
+            main_args: Vec::new(),
+            class_path: Vec::new(),
+            initial_heap_size: 1024 * 1024,
+            max_heap_size: 1024 * 1024,
+            nursery_size: 256 * 1024,
+            heap_dump_on_out_of_memory: false,
+            heap_dump_path: None,
+            heap_dump_on_ctrl_break: false,
+            print_class_histogram: false,
+            print_class_histogram_at_exit: false,
+            verify_heap: true,
+            frame_stack_size: 256,
+            jdwp_port: None,
+        };
+        let interner = Arc::new(ThreadedRodeo::default());
+        let (event_tx, _event_rx) = unbounded_channel();
+        let debug_state = Arc::new(DebugState::new(event_tx));
+        let (method_area, _) = MethodArea::init(&config, interner.clone(), debug_state).unwrap();
+        let heap = VirtualMachine::create_heap(&config, interner, &method_area).unwrap();
+        (method_area, heap)
+    }
+
+    #[test]
+    fn corrupted_field_is_reported_with_its_object() {
+        let (mut method_area, mut heap) = method_area_and_heap();
+        let string_ref = heap.alloc_string("corrupted").unwrap();
+        assert_eq!(heap.verify(&mut method_area, &mut []), Ok(()));
+
+        // String.value is the first field, it now points past the header of the string
+        let bogus_ref = string_ref + ObjectHeader::SIZE;
+        heap.write_field(
+            string_ref,
+            0,
+            Value::Ref(bogus_ref),
+            AllocationType::Reference,
+        )
+        .unwrap();
+
+        assert_eq!(
+            heap.verify(&mut method_area, &mut []),
+            Err(format!(
+                "object at {:#x} (java.lang.String): field value points to {:#x}, \
+                 which isn't the start of an object",
+                string_ref, bogus_ref
+            ))
+        );
+    }
+
+    #[test]
+    fn full_collection_leaves_no_stale_nursery_references() {
+        let (mut method_area, mut heap) = method_area_and_heap();
+        let object_class_id = method_area.br().get_java_lang_object_id().unwrap();
+        // too big for the nursery, both arrays are allocated in the old space
+        let old_referrer = heap.alloc_object_array(object_class_id, 10_000).unwrap();
+        let old_target = heap.alloc_object_array(object_class_id, 10_000).unwrap();
+        let young = heap.alloc_object_array(object_class_id, 1).unwrap();
+        assert!(!heap.nursery.contains(old_referrer));
+        assert!(heap.nursery.contains(young));
+
+        // nothing roots the three, the dirty card of the old referrer keeps the nursery object
+        // alive through the minor collection, the full one then frees both old arrays
+        let element = Heap::ARRAY_ELEMENTS_OFFSET;
+        heap.write_field(
+            young,
+            element,
+            Value::Ref(old_target),
+            AllocationType::Reference,
+        )
+        .unwrap();
+        heap.write_field(
+            old_referrer,
+            element,
+            Value::Ref(young),
+            AllocationType::Reference,
+        )
+        .unwrap();
+        heap.collect(&mut method_area, &mut [], true, false)
+            .unwrap();
+
+        assert_eq!(heap.verify(&mut method_area, &mut []), Ok(()));
+    }
+}
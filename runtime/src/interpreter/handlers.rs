@@ -1,5 +1,5 @@
 use crate::error::JvmError;
-use crate::interpreter::Interpreter;
+use crate::interpreter::{Interpreter, method_handles, string_concat};
 use crate::keys::{FieldKey, MethodKey};
 use crate::rt::constant_pool::RuntimeConstant;
 use crate::thread::JavaThreadState;
@@ -7,7 +7,6 @@ use crate::vm::Value;
 use crate::{VirtualMachine, throw_exception};
 use lagertha_common::instruction::{ArrayType, LookupSwitchData, TableSwitchData};
 use std::cmp::Ordering;
-use tracing_log::log::warn;
 
 fn branch16(bci: usize, off: i16) -> usize {
     ((bci as isize) + (off as isize)) as usize
@@ -95,11 +94,32 @@ pub(super) fn handle_baload(
     thread.stack.push_operand(value)
 }
 
-// TODO: stub
 #[inline]
-pub(super) fn handle_checkcast(thread: &mut JavaThreadState) -> Result<(), JvmError> {
-    let object_ref = thread.stack.pop_operand()?;
-    thread.stack.push_operand(object_ref)
+pub(super) fn handle_checkcast(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    idx: u16,
+) -> Result<(), JvmError> {
+    // the object stays on the stack, resolving through a user-defined loader may collect
+    if matches!(thread.stack.peek_operand_at(0)?, Value::Ref(_)) {
+        let cur_frame_method_id = thread.stack.cur_java_frame()?.method_id();
+        let class_name_sym = vm
+            .method_area_read()
+            .get_cp_by_method_id(&cur_frame_method_id)?
+            .get_class_sym(&idx, vm.interner())?;
+        let target_class_id = vm.resolve_class(thread, cur_frame_method_id, class_name_sym)?;
+        let obj_ref = thread.stack.peek_operand_at(0)?.as_obj_ref()?;
+        let obj_class_id = vm.get_object_class_id(obj_ref, thread.id)?;
+        let ma = vm.method_area_read();
+        if !ma.is_assignable_from(target_class_id, obj_class_id) {
+            throw_exception!(
+                ClassCastException,
+                from: ma.get_class(&obj_class_id).get_name(),
+                to: class_name_sym
+            )?
+        }
+    }
+    Ok(())
 }
 
 #[inline]
@@ -152,12 +172,10 @@ pub(super) fn handle_anewarray(
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_class_sym(&idx, vm.interner())?;
-    let target_array_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(target_array_sym, thread.id)?;
-    let array_ref = vm
-        .heap_write()
-        .alloc_object_array(target_array_class_id, size)?;
+    let target_array_class_id = vm.resolve_class(thread, cur_frame_method_id, target_array_sym)?;
+    let array_ref = vm.alloc_or_collect(thread, |heap| {
+        heap.alloc_object_array(target_array_class_id, size)
+    })?;
     thread.stack.push_operand(Value::Ref(array_ref))
 }
 
@@ -440,15 +458,18 @@ pub(super) fn handle_getfield(
     vm: &VirtualMachine,
     idx: u16,
 ) -> Result<(), JvmError> {
-    let target_obj_ref = thread.stack.pop_obj_val()?;
     let cur_frame_method_id = thread.stack.cur_java_frame()?.method_id();
     let field_view = vm
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_field_view(&idx, vm.interner())?;
-    let target_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(field_view.class_sym, thread.id)?;
+    let target_class_id = vm.resolve_class(thread, cur_frame_method_id, field_view.class_sym)?;
+    vm.check_loader_constraints(
+        cur_frame_method_id,
+        target_class_id,
+        field_view.name_and_type.descriptor_sym,
+    )?;
+    let target_obj_ref = thread.stack.pop_obj_val()?;
     let (target_field_offset, target_field_descriptor_id) = {
         let ma = vm.method_area_read();
         let target_field =
@@ -476,14 +497,18 @@ pub(super) fn handle_getstatic(
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_field_view(&idx, vm.interner())?;
-    let target_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(target_field_view.class_sym, thread.id)?;
+    let target_class_id =
+        vm.resolve_class(thread, cur_frame_method_id, target_field_view.class_sym)?;
     Interpreter::ensure_initialized(thread, Some(target_class_id), vm)?;
     let field_key: FieldKey = target_field_view.name_and_type.into();
     let actual_static_field_class_id = vm
         .method_area_read()
         .resolve_static_field_actual_class_id(target_class_id, &field_key)?;
+    vm.check_loader_constraints(
+        cur_frame_method_id,
+        actual_static_field_class_id,
+        field_key.desc,
+    )?;
     let value = vm
         .method_area_read()
         .get_static_field_value(&actual_static_field_class_id, &field_key)?;
@@ -909,6 +934,15 @@ pub(super) fn handle_invokevirtual(
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_method_view(&idx, vm.interner())?;
+    if method_handles::is_signature_polymorphic(vm, &target_method_view) {
+        return method_handles::invoke_signature_polymorphic(
+            thread,
+            vm,
+            cur_frame_method_id,
+            idx,
+            target_method_view,
+        );
+    }
     let method_key: MethodKey = target_method_view.name_and_type.into();
 
     let target_method_desc_id = vm
@@ -929,6 +963,11 @@ pub(super) fn handle_invokevirtual(
         .method_area_read()
         .get_class(&actual_class_id)
         .get_vtable_method_id(&method_key)?;
+    let target_method_class_id = vm
+        .method_area_read()
+        .get_method(&target_method_id)
+        .class_id();
+    vm.check_loader_constraints(cur_frame_method_id, target_method_class_id, method_key.desc)?;
     let args = Interpreter::prepare_method_args(thread, target_method_id, vm)?;
     Interpreter::invoke_method_internal(thread, target_method_id, args, vm)
 }
@@ -947,10 +986,18 @@ pub(super) fn handle_instanceof(
 
     let obj_ref = thread.stack.pop_nullable_ref_val()?;
     if let Some(obj_ref) = obj_ref {
-        let target_class = vm.heap_read().get_class_id(obj_ref)?;
-        let res = vm
-            .method_area_read()
-            .instance_of(target_class, class_name_sym);
+        let target_class = vm.get_object_class_id(obj_ref, thread.id)?;
+        let res = match vm.get_method_class_loader(cur_frame_method_id) {
+            // a bootstrap class that isn't loaded yet has no instances
+            None => vm
+                .method_area_read()
+                .instance_of(target_class, class_name_sym),
+            loader => {
+                let class_id = vm.load_class_with(thread, loader, class_name_sym)?;
+                vm.method_area_read()
+                    .is_assignable_from(class_id, target_class)
+            }
+        };
         thread
             .stack
             .push_operand(Value::Integer(if res { 1 } else { 0 }))
@@ -1212,18 +1259,34 @@ pub(super) fn handle_ldc_ldcw_ldc2w(
             RuntimeConstant::Class(class_entry) => {
                 let class_name_sym = class_entry.get_name_sym()?;
                 drop(ma);
-                let class_id = vm
-                    .method_area_write()
-                    .get_class_id_or_load(class_name_sym, thread.id)?;
-                Value::Ref(
-                    vm.method_area_write()
-                        .get_mirror_ref_or_create(class_id, &vm.heap)?,
-                )
+                let class_id = vm.resolve_class(thread, cur_method_id, class_name_sym)?;
+                Value::Ref(vm.get_mirror_ref_or_create(thread, class_id)?)
             }
             RuntimeConstant::String(str_entry) => {
                 let string_sym = str_entry.get_string_sym()?;
-                let string_ref = vm.heap_write().get_str_from_pool_or_new(string_sym)?;
-                Value::Ref(string_ref)
+                // the collector needs the method area
+                drop(ma);
+                Value::Ref(vm.get_str_from_pool_or_new(thread, string_sym)?)
+            }
+            RuntimeConstant::MethodType(_) => {
+                let desc_sym = cp.get_method_type_sym(&idx, vm.interner())?;
+                drop(ma);
+                Value::Ref(method_handles::resolve_method_type(
+                    thread,
+                    vm,
+                    cur_method_id,
+                    desc_sym,
+                )?)
+            }
+            RuntimeConstant::MethodHandle(_) => {
+                let handle_view = cp.get_method_handle_view(&idx, vm.interner())?;
+                drop(ma);
+                Value::Ref(method_handles::resolve_method_handle(
+                    thread,
+                    vm,
+                    cur_method_id,
+                    handle_view,
+                )?)
             }
             _ => unimplemented!(),
         }
@@ -1242,16 +1305,15 @@ pub(super) fn handle_new(
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_class_sym(&idx, vm.interner())?;
-    let target_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(target_class_name, thread.id)?;
+    let target_class_id = vm.resolve_class(thread, cur_frame_method_id, target_class_name)?;
     Interpreter::ensure_initialized(thread, Some(target_class_id), vm)?;
-    let instance_ref = vm.heap_write().alloc_instance(
-        vm.method_area_read()
-            .get_instance_class(&target_class_id)?
-            .get_instance_size()?,
-        target_class_id,
-    )?;
+    let instance_size = vm
+        .method_area_read()
+        .get_instance_class(&target_class_id)?
+        .get_instance_size()?;
+    let instance_ref = vm.alloc_or_collect(thread, |heap| {
+        heap.alloc_instance(instance_size, target_class_id)
+    })?;
     thread.stack.push_operand(Value::Ref(instance_ref))
 }
 
@@ -1269,9 +1331,9 @@ pub(super) fn handle_newarray(
         vm.interner().get_or_intern(array_type.descriptor()),
         thread.id,
     )?;
-    let array_ref = vm
-        .heap_write()
-        .alloc_primitive_array(class_id, array_type, size)?;
+    let array_ref = vm.alloc_or_collect(thread, |heap| {
+        heap.alloc_primitive_array(class_id, array_type, size)
+    })?;
     thread.stack.push_operand(Value::Ref(array_ref))
 }
 
@@ -1287,16 +1349,19 @@ pub(super) fn handle_putfield(
     vm: &VirtualMachine,
     idx: u16,
 ) -> Result<(), JvmError> {
-    let value = thread.stack.pop_operand()?;
-    let target_obj_ref = thread.stack.pop_obj_val()?;
     let cur_frame_method_id = thread.stack.cur_java_frame()?.method_id();
     let field_view = vm
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_field_view(&idx, vm.interner())?;
-    let target_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(field_view.class_sym, thread.id)?;
+    let target_class_id = vm.resolve_class(thread, cur_frame_method_id, field_view.class_sym)?;
+    vm.check_loader_constraints(
+        cur_frame_method_id,
+        target_class_id,
+        field_view.name_and_type.descriptor_sym,
+    )?;
+    let value = thread.stack.pop_operand()?;
+    let target_obj_ref = thread.stack.pop_obj_val()?;
     let (target_field_offset, target_field_descriptor_id) = {
         let ma = vm.method_area_read();
         let target_field =
@@ -1319,20 +1384,25 @@ pub(super) fn handle_putstatic(
     vm: &VirtualMachine,
     idx: u16,
 ) -> Result<(), JvmError> {
-    let value = thread.stack.pop_operand()?;
     let cur_frame_method_id = thread.stack.cur_java_frame()?.method_id();
     let target_field_view = vm
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_field_view(&idx, vm.interner())?;
-    let target_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(target_field_view.class_sym, thread.id)?;
+    let target_class_id =
+        vm.resolve_class(thread, cur_frame_method_id, target_field_view.class_sym)?;
     Interpreter::ensure_initialized(thread, Some(target_class_id), vm)?;
+    // popped after the initialization, the value stays rooted in the frame while <clinit> runs
+    let value = thread.stack.pop_operand()?;
     let field_key: FieldKey = target_field_view.name_and_type.into();
     let actual_static_field_class_id = vm
         .method_area_read()
         .resolve_static_field_actual_class_id(target_class_id, &field_key)?;
+    vm.check_loader_constraints(
+        cur_frame_method_id,
+        actual_static_field_class_id,
+        field_key.desc,
+    )?;
     vm.method_area_read()
         .get_class_like(&actual_static_field_class_id)?
         .set_static_field_value(&field_key, value)
@@ -1354,25 +1424,22 @@ pub(super) fn handle_invokeinterface(
         .stack
         .peek_operand_at(count as usize - 1)?
         .as_obj_ref()?;
-    if target_method_view.class_sym
-        == vm
-            .interner()
-            .get_or_intern("jdk/internal/access/JavaLangRefAccess")
-        && target_method_view.name_and_type.name_sym == vm.interner().get_or_intern("startThreads")
-    {
-        warn!("TODO: Stub: Ignoring call to jdk/internal/access/JavaLangRefAccess.startThreads");
-        for _ in 0..count {
-            let _ = thread.stack.pop_operand()?;
-        }
-    } else {
-        let target_class_id = vm.heap_read().get_class_id(object_ref)?;
-        let target_method_id = vm
-            .method_area_read()
-            .get_instance_class(&target_class_id)?
-            .get_interface_method_id(&target_method_view.name_and_type.into())?;
-        let args = Interpreter::prepare_method_args(thread, target_method_id, vm)?;
-        Interpreter::invoke_method_internal(thread, target_method_id, args, vm)?;
-    };
+    let target_class_id = vm.heap_read().get_class_id(object_ref)?;
+    let target_method_id = vm
+        .method_area_read()
+        .get_instance_class(&target_class_id)?
+        .get_interface_method_id(&target_method_view.name_and_type.into())?;
+    let target_method_class_id = vm
+        .method_area_read()
+        .get_method(&target_method_id)
+        .class_id();
+    vm.check_loader_constraints(
+        cur_frame_method_id,
+        target_method_class_id,
+        target_method_view.name_and_type.descriptor_sym,
+    )?;
+    let args = Interpreter::prepare_method_args(thread, target_method_id, vm)?;
+    Interpreter::invoke_method_internal(thread, target_method_id, args, vm)?;
     Ok(())
 }
 
@@ -1387,13 +1454,21 @@ pub(super) fn handle_invokespecial(
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_method_view(&idx, vm.interner())?;
-    let target_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(target_method_view.class_sym, thread.id)?;
+    let target_class_id =
+        vm.resolve_class(thread, cur_frame_method_id, target_method_view.class_sym)?;
     let target_method_id = vm
         .method_area_read()
         .get_instance_class(&target_class_id)?
         .get_special_method_id(&target_method_view.name_and_type.into())?;
+    let target_method_class_id = vm
+        .method_area_read()
+        .get_method(&target_method_id)
+        .class_id();
+    vm.check_loader_constraints(
+        cur_frame_method_id,
+        target_method_class_id,
+        target_method_view.name_and_type.descriptor_sym,
+    )?;
     let args = Interpreter::prepare_method_args(thread, target_method_id, vm)?;
     Interpreter::invoke_method_internal(thread, target_method_id, args, vm)
 }
@@ -1409,13 +1484,30 @@ pub(super) fn handle_invokestatic(
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
         .get_method_or_interface_method_view(&idx, vm.interner())?;
-    let target_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(target_method_view.class_sym, thread.id)?;
+    if method_handles::is_signature_polymorphic(vm, &target_method_view) {
+        return method_handles::invoke_signature_polymorphic(
+            thread,
+            vm,
+            cur_frame_method_id,
+            idx,
+            target_method_view,
+        );
+    }
+    let target_class_id =
+        vm.resolve_class(thread, cur_frame_method_id, target_method_view.class_sym)?;
     Interpreter::ensure_initialized(thread, Some(target_class_id), vm)?;
     let target_method_id = vm
         .method_area_read()
         .get_static_method_id(&target_class_id, target_method_view.name_and_type.into())?;
+    let target_method_class_id = vm
+        .method_area_read()
+        .get_method(&target_method_id)
+        .class_id();
+    vm.check_loader_constraints(
+        cur_frame_method_id,
+        target_method_class_id,
+        target_method_view.name_and_type.descriptor_sym,
+    )?;
     let args = Interpreter::prepare_method_args(thread, target_method_id, vm)?;
     Interpreter::invoke_static_method(thread, target_method_id, vm, args)
 }
@@ -1427,11 +1519,17 @@ pub(super) fn handle_invokedynamic(
     idx: u16,
 ) -> Result<(), JvmError> {
     let cur_frame_method_id = thread.stack.cur_java_frame()?.method_id();
-    let bootstrap_method = vm
+    if let Some(recipe) = string_concat::string_concat_recipe(vm, cur_frame_method_id, idx)? {
+        return string_concat::invoke_string_concat(thread, vm, &recipe);
+    }
+    let call_site_desc = vm
         .method_area_read()
         .get_cp_by_method_id(&cur_frame_method_id)?
-        .get_invoke_dynamic_view(&idx, vm.interner())?;
-    todo!()
+        .get_invoke_dynamic_view(&idx, vm.interner())?
+        .nat_view
+        .descriptor_sym;
+    let call_site = method_handles::link_call_site(thread, vm, cur_frame_method_id, idx)?;
+    method_handles::invoke_linked_call_site(thread, vm, call_site, call_site_desc, false)
 }
 
 #[inline]
@@ -1614,13 +1712,29 @@ pub(super) fn handle_tableswitch(
 }
 
 #[inline]
-pub(super) fn handle_monitorenter(thread: &mut JavaThreadState) -> Result<(), JvmError> {
-    let _obj = thread.stack.pop_obj_val()?;
+pub(super) fn handle_monitorenter(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+) -> Result<(), JvmError> {
+    let obj_ref = thread.stack.pop_obj_val()?;
+    let obj_ref = vm.monitor_enter(thread, obj_ref)?;
+    thread
+        .stack
+        .cur_java_frame_mut()?
+        .add_locked_monitor(obj_ref);
     Ok(())
 }
 
 #[inline]
-pub(super) fn handle_monitorexit(thread: &mut JavaThreadState) -> Result<(), JvmError> {
-    let _obj = thread.stack.pop_obj_val()?;
+pub(super) fn handle_monitorexit(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+) -> Result<(), JvmError> {
+    let obj_ref = thread.stack.pop_obj_val()?;
+    vm.monitor_exit(thread.id, obj_ref)?;
+    thread
+        .stack
+        .cur_java_frame_mut()?
+        .remove_locked_monitor(obj_ref);
     Ok(())
 }
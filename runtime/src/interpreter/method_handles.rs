@@ -0,0 +1,879 @@
+use crate::error::JvmError;
+use crate::heap::HeapRef;
+use crate::interpreter::Interpreter;
+use crate::keys::{ClassId, FieldKey, MethodKey};
+use crate::rt::JvmClass;
+use crate::rt::constant_pool::RuntimeConstant;
+use crate::rt::constant_pool::entry::{
+    InvokeDynamicEntryView, LinkedCallSite, MethodEntryView, MethodHandleEntryView,
+};
+use crate::thread::JavaThreadState;
+use crate::vm::Value;
+use crate::{MethodId, Symbol, VirtualMachine, build_exception, throw_exception};
+use lagertha_common::jtype::AllocationType;
+
+// Reference kinds, JVMS 5.4.3.5
+pub(crate) const REF_GET_FIELD: i32 = 1;
+pub(crate) const REF_GET_STATIC: i32 = 2;
+pub(crate) const REF_PUT_FIELD: i32 = 3;
+pub(crate) const REF_PUT_STATIC: i32 = 4;
+pub(crate) const REF_INVOKE_VIRTUAL: i32 = 5;
+pub(crate) const REF_INVOKE_STATIC: i32 = 6;
+pub(crate) const REF_INVOKE_SPECIAL: i32 = 7;
+pub(crate) const REF_NEW_INVOKE_SPECIAL: i32 = 8;
+pub(crate) const REF_INVOKE_INTERFACE: i32 = 9;
+
+// ResolvedMethodName has no declared fields, hotspot injects `vmtarget` into it, I do the same
+// by allocating a bit more space and storing the method id right after the declared fields.
+const RESOLVED_METHOD_NAME_VMTARGET_SIZE: usize = 8;
+
+const METHOD_HANDLE_SIGNATURE_POLYMORPHIC: [&str; 8] = [
+    "invoke",
+    "invokeExact",
+    "invokeBasic",
+    "linkToVirtual",
+    "linkToStatic",
+    "linkToSpecial",
+    "linkToInterface",
+    "linkToNative",
+];
+
+const VAR_HANDLE_SIGNATURE_POLYMORPHIC: [&str; 31] = [
+    "get",
+    "set",
+    "getVolatile",
+    "setVolatile",
+    "getAcquire",
+    "setRelease",
+    "getOpaque",
+    "setOpaque",
+    "compareAndSet",
+    "compareAndExchange",
+    "compareAndExchangeAcquire",
+    "compareAndExchangeRelease",
+    "weakCompareAndSetPlain",
+    "weakCompareAndSet",
+    "weakCompareAndSetAcquire",
+    "weakCompareAndSetRelease",
+    "getAndSet",
+    "getAndSetAcquire",
+    "getAndSetRelease",
+    "getAndAdd",
+    "getAndAddAcquire",
+    "getAndAddRelease",
+    "getAndBitwiseOr",
+    "getAndBitwiseOrRelease",
+    "getAndBitwiseOrAcquire",
+    "getAndBitwiseAnd",
+    "getAndBitwiseAndRelease",
+    "getAndBitwiseAndAcquire",
+    "getAndBitwiseXor",
+    "getAndBitwiseXorRelease",
+    "getAndBitwiseXorAcquire",
+];
+
+/// Splits a method descriptor like `(I[JLjava/lang/String;)V` into its parameter descriptors
+/// and return descriptor. A malformed descriptor comes from a bad class file.
+pub(crate) fn split_method_descriptor(desc: &str) -> Result<(Vec<&str>, &str), JvmError> {
+    let malformed = || build_exception!(ClassFormatError, "Malformed method descriptor: {}", desc);
+    if !desc.starts_with('(') {
+        return Err(malformed());
+    }
+    let params_end = desc.find(')').ok_or_else(malformed)?;
+    let params_str = &desc[1..params_end];
+    let mut params = Vec::new();
+    let mut pos = 0;
+    while pos < params_str.len() {
+        let len = field_descriptor_len(&params_str[pos..]).ok_or_else(malformed)?;
+        params.push(&params_str[pos..pos + len]);
+        pos += len;
+    }
+    let ret = &desc[params_end + 1..];
+    if ret != "V" && field_descriptor_len(ret) != Some(ret.len()) {
+        return Err(malformed());
+    }
+    Ok((params, ret))
+}
+
+/// Length of the field descriptor at the start of `desc`, `None` if there isn't a valid one.
+fn field_descriptor_len(desc: &str) -> Option<usize> {
+    let dims = desc.bytes().take_while(|b| *b == b'[').count();
+    match desc.as_bytes().get(dims)? {
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => Some(dims + 1),
+        b'L' => {
+            let name_len = desc[dims..].find(';')?;
+            // `L;` has no class name
+            if name_len < 2 {
+                return None;
+            }
+            Some(dims + name_len + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the class mirror for a field descriptor (or `V`), a class or array class is resolved
+/// by the loader of the caller class like a constant pool class reference.
+pub(crate) fn type_descriptor_to_mirror(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    desc: &str,
+) -> Result<HeapRef, JvmError> {
+    let br = vm.br();
+    let primitive_sym = match desc {
+        "I" => Some(br.int_sym),
+        "J" => Some(br.long_sym),
+        "F" => Some(br.float_sym),
+        "D" => Some(br.double_sym),
+        "B" => Some(br.byte_sym),
+        "C" => Some(br.char_sym),
+        "S" => Some(br.short_sym),
+        "Z" => Some(br.boolean_sym),
+        "V" => Some(br.void_sym),
+        _ => None,
+    };
+    let class_id = match primitive_sym {
+        Some(primitive_sym) => vm
+            .method_area_write()
+            .get_class_id_or_load(primitive_sym, thread.id)?,
+        None => {
+            let class_name = desc
+                .strip_prefix('L')
+                .and_then(|d| d.strip_suffix(';'))
+                .unwrap_or(desc);
+            let class_sym = vm.interner().get_or_intern(class_name);
+            vm.resolve_class(thread, caller_method_id, class_sym)?
+        }
+    };
+    vm.get_mirror_ref_or_create(thread, class_id)
+}
+
+/// Reverse of `type_descriptor_to_mirror`: the field descriptor of the class behind the mirror.
+pub(crate) fn mirror_to_type_descriptor(
+    vm: &VirtualMachine,
+    mirror: HeapRef,
+) -> Result<String, JvmError> {
+    let ma = vm.method_area_read();
+    let class_id = ma.get_class_id_by_mirror(&mirror)?;
+    let class = ma.get_class(&class_id);
+    let name = vm.interner().resolve(&class.get_name());
+    let br = vm.br();
+    let desc = match class {
+        JvmClass::Primitive(primitive) => match primitive.name {
+            s if s == br.int_sym => "I",
+            s if s == br.long_sym => "J",
+            s if s == br.float_sym => "F",
+            s if s == br.double_sym => "D",
+            s if s == br.byte_sym => "B",
+            s if s == br.char_sym => "C",
+            s if s == br.short_sym => "S",
+            s if s == br.boolean_sym => "Z",
+            _ => "V",
+        }
+        .to_string(),
+        JvmClass::PrimitiveArray(_) | JvmClass::InstanceArray(_) => name.to_string(),
+        JvmClass::Instance(_) | JvmClass::Interface(_) => format!("L{};", name),
+    };
+    Ok(desc)
+}
+
+/// Builds the method descriptor out of a `java.lang.invoke.MethodType` instance.
+pub(crate) fn method_type_to_descriptor(
+    vm: &VirtualMachine,
+    method_type: HeapRef,
+) -> Result<String, JvmError> {
+    let rtype_offset = field_offset(vm, method_type, &vm.br().method_type_rtype_fk)?;
+    let ptypes_offset = field_offset(vm, method_type, &vm.br().method_type_ptypes_fk)?;
+    let (rtype, ptypes) = {
+        let heap = vm.heap_read();
+        (
+            heap.read_field(method_type, rtype_offset, AllocationType::Reference)?
+                .as_obj_ref()?,
+            heap.read_field(method_type, ptypes_offset, AllocationType::Reference)?
+                .as_obj_ref()?,
+        )
+    };
+    let ptypes_len = vm.heap_read().get_array_length(ptypes)?;
+    let mut desc = String::from("(");
+    for i in 0..ptypes_len {
+        let ptype = vm.heap_read().read_array_element(ptypes, i)?.as_obj_ref()?;
+        desc.push_str(&mirror_to_type_descriptor(vm, ptype)?);
+    }
+    desc.push(')');
+    desc.push_str(&mirror_to_type_descriptor(vm, rtype)?);
+    Ok(desc)
+}
+
+pub(crate) fn field_offset(
+    vm: &VirtualMachine,
+    obj: HeapRef,
+    field_key: &FieldKey,
+) -> Result<usize, JvmError> {
+    let class_id = vm.heap_read().get_class_id(obj)?;
+    Ok(vm
+        .method_area_read()
+        .get_instance_field(&class_id, field_key)?
+        .offset)
+}
+
+/// Allocates a `java.lang.invoke.ResolvedMethodName` pointing to the given method.
+pub(crate) fn new_resolved_method_name(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    method_id: MethodId,
+) -> Result<HeapRef, JvmError> {
+    let class_id = vm
+        .method_area_write()
+        .get_class_id_or_load(vm.br().java_lang_invoke_resolved_method_name_sym, thread.id)?;
+    let instance_size = vm
+        .method_area_read()
+        .get_instance_class(&class_id)?
+        .get_instance_size()?;
+    vm.alloc_or_collect(thread, |heap| {
+        let resolved_ref =
+            heap.alloc_instance(instance_size + RESOLVED_METHOD_NAME_VMTARGET_SIZE, class_id)?;
+        heap.write_field(
+            resolved_ref,
+            instance_size,
+            Value::Integer(method_id.to_i32()),
+            AllocationType::Int,
+        )?;
+        Ok(resolved_ref)
+    })
+}
+
+/// Reads the method a resolved `java.lang.invoke.MemberName` points to.
+pub(crate) fn member_name_vmtarget(
+    vm: &VirtualMachine,
+    member_name: HeapRef,
+) -> Result<MethodId, JvmError> {
+    let method_offset = field_offset(vm, member_name, &vm.br().member_name_method_fk)?;
+    let resolved_ref = vm
+        .heap_read()
+        .read_field(member_name, method_offset, AllocationType::Reference)?
+        .as_nullable_obj_ref()?
+        .ok_or(build_exception!(
+            InternalError,
+            "MemberName is not resolved"
+        ))?;
+    let class_id = vm.heap_read().get_class_id(resolved_ref)?;
+    let instance_size = vm
+        .method_area_read()
+        .get_instance_class(&class_id)?
+        .get_instance_size()?;
+    let method_id = vm
+        .heap_read()
+        .read_field(resolved_ref, instance_size, AllocationType::Int)?
+        .as_int()?;
+    Ok(MethodId::from_i32(method_id))
+}
+
+fn static_method_for_result(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    method_id: MethodId,
+    args: Vec<Value>,
+) -> Result<HeapRef, JvmError> {
+    Interpreter::invoke_static_method_for_result(thread, method_id, vm, args)?
+        .ok_or(JvmError::Todo(
+            "Static method returned no value".to_string(),
+        ))?
+        .as_obj_ref()
+}
+
+fn method_handle_natives_method_id(
+    thread: &JavaThreadState,
+    vm: &VirtualMachine,
+    key: MethodKey,
+) -> Result<MethodId, JvmError> {
+    let class_id = vm.method_area_write().get_class_id_or_load(
+        vm.br().java_lang_invoke_method_handle_natives_sym,
+        thread.id,
+    )?;
+    vm.method_area_read().get_static_method_id(&class_id, key)
+}
+
+/// Mirror of the class referenced from the constant pool of the caller, resolved by its loader.
+fn class_mirror(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    class_sym: Symbol,
+) -> Result<HeapRef, JvmError> {
+    let class_id = vm.resolve_class(thread, caller_method_id, class_sym)?;
+    vm.get_mirror_ref_or_create(thread, class_id)
+}
+
+fn caller_class_mirror(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+) -> Result<HeapRef, JvmError> {
+    let caller_class_id = vm
+        .method_area_read()
+        .get_method(&caller_method_id)
+        .class_id();
+    vm.get_mirror_ref_or_create(thread, caller_class_id)
+}
+
+/// Pushes the results to the handles one after the other, every allocation may collect and
+/// move the references allocated before it.
+fn push_rooted(
+    thread: &mut JavaThreadState,
+    allocs: &[&dyn Fn(&mut JavaThreadState) -> Result<HeapRef, JvmError>],
+) -> Result<(), JvmError> {
+    for alloc in allocs {
+        let allocated = alloc(thread)?;
+        thread.handles.push(Value::Ref(allocated));
+    }
+    Ok(())
+}
+
+/// Resolves a method type descriptor into a `java.lang.invoke.MethodType` instance, its classes
+/// are resolved by the loader of the caller class.
+pub(super) fn resolve_method_type(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    desc_sym: Symbol,
+) -> Result<HeapRef, JvmError> {
+    let desc = vm.interner().resolve(&desc_sym);
+    let (params, ret) = split_method_descriptor(desc)?;
+    let class_class_id = vm.br().get_java_lang_class_id()?;
+    let ptypes = vm.alloc_or_collect(thread, |heap| {
+        heap.alloc_object_array(class_class_id, params.len() as i32)
+    })?;
+    // rooted while the mirrors are created, every one may collect
+    let mark = thread.push_handles(vec![Value::Ref(ptypes)]);
+    let rtype = params
+        .iter()
+        .enumerate()
+        .try_for_each(|(i, param)| {
+            let ptype = type_descriptor_to_mirror(thread, vm, caller_method_id, param)?;
+            let ptypes = thread.handles[mark].as_obj_ref()?;
+            vm.heap_write()
+                .write_array_element(ptypes, i as i32, Value::Ref(ptype))
+        })
+        .and_then(|_| type_descriptor_to_mirror(thread, vm, caller_method_id, ret));
+    let ptypes = thread.pop_handles(mark)[0].as_obj_ref()?;
+    let rtype = rtype?;
+    let find_method_type_id =
+        method_handle_natives_method_id(thread, vm, vm.br().mhn_find_method_handle_type_mk)?;
+    static_method_for_result(
+        thread,
+        vm,
+        find_method_type_id,
+        vec![Value::Ref(rtype), Value::Ref(ptypes)],
+    )
+}
+
+/// Resolves a `CONSTANT_MethodHandle` into a `java.lang.invoke.MethodHandle` instance.
+pub(super) fn resolve_method_handle(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    handle: MethodHandleEntryView,
+) -> Result<HeapRef, JvmError> {
+    let name_and_type = handle.name_and_type();
+    // the method type is resolved first, it runs java code that may move the other references
+    let type_ref = match handle {
+        MethodHandleEntryView::GetField(_)
+        | MethodHandleEntryView::GetStatic(_)
+        | MethodHandleEntryView::PutField(_)
+        | MethodHandleEntryView::PutStatic(_) => type_descriptor_to_mirror(
+            thread,
+            vm,
+            caller_method_id,
+            vm.interner().resolve(&name_and_type.descriptor_sym),
+        )?,
+        _ => resolve_method_type(thread, vm, caller_method_id, name_and_type.descriptor_sym)?,
+    };
+    let mark = thread.push_handles(vec![Value::Ref(type_ref)]);
+    let pushed = push_rooted(
+        thread,
+        &[
+            &|thread| caller_class_mirror(thread, vm, caller_method_id),
+            &|thread| class_mirror(thread, vm, caller_method_id, handle.class_sym()),
+            &|thread| vm.get_str_from_pool_or_new(thread, name_and_type.name_sym),
+        ],
+    );
+    let refs = thread.pop_handles(mark);
+    pushed?;
+    let link_constant_id =
+        method_handle_natives_method_id(thread, vm, vm.br().mhn_link_method_handle_constant_mk)?;
+    static_method_for_result(
+        thread,
+        vm,
+        link_constant_id,
+        vec![
+            refs[1],
+            Value::Integer(handle.ref_kind()),
+            refs[2],
+            refs[3],
+            refs[0],
+        ],
+    )
+}
+
+fn box_value(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    class_name: &str,
+    value_of_desc: &str,
+    value: Value,
+) -> Result<HeapRef, JvmError> {
+    let class_id = vm
+        .method_area_write()
+        .get_class_id_or_load(vm.interner().get_or_intern(class_name), thread.id)?;
+    let value_of_id = vm.method_area_read().get_static_method_id(
+        &class_id,
+        MethodKey {
+            name: vm.interner().get_or_intern("valueOf"),
+            desc: vm.interner().get_or_intern(value_of_desc),
+        },
+    )?;
+    static_method_for_result(thread, vm, value_of_id, vec![value])
+}
+
+pub(crate) fn box_long(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    value: i64,
+) -> Result<HeapRef, JvmError> {
+    box_value(
+        thread,
+        vm,
+        "java/lang/Long",
+        "(J)Ljava/lang/Long;",
+        Value::Long(value),
+    )
+}
+
+enum StaticArgument {
+    Boxed(&'static str, &'static str, Value),
+    Class(Symbol),
+    String(Symbol),
+    MethodType(Symbol),
+    MethodHandle(MethodHandleEntryView),
+}
+
+fn resolve_static_argument(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    idx: u16,
+) -> Result<HeapRef, JvmError> {
+    let argument = {
+        let ma = vm.method_area_read();
+        let cp = ma.get_cp_by_method_id(&caller_method_id)?;
+        match cp.get_constant(&idx, vm.interner())? {
+            RuntimeConstant::Integer(v) => StaticArgument::Boxed(
+                "java/lang/Integer",
+                "(I)Ljava/lang/Integer;",
+                Value::Integer(*v),
+            ),
+            RuntimeConstant::Float(v) => {
+                StaticArgument::Boxed("java/lang/Float", "(F)Ljava/lang/Float;", Value::Float(*v))
+            }
+            RuntimeConstant::Long(v) => {
+                StaticArgument::Boxed("java/lang/Long", "(J)Ljava/lang/Long;", Value::Long(*v))
+            }
+            RuntimeConstant::Double(v) => StaticArgument::Boxed(
+                "java/lang/Double",
+                "(D)Ljava/lang/Double;",
+                Value::Double(*v),
+            ),
+            RuntimeConstant::Class(entry) => StaticArgument::Class(entry.get_name_sym()?),
+            RuntimeConstant::String(entry) => StaticArgument::String(entry.get_string_sym()?),
+            RuntimeConstant::MethodType(_) => {
+                StaticArgument::MethodType(cp.get_method_type_sym(&idx, vm.interner())?)
+            }
+            RuntimeConstant::MethodHandle(_) => {
+                StaticArgument::MethodHandle(cp.get_method_handle_view(&idx, vm.interner())?)
+            }
+            other => throw_exception!(
+                InternalError,
+                "Unsupported bootstrap method argument: {}",
+                other.get_type()
+            )?,
+        }
+    };
+    match argument {
+        StaticArgument::Boxed(class_name, value_of_desc, value) => {
+            box_value(thread, vm, class_name, value_of_desc, value)
+        }
+        StaticArgument::Class(class_sym) => class_mirror(thread, vm, caller_method_id, class_sym),
+        StaticArgument::String(string_sym) => vm.get_str_from_pool_or_new(thread, string_sym),
+        StaticArgument::MethodType(desc_sym) => {
+            resolve_method_type(thread, vm, caller_method_id, desc_sym)
+        }
+        StaticArgument::MethodHandle(handle) => {
+            resolve_method_handle(thread, vm, caller_method_id, handle)
+        }
+    }
+}
+
+/// Static arguments are passed the same way hotspot does it:
+/// null if there are none, the argument itself if there is only one, Object[] otherwise.
+fn resolve_static_arguments(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    arguments: &[u16],
+) -> Result<Value, JvmError> {
+    match arguments {
+        [] => Ok(Value::Null),
+        [single] => Ok(Value::Ref(resolve_static_argument(
+            thread,
+            vm,
+            caller_method_id,
+            *single,
+        )?)),
+        _ => {
+            let object_class_id = vm.br().get_java_lang_object_id()?;
+            let array_ref = vm.alloc_or_collect(thread, |heap| {
+                heap.alloc_object_array(object_class_id, arguments.len() as i32)
+            })?;
+            // rooted while the arguments are resolved, that runs java code
+            let mark = thread.push_handles(vec![Value::Ref(array_ref)]);
+            let res = arguments.iter().enumerate().try_for_each(|(i, idx)| {
+                let argument = resolve_static_argument(thread, vm, caller_method_id, *idx)?;
+                let array_ref = thread.handles[mark].as_obj_ref()?;
+                vm.heap_write()
+                    .write_array_element(array_ref, i as i32, Value::Ref(argument))
+            });
+            let array = thread.pop_handles(mark);
+            res.map(|_| array[0])
+        }
+    }
+}
+
+fn linked_call_site(
+    vm: &VirtualMachine,
+    member_name: HeapRef,
+    appendix_box: HeapRef,
+) -> Result<LinkedCallSite, JvmError> {
+    let adapter = member_name_vmtarget(vm, member_name)?;
+    let appendix = vm
+        .heap_read()
+        .read_array_element(appendix_box, 0)?
+        .as_nullable_obj_ref()?;
+    Ok(LinkedCallSite { adapter, appendix })
+}
+
+/// Calls a `MethodHandleNatives` link method with the arguments followed by a fresh appendix
+/// box, the box stays rooted during the call, so the appendix can be read back from it.
+fn link_with_appendix(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    link_method_id: MethodId,
+    args: Vec<Value>,
+) -> Result<LinkedCallSite, JvmError> {
+    let object_class_id = vm.br().get_java_lang_object_id()?;
+    let (appendix_box, mut args) = thread.with_handles(args, |thread| {
+        vm.alloc_or_collect(thread, |heap| heap.alloc_object_array(object_class_id, 1))
+    });
+    let appendix_box = appendix_box?;
+    args.push(Value::Ref(appendix_box));
+    let mark = thread.push_handles(vec![Value::Ref(appendix_box)]);
+    let member_name = static_method_for_result(thread, vm, link_method_id, args);
+    let appendix_box = thread.pop_handles(mark)[0].as_obj_ref()?;
+    linked_call_site(vm, member_name?, appendix_box)
+}
+
+/// Pushes the `MethodHandleNatives.linkCallSite` arguments (all but the appendix box) to the
+/// handles, resolving the bootstrap method and its arguments runs java code, so the ones
+/// resolved before stay rooted.
+fn push_link_call_site_args(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    indy_view: InvokeDynamicEntryView,
+) -> Result<(), JvmError> {
+    let caller_mirror = caller_class_mirror(thread, vm, caller_method_id)?;
+    thread.handles.push(Value::Ref(caller_mirror));
+    let bootstrap_method =
+        resolve_method_handle(thread, vm, caller_method_id, indy_view.method_handle)?;
+    thread.handles.push(Value::Ref(bootstrap_method));
+    let name_ref = vm.get_str_from_pool_or_new(thread, indy_view.nat_view.name_sym)?;
+    thread.handles.push(Value::Ref(name_ref));
+    let type_ref = resolve_method_type(
+        thread,
+        vm,
+        caller_method_id,
+        indy_view.nat_view.descriptor_sym,
+    )?;
+    thread.handles.push(Value::Ref(type_ref));
+    let static_arguments =
+        resolve_static_arguments(thread, vm, caller_method_id, &indy_view.bootstrap_arguments)?;
+    thread.handles.push(static_arguments);
+    Ok(())
+}
+
+/// Links an invokedynamic call site: the bootstrap method is run by
+/// `MethodHandleNatives.linkCallSite`, which returns the adapter to call with the target
+/// `CallSite` in the appendix. The result is cached in the constant pool entry.
+pub(super) fn link_call_site(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    idx: u16,
+) -> Result<LinkedCallSite, JvmError> {
+    let (cached, indy_view) = {
+        let ma = vm.method_area_read();
+        let cp = ma.get_cp_by_method_id(&caller_method_id)?;
+        (
+            cp.get_linked_call_site(&idx)?,
+            cp.get_invoke_dynamic_view(&idx, vm.interner())?,
+        )
+    };
+    if let Some(call_site) = cached {
+        return Ok(call_site);
+    }
+    let mark = thread.handles.len();
+    let resolved = push_link_call_site_args(thread, vm, caller_method_id, indy_view);
+    let args = thread.pop_handles(mark);
+    resolved?;
+    let link_call_site_id =
+        method_handle_natives_method_id(thread, vm, vm.br().mhn_link_call_site_mk)?;
+    let call_site = link_with_appendix(thread, vm, link_call_site_id, args)?;
+    vm.method_area_read()
+        .get_cp_by_method_id(&caller_method_id)?
+        .set_linked_call_site(&idx, call_site)
+}
+
+/// Pops the call site arguments (described by `desc_sym`, plus the receiver if any), appends
+/// the appendix and calls the adapter, the result is pushed on the caller's operand stack.
+pub(super) fn invoke_linked_call_site(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    call_site: LinkedCallSite,
+    desc_sym: Symbol,
+    has_receiver: bool,
+) -> Result<(), JvmError> {
+    let mut args_count = method_params_count(vm, desc_sym)?;
+    if has_receiver {
+        args_count += 1;
+    }
+    let mut args = pop_args(thread, args_count)?;
+    if let Some(appendix) = call_site.appendix {
+        args.push(Value::Ref(appendix));
+    }
+    Interpreter::invoke_static_method(thread, call_site.adapter, vm, args)
+}
+
+/// Number of parameters of a call site or signature polymorphic descriptor, a malformed one
+/// comes from a bad class file.
+fn method_params_count(vm: &VirtualMachine, desc_sym: Symbol) -> Result<usize, JvmError> {
+    let desc_id = vm
+        .method_area_write()
+        .get_or_new_method_descriptor_id(&desc_sym)
+        .map_err(|_| {
+            build_exception!(
+                ClassFormatError,
+                "Malformed method descriptor: {}",
+                vm.interner().resolve(&desc_sym)
+            )
+        })?;
+    Ok(vm
+        .method_area_read()
+        .get_method_descriptor(&desc_id)
+        .params
+        .len())
+}
+
+fn pop_args(thread: &mut JavaThreadState, args_count: usize) -> Result<Vec<Value>, JvmError> {
+    let mut args = Vec::with_capacity(args_count + 1);
+    for _ in 0..args_count {
+        args.push(thread.stack.pop_operand()?);
+    }
+    args.reverse();
+    Ok(args)
+}
+
+/// JVMS 2.9.3, `MethodHandle` and `VarHandle` native varargs methods
+pub(super) fn is_signature_polymorphic(vm: &VirtualMachine, method_view: &MethodEntryView) -> bool {
+    let name = vm.interner().resolve(&method_view.name_and_type.name_sym);
+    if method_view.class_sym == vm.br().java_lang_invoke_method_handle_sym {
+        METHOD_HANDLE_SIGNATURE_POLYMORPHIC.contains(&name)
+    } else if method_view.class_sym == vm.br().java_lang_invoke_var_handle_sym {
+        VAR_HANDLE_SIGNATURE_POLYMORPHIC.contains(&name)
+    } else {
+        false
+    }
+}
+
+/// `invokeBasic` and `linkTo*` are the VM intrinsics the lambda forms are built of,
+/// anything else (`invokeExact`, `invoke`, VarHandle access modes) is linked through
+/// `MethodHandleNatives.linkMethod`, the same way as an invokedynamic call site.
+pub(super) fn invoke_signature_polymorphic(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    idx: u16,
+    method_view: MethodEntryView,
+) -> Result<(), JvmError> {
+    let desc_sym = method_view.name_and_type.descriptor_sym;
+    let params_count = method_params_count(vm, desc_sym)?;
+    let name = vm.interner().resolve(&method_view.name_and_type.name_sym);
+    match name {
+        "invokeBasic" => {
+            let method_handle = thread.stack.peek_operand_at(params_count)?.as_obj_ref()?;
+            let target_id = method_handle_vmtarget(vm, method_handle)?;
+            let args = pop_args(thread, params_count + 1)?;
+            Interpreter::invoke_method_internal(thread, target_id, args, vm)
+        }
+        "linkToStatic" | "linkToSpecial" | "linkToVirtual" | "linkToInterface" => {
+            let mut args = pop_args(thread, params_count)?;
+            let member_name = args
+                .pop()
+                .ok_or(JvmError::OperandStackIsEmpty)?
+                .as_obj_ref()?;
+            let mut target_id = member_name_vmtarget(vm, member_name)?;
+            if name == "linkToVirtual" || name == "linkToInterface" {
+                let receiver = args
+                    .first()
+                    .ok_or(JvmError::OperandStackIsEmpty)?
+                    .as_obj_ref()?;
+                let receiver_class_id = vm.heap_read().get_class_id(receiver)?;
+                let ma = vm.method_area_read();
+                let target = ma.get_method(&target_id);
+                let key = MethodKey {
+                    name: target.name,
+                    desc: target.desc,
+                };
+                let dispatched = if name == "linkToVirtual" {
+                    ma.get_class(&receiver_class_id).get_vtable_method_id(&key)
+                } else {
+                    ma.get_instance_class(&receiver_class_id)?
+                        .get_interface_method_id(&key)
+                };
+                // private and final methods aren't in the vtable, they are called directly
+                target_id = dispatched.unwrap_or(target_id);
+            }
+            let args = if name == "linkToStatic" {
+                let class_id = vm.method_area_read().get_method(&target_id).class_id();
+                Interpreter::ensure_initialized_with_args(thread, class_id, vm, args)?
+            } else {
+                args
+            };
+            Interpreter::invoke_method_internal(thread, target_id, args, vm)
+        }
+        _ => {
+            let call_site =
+                link_signature_polymorphic(thread, vm, caller_method_id, idx, method_view)?;
+            invoke_linked_call_site(thread, vm, call_site, desc_sym, true)
+        }
+    }
+}
+
+fn method_handle_vmtarget(
+    vm: &VirtualMachine,
+    method_handle: HeapRef,
+) -> Result<MethodId, JvmError> {
+    let form_offset = field_offset(vm, method_handle, &vm.br().method_handle_form_fk)?;
+    let form = vm
+        .heap_read()
+        .read_field(method_handle, form_offset, AllocationType::Reference)?
+        .as_obj_ref()?;
+    let vmentry_offset = field_offset(vm, form, &vm.br().lambda_form_vmentry_fk)?;
+    let vmentry = vm
+        .heap_read()
+        .read_field(form, vmentry_offset, AllocationType::Reference)?
+        .as_obj_ref()?;
+    member_name_vmtarget(vm, vmentry)
+}
+
+fn link_signature_polymorphic(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    idx: u16,
+    method_view: MethodEntryView,
+) -> Result<LinkedCallSite, JvmError> {
+    if let Some(call_site) = vm
+        .method_area_read()
+        .get_cp_by_method_id(&caller_method_id)?
+        .get_linked_call_site(&idx)?
+    {
+        return Ok(call_site);
+    }
+    // the method type is resolved first, it runs java code that may move the other references
+    let type_ref = resolve_method_type(
+        thread,
+        vm,
+        caller_method_id,
+        method_view.name_and_type.descriptor_sym,
+    )?;
+    let mark = thread.push_handles(vec![Value::Ref(type_ref)]);
+    let pushed = push_rooted(
+        thread,
+        &[
+            &|thread| caller_class_mirror(thread, vm, caller_method_id),
+            &|thread| class_mirror(thread, vm, caller_method_id, method_view.class_sym),
+            &|thread| vm.get_str_from_pool_or_new(thread, method_view.name_and_type.name_sym),
+        ],
+    );
+    let refs = thread.pop_handles(mark);
+    pushed?;
+    let link_method_id = method_handle_natives_method_id(thread, vm, vm.br().mhn_link_method_mk)?;
+    let call_site = link_with_appendix(
+        thread,
+        vm,
+        link_method_id,
+        vec![
+            refs[1],
+            Value::Integer(REF_INVOKE_VIRTUAL),
+            refs[2],
+            refs[3],
+            refs[0],
+        ],
+    )?;
+    vm.method_area_read()
+        .get_cp_by_method_id(&caller_method_id)?
+        .set_linked_call_site(&idx, call_site)
+}
+
+/// Finds the method a `MemberName` with the given reference kind refers to,
+/// used by `MethodHandleNatives.resolve`.
+pub(crate) fn find_method_for_ref_kind(
+    vm: &VirtualMachine,
+    class_id: ClassId,
+    ref_kind: i32,
+    key: &MethodKey,
+) -> Result<Option<MethodId>, JvmError> {
+    let ma = vm.method_area_read();
+    let class = ma.get_class(&class_id);
+    let method_id = match (ref_kind, class) {
+        (REF_INVOKE_STATIC, _) => ma.get_static_method_id(&class_id, *key).ok(),
+        (REF_INVOKE_SPECIAL | REF_NEW_INVOKE_SPECIAL, JvmClass::Instance(ic)) => {
+            ic.get_special_method_id_opt(key)
+        }
+        (REF_INVOKE_VIRTUAL | REF_INVOKE_INTERFACE, JvmClass::Instance(ic)) => ic
+            .get_vtable_method_id(key)
+            .ok()
+            .or_else(|| ic.get_special_method_id_opt(key)),
+        (_, JvmClass::Interface(i)) => match i.get_methods().get(key) {
+            Some(method_id) => Some(*method_id),
+            // interfaces implicitly have public methods of Object
+            None => ma
+                .get_instance_class(&vm.br().get_java_lang_object_id()?)?
+                .get_vtable_method_id(key)
+                .ok(),
+        },
+        (_, JvmClass::PrimitiveArray(_) | JvmClass::InstanceArray(_)) => {
+            class.get_vtable_method_id(key).ok()
+        }
+        _ => None,
+    };
+    Ok(method_id)
+}
+
+/// Kinds of method handle constants that refer to fields.
+pub(crate) fn is_field_ref_kind(ref_kind: i32) -> bool {
+    matches!(
+        ref_kind,
+        REF_GET_FIELD | REF_GET_STATIC | REF_PUT_FIELD | REF_PUT_STATIC
+    )
+}
@@ -8,13 +8,15 @@ use crate::thread::JavaThreadState;
 use crate::vm::Value;
 use crate::vm::stack::{FrameType, JavaFrame, NativeFrame};
 use crate::{MethodId, VirtualMachine, build_exception, debug_log_instruction, error_log_method};
-use lagertha_common::instruction::Instruction;
 use lagertha_classfile::attribute::method::ExceptionTableEntry;
+use lagertha_common::instruction::Instruction;
 use std::ops::ControlFlow;
 use tracing_log::log::warn;
 
 mod handlers;
+pub(crate) mod method_handles;
 mod return_handlers;
+pub(crate) mod string_concat;
 
 pub struct Interpreter;
 
@@ -38,7 +40,7 @@ impl Interpreter {
             Instruction::Iaload => handle_iaload(thread, vm)?,
             Instruction::Caload => handle_caload(thread, vm)?,
             Instruction::Baload => handle_baload(thread, vm)?,
-            Instruction::Checkcast(_idx) => handle_checkcast(thread)?,
+            Instruction::Checkcast(idx) => handle_checkcast(thread, vm, idx)?,
             Instruction::AconstNull => handle_aconst_null(thread)?,
             Instruction::Aload0 => handle_aload0(thread)?,
             Instruction::Aload1 => handle_aload1(thread)?,
@@ -193,8 +195,8 @@ impl Interpreter {
             Instruction::Sastore => handle_sastore(thread, vm)?,
             Instruction::Sipush(value) => handle_sipush(thread, value)?,
             Instruction::TableSwitch(switch) => handle_tableswitch(thread, switch)?,
-            Instruction::Monitorenter => handle_monitorenter(thread)?,
-            Instruction::Monitorexit => handle_monitorexit(thread)?,
+            Instruction::Monitorenter => handle_monitorenter(thread, vm)?,
+            Instruction::Monitorexit => handle_monitorexit(thread, vm)?,
             Instruction::Return => {
                 return Ok(ControlFlow::Break(None));
             }
@@ -268,14 +270,17 @@ impl Interpreter {
         }
 
         let exception_class_id = vm.heap_read().get_class_id(java_exception)?;
-        let catch_type_sym = vm
-            .method_area_read()
+        let ma = vm.method_area_read();
+        let catch_type_sym = ma
             .get_cp_by_method_id(method_id)?
             .get_class_sym(&catch_type, vm.interner())?;
-
-        Ok(vm
-            .method_area_read()
-            .instance_of(exception_class_id, catch_type_sym))
+        // nothing can be loaded here, a catch type the method's loader hasn't seen yet is looked
+        // up by name like for the bootstrap classes
+        let loader = ma.get_class_loader_id(&ma.get_method(method_id).class_id());
+        match ma.find_loaded_class(loader, catch_type_sym) {
+            Some(catch_class_id) => Ok(ma.is_assignable_from(catch_class_id, exception_class_id)),
+            None => Ok(ma.instance_of(exception_class_id, catch_type_sym)),
+        }
     }
 
     fn find_exception_handler(
@@ -315,6 +320,7 @@ impl Interpreter {
             // SAFETY: code_ptr is valid as long as method exists in method area (always)
             // need to use pointer to avoid borrow checker issues
             let code = unsafe { &*code_ptr };
+            vm.safepoint_poll(thread)?;
             let pc = thread.stack.pc()?;
             let instruction = Instruction::new_at(code, pc)?;
 
@@ -337,7 +343,12 @@ impl Interpreter {
                         thread.stack.pop_native_frame()?;
                     }
                     if !Self::find_exception_handler(vm, &method_id, java_exception, thread)? {
-                        thread.stack.pop_java_frame()?;
+                        let frame = thread.stack.pop_java_frame()?;
+                        // javac guards synchronized blocks with handlers, but other bytecode
+                        // may leave monitors locked
+                        for obj_ref in frame.locked_monitors().iter().rev() {
+                            vm.monitor_exit(thread.id, *obj_ref)?;
+                        }
                         return Err(JvmError::JavaExceptionThrown(java_exception));
                     }
                 }
@@ -369,7 +380,7 @@ impl Interpreter {
         {
             method_key.class = None;
         }
-        let frame = NativeFrame::new(method_id);
+        let frame = NativeFrame::new(method_id, args.clone());
         thread.stack.push_frame(FrameType::NativeFrame(frame))?;
         let native = vm.native_registry.get(&method_key).ok_or(build_exception!(
             UnsatisfiedLinkError,
@@ -418,18 +429,52 @@ impl Interpreter {
     fn invoke_method_core(
         thread: &mut JavaThreadState,
         method_id: MethodId,
-        args: Vec<Value>,
+        mut args: Vec<Value>,
         vm: &VirtualMachine,
     ) -> Result<Option<Value>, JvmError> {
-        let is_native = {
+        let (is_native, is_synchronized, is_static, class_id) = {
             let ma = vm.method_area_read();
-            ma.get_method(&method_id).is_native()
+            let method = ma.get_method(&method_id);
+            (
+                method.is_native(),
+                method.is_synchronized(),
+                method.is_static(),
+                method.class_id(),
+            )
+        };
+        // JVMS 2.11.10, static methods lock the class mirror, instance methods lock `this`
+        let sync_obj = match (is_synchronized, is_static) {
+            (false, _) => None,
+            (true, true) => {
+                // the arguments aren't in a frame yet
+                let (mirror_ref, rooted) = thread
+                    .with_handles(args, |thread| vm.get_mirror_ref_or_create(thread, class_id));
+                args = rooted;
+                Some(mirror_ref?)
+            }
+            (true, false) => Some(args[0].as_obj_ref()?),
         };
-        if is_native {
+        let (args, sync_mark) = match sync_obj {
+            Some(obj_ref) => {
+                // the arguments aren't in a frame yet
+                let mark = thread.push_handles(args);
+                let obj_ref = vm.monitor_enter(thread, obj_ref)?;
+                let args = thread.pop_handles(mark);
+                // the locked object stays rooted until it's unlocked, the method may move it
+                (args, Some(thread.push_handles(vec![Value::Ref(obj_ref)])))
+            }
+            None => (args, None),
+        };
+        let method_ret = if is_native {
             Self::invoke_native_method(thread, method_id, args, vm)
         } else {
             Self::invoke_java_method(thread, method_id, args, vm)
+        };
+        if let Some(mark) = sync_mark {
+            let obj_ref = thread.pop_handles(mark)[0].as_obj_ref()?;
+            vm.monitor_exit(thread.id, obj_ref)?;
         }
+        method_ret
     }
 
     fn invoke_method_internal(
@@ -481,17 +526,71 @@ impl Interpreter {
             return Ok(());
         };
 
-        {
-            let ma = vm.method_area_read();
-            let class = ma.get_class_like(&class_id)?;
+        if !Self::start_initialization(thread, class_id, vm)? {
+            return Ok(());
+        }
+        let res = Self::initialize_class(thread, class_id, vm);
+        if res.is_ok() {
+            vm.method_area_read()
+                .get_class_like(&class_id)?
+                .set_initialized();
+        }
+        vm.class_init_threads.lock().unwrap().remove(&class_id);
+        vm.class_init_cv.notify_all();
+        res
+    }
 
-            if class.is_initialized_or_initializing() {
-                return Ok(());
-            }
+    /// `ensure_initialized` for a call whose arguments are already popped, they are rooted while
+    /// the class initialization runs java code.
+    fn ensure_initialized_with_args(
+        thread: &mut JavaThreadState,
+        class_id: ClassId,
+        vm: &VirtualMachine,
+        args: Vec<Value>,
+    ) -> Result<Vec<Value>, JvmError> {
+        let mark = thread.push_handles(args);
+        let res = Self::ensure_initialized(thread, Some(class_id), vm);
+        let args = thread.pop_handles(mark);
+        res.map(|_| args)
+    }
 
-            class.set_initializing();
+    /// Claims the initialization of the class for the thread, waiting while another thread
+    /// initializes it (JVMS 5.5). Returns false if the thread has nothing to initialize.
+    fn start_initialization(
+        thread: &mut JavaThreadState,
+        class_id: ClassId,
+        vm: &VirtualMachine,
+    ) -> Result<bool, JvmError> {
+        let mut init_threads = vm.class_init_threads.lock().unwrap();
+        loop {
+            {
+                let ma = vm.method_area_read();
+                let class = ma.get_class_like(&class_id)?;
+                if !class.is_initialized_or_initializing() {
+                    class.set_initializing();
+                    init_threads.insert(class_id, thread.id);
+                    return Ok(true);
+                }
+            }
+            match init_threads.get(&class_id) {
+                Some(init_thread_id) if *init_thread_id != thread.id => {
+                    // entered with the lock held, a notification can't be missed
+                    vm.safepoint.enter_safe_region(thread);
+                    drop(vm.class_init_cv.wait(init_threads).unwrap());
+                    vm.safepoint.leave_safe_region(thread.id);
+                    init_threads = vm.class_init_threads.lock().unwrap();
+                }
+                // already initialized, or a recursive request of the initializing thread
+                _ => return Ok(false),
+            }
         }
+    }
 
+    fn initialize_class(
+        thread: &mut JavaThreadState,
+        class_id: ClassId,
+        vm: &VirtualMachine,
+    ) -> Result<(), JvmError> {
         let (is_instance, is_interface) = {
             let ma = vm.method_area_read();
             let jvm_class = ma.get_class(&class_id);
@@ -555,10 +654,6 @@ impl Interpreter {
 
             Self::run_clinit_if_exists(thread, class_id, vm)?;
         }
-
-        vm.method_area_read()
-            .get_class_like(&class_id)?
-            .set_initialized();
         Ok(())
     }
 
@@ -572,6 +667,21 @@ impl Interpreter {
         Self::invoke_method_core(thread, method_id, args, vm)
     }
 
+    /// Runs the constructor on the freshly allocated instance in `args[0]` and returns the
+    /// instance, the constructor may run a collection that moves it.
+    pub fn invoke_constructor(
+        thread: &mut JavaThreadState,
+        method_id: MethodId,
+        vm: &VirtualMachine,
+        args: Vec<Value>,
+    ) -> Result<HeapRef, JvmError> {
+        let mark = thread.push_handles(vec![args[0]]);
+        let res = Self::invoke_method_core(thread, method_id, args, vm);
+        let instance = thread.pop_handles(mark)[0];
+        res?;
+        instance.as_obj_ref()
+    }
+
     pub fn invoke_static_method(
         thread: &mut JavaThreadState,
         method_id: MethodId,
@@ -579,8 +689,21 @@ impl Interpreter {
         args: Vec<Value>,
     ) -> Result<(), JvmError> {
         let class_id = vm.method_area_read().get_method(&method_id).class_id();
-        Self::ensure_initialized(thread, Some(class_id), vm)?;
+        let args = Self::ensure_initialized_with_args(thread, class_id, vm, args)?;
         Self::invoke_method_internal(thread, method_id, args, vm)?;
         Ok(())
     }
+
+    /// Same as `invoke_static_method`, but the result is returned instead of being pushed
+    /// on the operand stack, used for upcalls from the VM into java code
+    pub fn invoke_static_method_for_result(
+        thread: &mut JavaThreadState,
+        method_id: MethodId,
+        vm: &VirtualMachine,
+        args: Vec<Value>,
+    ) -> Result<Option<Value>, JvmError> {
+        let class_id = vm.method_area_read().get_method(&method_id).class_id();
+        let args = Self::ensure_initialized_with_args(thread, class_id, vm, args)?;
+        Self::invoke_method_core(thread, method_id, args, vm)
+    }
 }
@@ -0,0 +1,160 @@
+use crate::error::JvmError;
+use crate::interpreter::Interpreter;
+use crate::interpreter::method_handles::{REF_INVOKE_STATIC, split_method_descriptor};
+use crate::keys::MethodKey;
+use crate::rt::constant_pool::RuntimeConstant;
+use crate::rt::constant_pool::entry::InvokeDynamicEntryView;
+use crate::rt::constant_pool::string_concat::{
+    StringConcatArg, StringConcatPart, StringConcatRecipe,
+};
+use crate::thread::JavaThreadState;
+use crate::vm::Value;
+use crate::{MethodId, VirtualMachine};
+use std::sync::Arc;
+
+/// Returns the recipe if the invokedynamic at `idx` is bootstrapped by
+/// `StringConcatFactory.makeConcatWithConstants` and can be concatenated natively, the recipe
+/// (or its absence) is cached in the constant pool entry so it is parsed only once.
+pub(super) fn string_concat_recipe(
+    vm: &VirtualMachine,
+    caller_method_id: MethodId,
+    idx: u16,
+) -> Result<Option<Arc<StringConcatRecipe>>, JvmError> {
+    let ma = vm.method_area_read();
+    let cp = ma.get_cp_by_method_id(&caller_method_id)?;
+    cp.get_or_init_string_concat_recipe(&idx, || {
+        let indy_view = cp.get_invoke_dynamic_view(&idx, vm.interner())?;
+        if !is_string_concat_bootstrap(vm, &indy_view) {
+            return Ok(None);
+        }
+        let Some((recipe_idx, constant_idxs)) = indy_view.bootstrap_arguments.split_first() else {
+            return Ok(None);
+        };
+        let recipe_sym = match cp.get_constant(recipe_idx, vm.interner())? {
+            RuntimeConstant::String(_) => cp.get_string_sym(recipe_idx, vm.interner())?,
+            _ => return Ok(None),
+        };
+        let mut constants = Vec::with_capacity(constant_idxs.len());
+        for constant_idx in constant_idxs {
+            let constant = match cp.get_constant(constant_idx, vm.interner())? {
+                RuntimeConstant::String(_) => vm
+                    .interner()
+                    .resolve(&cp.get_string_sym(constant_idx, vm.interner())?)
+                    .to_string(),
+                RuntimeConstant::Integer(value) => value.to_string(),
+                RuntimeConstant::Long(value) => value.to_string(),
+                // float and double formatting is left to the bootstrap method
+                _ => return Ok(None),
+            };
+            constants.push(constant);
+        }
+        let desc = vm.interner().resolve(&indy_view.nat_view.descriptor_sym);
+        let (params, _) = split_method_descriptor(desc)?;
+        let args = params
+            .into_iter()
+            .map(StringConcatArg::from_descriptor)
+            .collect();
+        Ok(StringConcatRecipe::parse(
+            vm.interner().resolve(&recipe_sym),
+            constants,
+            args,
+        ))
+    })
+}
+
+fn is_string_concat_bootstrap(vm: &VirtualMachine, indy_view: &InvokeDynamicEntryView) -> bool {
+    let method_handle = &indy_view.method_handle;
+    method_handle.ref_kind() == REF_INVOKE_STATIC
+        && method_handle.class_sym() == vm.br().java_lang_invoke_string_concat_factory_sym
+        && method_handle.name_and_type().name_sym == vm.br().make_concat_with_constants_sym
+}
+
+/// Concatenates the call site arguments according to the recipe, pops them and pushes
+/// the resulting string.
+pub(super) fn invoke_string_concat(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    recipe: &StringConcatRecipe,
+) -> Result<(), JvmError> {
+    let args_count = recipe.args.len();
+    let mut result = Vec::new();
+    for part in &recipe.parts {
+        match part {
+            StringConcatPart::Literal(literal) => result.extend_from_slice(literal),
+            StringConcatPart::Argument(i) => {
+                // the arguments stay on the operand stack, String.valueOf upcalls may collect
+                let value = *thread.stack.peek_operand_at(args_count - 1 - i)?;
+                append_argument(thread, vm, &mut result, recipe.args[*i], value)?
+            }
+        }
+    }
+    for _ in 0..args_count {
+        thread.stack.pop_operand()?;
+    }
+
+    let string_ref = vm.alloc_or_collect(thread, |heap| heap.alloc_string_from_utf16(&result))?;
+    thread.stack.push_operand(Value::Ref(string_ref))
+}
+
+fn append_argument(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    result: &mut Vec<u16>,
+    arg: StringConcatArg,
+    value: Value,
+) -> Result<(), JvmError> {
+    let mut push_str = |s: &str| result.extend(s.encode_utf16());
+    match arg {
+        StringConcatArg::Int => push_str(&value.as_int()?.to_string()),
+        StringConcatArg::Long => push_str(&value.as_long()?.to_string()),
+        StringConcatArg::Boolean => push_str(if value.as_int()? != 0 {
+            "true"
+        } else {
+            "false"
+        }),
+        StringConcatArg::Char => result.push(value.as_int()? as u16),
+        // java formatting of floating point numbers differs from rust's one
+        StringConcatArg::Float => {
+            let key = vm.br().string_value_of_float_mk;
+            result.extend(string_value_of(thread, vm, key, value)?)
+        }
+        StringConcatArg::Double => {
+            let key = vm.br().string_value_of_double_mk;
+            result.extend(string_value_of(thread, vm, key, value)?)
+        }
+        StringConcatArg::Reference => match value.as_nullable_obj_ref()? {
+            None => push_str("null"),
+            Some(obj_ref) => {
+                let is_string =
+                    vm.heap_read().get_class_id(obj_ref)? == vm.br().get_java_lang_string_id()?;
+                let string = if is_string {
+                    vm.heap_read().get_utf16_from_java_string(obj_ref)?
+                } else {
+                    let key = vm.br().string_value_of_object_mk;
+                    string_value_of(thread, vm, key, value)?
+                };
+                result.extend(string)
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Calls one of the `String.valueOf` overloads, used when the formatting must match java's.
+fn string_value_of(
+    thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    key: MethodKey,
+    value: Value,
+) -> Result<Vec<u16>, JvmError> {
+    let method_id = vm
+        .method_area_read()
+        .get_static_method_id(&vm.br().get_java_lang_string_id()?, key)?;
+    let string_ref =
+        Interpreter::invoke_static_method_for_result(thread, method_id, vm, vec![value])?
+            .ok_or(JvmError::Todo(
+                "String.valueOf returned no value".to_string(),
+            ))?
+            .as_obj_ref()?;
+    vm.heap_read().get_utf16_from_java_string(string_ref)
+}
@@ -131,6 +131,19 @@ impl FieldId {
     }
 }
 
+#[repr(transparent)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ClassLoaderId(NonZeroU32);
+
+impl ClassLoaderId {
+    pub fn from_usize(index: usize) -> Self {
+        ClassLoaderId(NonZeroU32::new(index as u32).unwrap())
+    }
+    pub fn to_index(&self) -> usize {
+        (self.0.get() - 1) as usize
+    }
+}
+
 pub type Symbol = Spur;
 
 #[derive(Hash, Eq, PartialEq, Clone)]
@@ -1,18 +1,30 @@
-use crate::error::{JavaExceptionFromJvm, JvmError};
+use crate::error::{JavaExceptionFromJvm, JavaExceptionKind, JvmError};
+use crate::heap::histogram::ClassHistogram;
 use crate::heap::method_area::MethodArea;
 use crate::heap::{Heap, HeapRef};
 use crate::interpreter::Interpreter;
 use crate::jdwp::agent::start_jdwp_agent;
 use crate::jdwp::{DebugEvent, DebugState};
-use crate::keys::{MethodId, MethodKey, Symbol, ThreadId};
+use crate::keys::{ClassId, ClassLoaderId, FieldKey, MethodId, MethodKey, Symbol, ThreadId};
 use crate::native::NativeRegistry;
-use crate::thread::JavaThreadState;
+use crate::thread::{
+    IdentityHashGenerator, JavaThreadState, THREAD_STATUS_RUNNABLE, THREAD_STATUS_TERMINATED,
+    ThreadRegistry,
+};
 use crate::vm::Value;
 use crate::vm::bootstrap_registry::BootstrapRegistry;
+use crate::vm::monitor::MonitorTable;
+use crate::vm::safepoint::Safepoint;
+use crate::vm::signal::start_signal_dispatcher;
 use crate::vm::stack::FrameStack;
+use lagertha_classfile::ClassFile;
+use lagertha_common::jtype::AllocationType;
 use lasso::ThreadedRodeo;
-use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock, Weak};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::unbounded_channel;
 
 mod class_loader;
@@ -27,14 +39,29 @@ pub mod rt;
 mod thread;
 mod vm;
 
+pub use class_loader::JarManifest;
+
 #[derive(Debug, Clone)]
 pub struct VmConfig {
     pub home: PathBuf,
     pub version: String,
     pub main_class: String,
+    pub main_args: Vec<String>,
     pub class_path: Vec<String>,
+    // bytes, the nursery included
     pub initial_heap_size: usize,
     pub max_heap_size: usize,
+    // bytes, split in two semispaces
+    pub nursery_size: usize,
+    pub heap_dump_on_out_of_memory: bool,
+    // file or directory of the heap dumps, java_pid<pid>.hprof in the working directory if unset
+    pub heap_dump_path: Option<PathBuf>,
+    // on SIGQUIT
+    pub heap_dump_on_ctrl_break: bool,
+    pub print_class_histogram: bool,
+    pub print_class_histogram_at_exit: bool,
+    // checks the heap before and after every collection, aborts if it is corrupted
+    pub verify_heap: bool,
     pub frame_stack_size: usize,
     pub jdwp_port: Option<u16>,
 }
@@ -55,10 +82,29 @@ pub struct VirtualMachine {
     config: VmConfig,
     method_area: RwLock<MethodArea>,
     heap: RwLock<Heap>,
+    monitors: MonitorTable,
+    threads: ThreadRegistry,
+    safepoint: Safepoint,
+    // set by the heap once it fills up, the next thread reaching a safepoint collects
+    gc_requested: Arc<AtomicBool>,
+    // like hotspot, only the first OutOfMemoryError dumps the heap
+    heap_dumped_on_out_of_memory: AtomicBool,
+    // set on SIGQUIT, the next thread reaching a safepoint dumps the heap
+    heap_dump_requested: AtomicBool,
+    // dumps written so far, the later ones get it as a suffix so they don't overwrite the first
+    heap_dump_count: AtomicUsize,
+    // threads running <clinit>, others wait on the condvar until they are done (JVMS 5.5)
+    class_init_threads: Mutex<HashMap<ClassId, ThreadId>>,
+    class_init_cv: Condvar,
+    // the Reference Handler thread waits on the condvar until a collection clears references
+    reference_pending_lock: Mutex<()>,
+    reference_pending_cv: Condvar,
     native_registry: NativeRegistry,
     string_interner: Arc<ThreadedRodeo>,
     br: Arc<BootstrapRegistry>,
     debug_state: Arc<DebugState>,
+    // lets natives hand the VM over to the threads they start
+    this: Weak<VirtualMachine>,
 }
 
 impl VirtualMachine {
@@ -76,26 +122,44 @@ impl VirtualMachine {
                     eprintln!("Caused by: {}", e.into_pretty_string(&string_interner));
                 },
             )?;
-        let heap = Self::create_heap(string_interner.clone(), &method_area).map_err(|e| {
-            eprintln!("Error: Could not initialize JVM.");
-            eprintln!("Caused by: {}", e.into_pretty_string(&string_interner));
-        })?;
+        let heap =
+            Self::create_heap(&config, string_interner.clone(), &method_area).map_err(|e| {
+                eprintln!("Error: Could not initialize JVM.");
+                eprintln!("Caused by: {}", e.into_pretty_string(&string_interner));
+            })?;
+        let gc_requested = heap.gc_requested_flag();
 
         let native_registry = NativeRegistry::new(string_interner.clone());
 
-        let vm = Arc::new(Self {
+        let vm = Arc::new_cyclic(|this| Self {
             config,
             native_registry,
             string_interner: string_interner.clone(),
             method_area: RwLock::new(method_area),
             heap: RwLock::new(heap),
+            monitors: MonitorTable::default(),
+            threads: ThreadRegistry::default(),
+            safepoint: Safepoint::default(),
+            gc_requested,
+            heap_dumped_on_out_of_memory: AtomicBool::new(false),
+            heap_dump_requested: AtomicBool::new(false),
+            heap_dump_count: AtomicUsize::new(0),
+            class_init_threads: Mutex::new(HashMap::new()),
+            class_init_cv: Condvar::new(),
+            reference_pending_lock: Mutex::new(()),
+            reference_pending_cv: Condvar::new(),
             br,
             debug_state: debug_state.clone(),
+            this: this.clone(),
         });
 
         #[cfg(feature = "log-runtime-traces")]
         log_traces::debug::init(&vm);
 
+        if vm.config.print_class_histogram || vm.config.heap_dump_on_ctrl_break {
+            start_signal_dispatcher(Arc::downgrade(&vm));
+        }
+
         if let Some(jdwp_port) = vm.config.jdwp_port {
             start_jdwp_agent(vm.clone(), debug_state.clone(), event_rx, jdwp_port);
             debug_state.send_event(DebugEvent::VMStart);
@@ -133,10 +197,17 @@ impl VirtualMachine {
              */
         })?;
 
+        vm.initialize_built_in_class_loaders(&mut main_thread)
+            .map_err(|e| {
+                eprintln!("Error: Could not initialize JVM.");
+                eprintln!("Caused by: {}", e.into_pretty_string(&string_interner));
+            })?;
+
         Ok((vm, main_thread))
     }
 
     fn create_heap(
+        config: &VmConfig,
         interner: Arc<ThreadedRodeo>,
         method_area: &MethodArea,
     ) -> Result<Heap, JvmError> {
@@ -147,7 +218,9 @@ impl VirtualMachine {
             .get_instance_class(&string_class_id)?
             .get_instance_size()?;
         Heap::new(
-            1, // TODO: from config
+            config.initial_heap_size,
+            config.max_heap_size,
+            config.nursery_size,
             interner,
             string_class_id,
             string_instance_size,
@@ -175,6 +248,7 @@ impl VirtualMachine {
                 Value::Ref(main_thread.name),
             ],
         )?;
+        self.set_thread_status(main_thread.thread_obj, THREAD_STATUS_RUNNABLE)?;
         Ok(())
     }
 
@@ -184,22 +258,97 @@ impl VirtualMachine {
             .method_area_read()
             .get_instance_class(&thread_class_id)?
             .get_instance_size()?;
+        // there is no thread to run a collection on yet, these are the first allocations
         let main_thread_ref = self
             .heap_write()
             .alloc_instance(thread_instance_size, thread_class_id)?;
         let main_string_ref = self
             .heap_write()
             .get_str_from_pool_or_new(self.br().main_sym)?;
+        let thread_id = self.threads.next_id();
+        self.set_thread_eetop(main_thread_ref, thread_id.as_usize() as i64)?;
+        self.threads.register(thread_id, false);
+        self.safepoint.attach();
         let thread = JavaThreadState {
-            id: ThreadId::from_index(0), // TODO: hardcoded for main thread
+            id: thread_id,
             thread_obj: main_thread_ref,
             group_obj: 0,
             name: main_string_ref,
             stack: FrameStack::new(&self.config),
+            handles: Vec::new(),
+            identity_hashes: IdentityHashGenerator::new(thread_id),
         };
         Ok(thread)
     }
 
+    /// Selects the main method like the java launcher does since JEP 512: `main(String[])` is
+    /// preferred over `main()`, either one can be static or an instance method, declared in the
+    /// main class or inherited, as long as it isn't private.
+    fn find_main_method(&self, main_class_id: ClassId) -> Result<Option<MethodId>, JvmError> {
+        let ma = self.method_area_read();
+        for main_mk in [self.br().main_mk, self.br().main_no_args_mk] {
+            let mut class_id = Some(main_class_id);
+            while let Some(cur_class_id) = class_id {
+                let class = ma.get_instance_class(&cur_class_id)?;
+                if let Some(method_id) = class.get_declared_method_id_opt(&main_mk) {
+                    if !ma.get_method(&method_id).is_private() {
+                        return Ok(Some(method_id));
+                    }
+                }
+                class_id = class.get_super();
+            }
+        }
+        Ok(None)
+    }
+
+    /// Non-private no-arg constructor used to instantiate the main class for an instance main.
+    fn find_main_constructor(&self, main_class_id: ClassId) -> Result<Option<MethodId>, JvmError> {
+        let ma = self.method_area_read();
+        let main_class = ma.get_instance_class(&main_class_id)?;
+        if main_class.flags().is_abstract() {
+            return Ok(None);
+        }
+        Ok(main_class
+            .get_declared_method_id_opt(&self.br().no_arg_constructor_mk)
+            .filter(|constructor_id| !ma.get_method(constructor_id).is_private()))
+    }
+
+    fn create_main_instance(
+        &self,
+        main_thread: &mut JavaThreadState,
+        main_class_id: ClassId,
+        constructor_id: MethodId,
+    ) -> Result<HeapRef, JvmError> {
+        Interpreter::ensure_initialized(main_thread, Some(main_class_id), self)?;
+        let instance_size = self
+            .method_area_read()
+            .get_instance_class(&main_class_id)?
+            .get_instance_size()?;
+        let instance = self.alloc_or_collect(main_thread, |heap| {
+            heap.alloc_instance(instance_size, main_class_id)
+        })?;
+        Interpreter::invoke_constructor(
+            main_thread,
+            constructor_id,
+            self,
+            vec![Value::Ref(instance)],
+        )
+    }
+
+    /// `String[]` with the program arguments, passed to `main`.
+    fn create_main_args(&self, main_thread: &mut JavaThreadState) -> Result<HeapRef, JvmError> {
+        let string_class_id = self.br().get_java_lang_string_id()?;
+        self.alloc_or_collect(main_thread, |heap| {
+            let args_ref =
+                heap.alloc_object_array(string_class_id, self.config.main_args.len() as i32)?;
+            for (i, arg) in self.config.main_args.iter().enumerate() {
+                let arg_ref = heap.alloc_string(arg)?;
+                heap.write_array_element(args_ref, i as i32, Value::Ref(arg_ref))?;
+            }
+            Ok(args_ref)
+        })
+    }
+
     fn create_system_thread_group(
         &self,
         main_thread: &mut JavaThreadState,
@@ -213,17 +362,15 @@ impl VirtualMachine {
                 thread_group_class.get_instance_size()?,
             )
         };
-        let system_thread_group_ref = self
-            .heap_write()
-            .alloc_instance(thread_group_instance_size, system_thread_group_class_id)?;
-        Interpreter::invoke_instance_method(
+        let system_thread_group_ref = self.alloc_or_collect(main_thread, |heap| {
+            heap.alloc_instance(thread_group_instance_size, system_thread_group_class_id)
+        })?;
+        Interpreter::invoke_constructor(
             main_thread,
             thread_group_no_arg_constructor_id,
             self,
             vec![Value::Ref(system_thread_group_ref)],
-        )?;
-
-        Ok(system_thread_group_ref)
+        )
     }
 
     fn create_main_thread_group(
@@ -242,23 +389,24 @@ impl VirtualMachine {
                 thread_group_class.get_instance_size()?,
             )
         };
-        let main_thread_group_ref = self
-            .heap_write()
-            .alloc_instance(thread_group_instance_size, system_thread_group_class_id)?;
-        let main_string_ref = self
-            .heap_write()
-            .get_str_from_pool_or_new(self.br().main_sym)?;
-        Interpreter::invoke_instance_method(
+        let (main_thread_group_ref, rooted) =
+            main_thread.with_handles(vec![Value::Ref(system_thread_group_ref)], |main_thread| {
+                self.alloc_or_collect(main_thread, |heap| {
+                    heap.alloc_instance(thread_group_instance_size, system_thread_group_class_id)
+                })
+            });
+        // the group is named "main" like the thread
+        let main_string_ref = main_thread.name;
+        Interpreter::invoke_constructor(
             main_thread,
             thread_group_constructor_id,
             self,
             vec![
-                Value::Ref(main_thread_group_ref),
-                Value::Ref(system_thread_group_ref),
+                Value::Ref(main_thread_group_ref?),
+                rooted[0],
                 Value::Ref(main_string_ref),
             ],
-        )?;
-        Ok(main_thread_group_ref)
+        )
     }
 
     fn initialize_system_class(&self, thread: &mut JavaThreadState) -> Result<(), JvmError> {
@@ -296,6 +444,49 @@ impl VirtualMachine {
         Ok(())
     }
 
+    /// Registers the loaders of the platform modules and of the classpath classes,
+    /// `ClassLoader.getSystemClassLoader()` is the built-in app loader while the system isn't
+    /// fully initialized.
+    fn initialize_built_in_class_loaders(
+        &self,
+        thread: &mut JavaThreadState,
+    ) -> Result<(), JvmError> {
+        let platform_loader_ref = self.invoke_class_loader_getter(
+            thread,
+            self.br().class_loader_get_platform_class_loader_mk,
+        )?;
+        self.method_area_write()
+            .set_platform_class_loader(platform_loader_ref);
+        let app_loader_ref = self.invoke_class_loader_getter(
+            thread,
+            self.br().class_loader_get_system_class_loader_mk,
+        )?;
+        self.method_area_write()
+            .set_app_class_loader(app_loader_ref);
+        Ok(())
+    }
+
+    fn invoke_class_loader_getter(
+        &self,
+        thread: &mut JavaThreadState,
+        method_key: MethodKey,
+    ) -> Result<HeapRef, JvmError> {
+        let class_loader_class_id = self
+            .method_area_write()
+            .get_class_id_or_load(self.br().java_lang_class_loader_sym, thread.id)?;
+        let method_id = self
+            .method_area_read()
+            .get_static_method_id(&class_loader_class_id, method_key)?;
+        match Interpreter::invoke_static_method_for_result(thread, method_id, self, vec![])? {
+            Some(Value::Ref(loader_ref)) => Ok(loader_ref),
+            _ => throw_exception!(
+                InternalError,
+                "ClassLoader.{} returned no class loader",
+                self.interner().resolve(&method_key.name)
+            ),
+        }
+    }
+
     // TODO: refactor and improve error handling. ideally can't fail
     //TODO: exception arg should be actually JvmError, like any error
     fn map_rust_error_to_java_exception(
@@ -325,24 +516,26 @@ impl VirtualMachine {
                 class.get_instance_size()?,
             )
         };
-        let instance = self.heap_write().alloc_instance(instance_size, class_id)?;
+        let instance =
+            self.alloc_or_collect(thread, |heap| heap.alloc_instance(instance_size, class_id))?;
         let params = if let Some(msg) = exception.message {
             let resolved_msg = msg.into_resolved(self.interner());
-            vec![
-                Value::Ref(instance),
-                Value::Ref(self.heap_write().alloc_string(&resolved_msg)?),
-            ]
+            let (msg_ref, rooted) = thread.with_handles(vec![Value::Ref(instance)], |thread| {
+                self.alloc_string(thread, &resolved_msg)
+            });
+            vec![rooted[0], Value::Ref(msg_ref?)]
         } else {
             vec![Value::Ref(instance)]
         };
-        Interpreter::invoke_instance_method(thread, method_id, self, params)?;
-        Ok(instance)
+        Interpreter::invoke_constructor(thread, method_id, self, params)
     }
 
     //TODO: exception should be allocated on java heap at this point, and be a reference
     //TODO: get rid of unwrap, need to understand how to handle errors here properly
     fn unhandled_exception(&self, thread: &mut JavaThreadState, exception: JvmError) {
         if let JvmError::JavaExceptionThrown(exception_ref) = exception {
+            // rooted during the getThreadGroup upcall
+            let mark = thread.push_handles(vec![Value::Ref(exception_ref)]);
             let get_thread_group_method_id = self
                 .method_area_read()
                 .get_class(&self.br().get_java_lang_thread_id().unwrap())
@@ -358,6 +551,7 @@ impl VirtualMachine {
             .unwrap()
             .as_obj_ref()
             .unwrap();
+            let exception = thread.pop_handles(mark)[0];
             let uncaught_exception_method_id = self
                 .method_area_read()
                 .get_class(&self.br().get_java_lang_thread_group_id().unwrap())
@@ -370,7 +564,7 @@ impl VirtualMachine {
                 vec![
                     Value::Ref(thread_group_ref),
                     Value::Ref(thread.thread_obj),
-                    Value::Ref(exception_ref),
+                    exception,
                 ],
             )
             .unwrap();
@@ -402,6 +596,885 @@ impl VirtualMachine {
         method_desc.to_java_signature(class_name, method_name)
     }
 
+    /// Class of the given object, for object arrays the heap only keeps the element class.
+    pub fn get_object_class_id(
+        &self,
+        obj_ref: HeapRef,
+        thread_id: ThreadId,
+    ) -> Result<ClassId, JvmError> {
+        let (class_id, is_object_array) = {
+            let heap = self.heap_read();
+            (heap.get_class_id(obj_ref)?, heap.is_object_array(obj_ref)?)
+        };
+        if is_object_array {
+            self.method_area_write()
+                .get_object_array_class_id(class_id, thread_id)
+        } else {
+            Ok(class_id)
+        }
+    }
+
+    /// Loader of the class declaring the method, `None` for the bootstrap loader.
+    pub fn get_method_class_loader(&self, method_id: MethodId) -> Option<ClassLoaderId> {
+        let ma = self.method_area_read();
+        let class_id = ma.get_method(&method_id).class_id();
+        ma.get_class_loader_id(&class_id)
+    }
+
+    /// Resolves a class referenced by the method through the loader of its declaring class, a
+    /// class no loader finds is a `NoClassDefFoundError` like in hotspot.
+    pub fn resolve_class(
+        &self,
+        thread: &mut JavaThreadState,
+        referrer: MethodId,
+        name_sym: Symbol,
+    ) -> Result<ClassId, JvmError> {
+        let loader = self.get_method_class_loader(referrer);
+        match self.load_class_with(thread, loader, name_sym) {
+            Err(JvmError::JavaException(exception))
+                if exception.kind == JavaExceptionKind::ClassNotFoundException =>
+            {
+                throw_exception!(NoClassDefFoundError, self.interner().resolve(&name_sym))
+            }
+            loaded => loaded,
+        }
+    }
+
+    /// Loads the class as seen by the loader, `None` being the bootstrap loader (JVMS 5.3).
+    /// A user-defined loader is asked through `loadClass` and recorded as initiating loader of
+    /// the class it returns, the platform and app loaders are the VM's own (see
+    /// `load_built_in_class`). Runs Java code, so the caller must not hold unrooted references.
+    pub fn load_class_with(
+        &self,
+        thread: &mut JavaThreadState,
+        loader: Option<ClassLoaderId>,
+        name_sym: Symbol,
+    ) -> Result<ClassId, JvmError> {
+        let Some(loader_id) = loader else {
+            return self
+                .method_area_write()
+                .get_class_id_or_load(name_sym, thread.id);
+        };
+        if let Some(class_id) = self.method_area_read().find_loaded_class(loader, name_sym) {
+            return Ok(class_id);
+        }
+        let name = self.interner().resolve(&name_sym);
+        if let Some(component) = name.strip_prefix('[') {
+            if component.starts_with('[') || component.starts_with('L') {
+                let component_name = component
+                    .strip_prefix('L')
+                    .and_then(|component| component.strip_suffix(';'))
+                    .unwrap_or(component);
+                let component_sym = self.interner().get_or_intern(component_name);
+                self.load_class_with(thread, loader, component_sym)?;
+            }
+            return self
+                .method_area_write()
+                .load_array_class_with(loader, name_sym, thread.id);
+        }
+        if let Some(from_class_path) = self.method_area_read().is_built_in_class_loader(loader_id) {
+            return self.load_built_in_class(thread, loader_id, from_class_path, name_sym);
+        }
+
+        let dotted_name = self.symbol_to_pretty_string(name_sym);
+        let name_ref = self.alloc_or_collect(thread, |heap| heap.alloc_string(&dotted_name))?;
+        let loader_ref = self.method_area_read().get_class_loader_ref(loader_id);
+        let loader_class_id = self.heap_read().get_class_id(loader_ref)?;
+        let load_class_method_id = self
+            .method_area_read()
+            .get_class(&loader_class_id)
+            .get_vtable_method_id(&self.br().class_loader_load_class_mk)?;
+        let class_ref = Interpreter::invoke_instance_method(
+            thread,
+            load_class_method_id,
+            self,
+            vec![Value::Ref(loader_ref), Value::Ref(name_ref)],
+        )?;
+        let class_id = match class_ref {
+            Some(Value::Ref(class_ref)) => {
+                self.method_area_read().get_class_id_by_mirror(&class_ref)?
+            }
+            _ => throw_exception!(NoClassDefFoundError, self.interner().resolve(&name_sym))?,
+        };
+        let loaded_name_sym = self.method_area_read().get_class(&class_id).get_name();
+        if loaded_name_sym != name_sym {
+            throw_exception!(
+                NoClassDefFoundError,
+                "{} (wrong name: {})",
+                self.interner().resolve(&name_sym),
+                self.interner().resolve(&loaded_name_sym)
+            )?
+        }
+        self.method_area_write()
+            .record_loaded_class(loader, name_sym, class_id)?;
+        Ok(class_id)
+    }
+
+    /// The platform loader delegates to the bootstrap loader and the app loader to the platform
+    /// loader, then they define the class from the image or the classpath, the VM does both
+    /// instead of running `loadClass` for every class of theirs.
+    fn load_built_in_class(
+        &self,
+        thread: &mut JavaThreadState,
+        loader_id: ClassLoaderId,
+        from_class_path: bool,
+        name_sym: Symbol,
+    ) -> Result<ClassId, JvmError> {
+        let loader = Some(loader_id);
+        let parent = if from_class_path {
+            self.method_area_read().get_platform_class_loader_id()
+        } else {
+            None
+        };
+        match self.load_class_with(thread, parent, name_sym) {
+            Ok(class_id) => {
+                self.method_area_write()
+                    .record_loaded_class(loader, name_sym, class_id)?;
+                return Ok(class_id);
+            }
+            Err(JvmError::JavaException(exception))
+                if exception.kind == JavaExceptionKind::ClassNotFoundException => {}
+            Err(e) => return Err(e),
+        }
+        let name = self.interner().resolve(&name_sym);
+        let bytes = if from_class_path {
+            self.method_area_read().load_from_class_path(name)?
+        } else {
+            self.method_area_read().load_from_platform_modules(name)?
+        };
+        let cf = MethodArea::parse_class_file(bytes)?;
+        let actual_name = MethodArea::get_class_file_name(&cf)?;
+        if actual_name != name {
+            throw_exception!(
+                NoClassDefFoundError,
+                "{} (wrong name: {})",
+                name,
+                actual_name
+            )?
+        }
+        let loader_ref = self.method_area_read().get_class_loader_ref(loader_id);
+        match self.define_class(thread, Some(loader_ref), cf, false) {
+            Ok(class_id) => Ok(class_id),
+            // another thread may have defined it in the meantime
+            Err(e) => self
+                .method_area_read()
+                .find_loaded_class(loader, name_sym)
+                .ok_or(e),
+        }
+    }
+
+    /// Defines a class for the loader, `None` being the bootstrap loader. A user-defined loader
+    /// loads the superclass and the superinterfaces first, they are resolved through it.
+    pub fn define_class(
+        &self,
+        thread: &mut JavaThreadState,
+        loader_ref: Option<HeapRef>,
+        cf: ClassFile,
+        is_hidden: bool,
+    ) -> Result<ClassId, JvmError> {
+        let loader = loader_ref.map(|loader_ref| {
+            self.method_area_write()
+                .get_or_register_class_loader(loader_ref)
+        });
+        if loader.is_some() {
+            for super_name in MethodArea::get_super_type_names(&cf)? {
+                let super_sym = self.interner().get_or_intern(super_name);
+                self.load_class_with(thread, loader, super_sym)?;
+            }
+        }
+        self.method_area_write()
+            .define_class(cf, loader, is_hidden, thread.id)
+    }
+
+    /// Loader constraints of a member of another class used by the method, there are none while
+    /// both classes have the same loader.
+    pub fn check_loader_constraints(
+        &self,
+        referrer: MethodId,
+        member_class_id: ClassId,
+        descriptor_sym: Symbol,
+    ) -> Result<(), JvmError> {
+        let referrer_class_id = {
+            let ma = self.method_area_read();
+            let referrer_class_id = ma.get_method(&referrer).class_id();
+            let referrer_loader = ma.get_class_loader_id(&referrer_class_id);
+            let member_loader = ma.get_class_loader_id(&member_class_id);
+            // checked once per descriptor, classpath classes use the bootstrap ones all the time
+            if referrer_loader == member_loader
+                || ma.has_loader_constraints(referrer_loader, member_loader, descriptor_sym)
+            {
+                return Ok(());
+            }
+            referrer_class_id
+        };
+        self.method_area_write().add_loader_constraints(
+            referrer_class_id,
+            member_class_id,
+            descriptor_sym,
+        )
+    }
+
+    /// Blocks in a safe region while another thread owns the monitor.
+    /// Returns the object, which may have been moved by a collection while the thread blocked.
+    pub fn monitor_enter(
+        &self,
+        thread: &mut JavaThreadState,
+        obj_ref: HeapRef,
+    ) -> Result<HeapRef, JvmError> {
+        let monitor = self.monitors.get_or_inflate(&self.heap, obj_ref);
+        let thread_id = thread.id;
+        let mark = thread.push_handles(vec![Value::Ref(obj_ref)]);
+        self.safepoint.enter_safe_region(thread);
+        monitor.enter(thread_id);
+        self.safepoint.leave_safe_region(thread_id);
+        thread.pop_handles(mark)[0].as_obj_ref()
+    }
+
+    pub fn monitor_exit(&self, thread_id: ThreadId, obj_ref: HeapRef) -> Result<(), JvmError> {
+        self.monitors
+            .get_or_inflate(&self.heap, obj_ref)
+            .exit(thread_id)
+    }
+
+    /// `Object.wait`, a timeout of 0 waits until the thread is notified or interrupted.
+    pub fn monitor_wait(
+        &self,
+        thread: &mut JavaThreadState,
+        obj_ref: HeapRef,
+        timeout_ms: i64,
+    ) -> Result<(), JvmError> {
+        let monitor = self.monitors.get_or_inflate(&self.heap, obj_ref);
+        monitor.check_owner(thread.id)?;
+        // registered before the interrupt status is checked, so an interrupt can't slip in between
+        self.monitors.set_waiting(thread.id, monitor.clone());
+        let result = if self.is_thread_interrupted(thread.thread_obj)? {
+            Ok(())
+        } else {
+            let timeout = (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms as u64));
+            let thread_id = thread.id;
+            self.safepoint.enter_safe_region(thread);
+            let result = monitor.wait(thread_id, timeout);
+            self.safepoint.leave_safe_region(thread_id);
+            result
+        };
+        self.monitors.clear_waiting(thread.id);
+        result?;
+        if self.is_thread_interrupted(thread.thread_obj)? {
+            self.set_thread_interrupted(thread.thread_obj, false)?;
+            throw_exception!(InterruptedException)?
+        }
+        Ok(())
+    }
+
+    /// `Thread.holdsLock`
+    pub fn monitor_holds_lock(&self, thread_id: ThreadId, obj_ref: HeapRef) -> bool {
+        self.monitors.is_owned_by(&self.heap, obj_ref, thread_id)
+    }
+
+    pub fn monitor_notify(&self, thread_id: ThreadId, obj_ref: HeapRef) -> Result<(), JvmError> {
+        self.monitors
+            .get_or_inflate(&self.heap, obj_ref)
+            .notify(thread_id)
+    }
+
+    pub fn monitor_notify_all(
+        &self,
+        thread_id: ThreadId,
+        obj_ref: HeapRef,
+    ) -> Result<(), JvmError> {
+        self.monitors
+            .get_or_inflate(&self.heap, obj_ref)
+            .notify_all(thread_id)
+    }
+
+    /// Stops at a safepoint if another thread requested one, collects garbage first if the heap
+    /// asked for it and dumps it if a signal did. The interpreter polls before every instruction.
+    pub fn safepoint_poll(&self, thread: &mut JavaThreadState) -> Result<(), JvmError> {
+        // loaded first, the swap would make every thread contend on the flag
+        if self.heap_dump_requested.load(Ordering::Relaxed)
+            && self.heap_dump_requested.swap(false, Ordering::Relaxed)
+        {
+            self.write_heap_dump(thread);
+        }
+        if self.gc_requested.load(Ordering::Relaxed) {
+            return self.collect_garbage(thread, false, false);
+        }
+        self.safepoint.poll(thread);
+        Ok(())
+    }
+
+    /// Stops the world and collects garbage, unless another thread is already doing it.
+    /// A minor collection is enough unless `full` is set or the old space fills up, softly
+    /// reachable objects survive unless `clear_soft_references` is set too.
+    pub fn collect_garbage(
+        &self,
+        thread: &mut JavaThreadState,
+        full: bool,
+        clear_soft_references: bool,
+    ) -> Result<(), JvmError> {
+        let mut res = Ok(());
+        self.safepoint.stop_the_world(thread, |threads| {
+            let mut method_area = self.method_area_write();
+            res = self.collect_verified(
+                &mut self.heap_write(),
+                &mut method_area,
+                threads,
+                full,
+                clear_soft_references,
+            );
+        });
+        self.free_dead_monitors();
+        self.notify_reference_pending_list();
+        res
+    }
+
+    /// `Heap::collect`, with `-XX:+VerifyHeap` the heap is verified before the collection, after
+    /// the allocations since the previous one, and after it.
+    fn collect_verified(
+        &self,
+        heap: &mut Heap,
+        method_area: &mut MethodArea,
+        threads: &mut [&mut JavaThreadState],
+        full: bool,
+        clear_soft_references: bool,
+    ) -> Result<(), JvmError> {
+        self.verify_heap(heap, method_area, threads, "before GC");
+        heap.collect(method_area, threads, full, clear_soft_references)?;
+        self.verify_heap(heap, method_area, threads, "after GC");
+        Ok(())
+    }
+
+    /// A corrupted heap can't be trusted to even report an error to java code, so like hotspot
+    /// the VM aborts.
+    fn verify_heap(
+        &self,
+        heap: &mut Heap,
+        method_area: &mut MethodArea,
+        threads: &mut [&mut JavaThreadState],
+        when: &str,
+    ) {
+        if !self.config.verify_heap {
+            return;
+        }
+        if let Err(e) = heap.verify(method_area, threads) {
+            eprintln!("Heap verification failed {}: {}", when, e);
+            std::process::abort();
+        }
+    }
+
+    /// Frees the monitors of the objects the last collection found dead. The ids are taken out
+    /// of the heap before the monitor table is locked, inflation locks them the other way round.
+    fn free_dead_monitors(&self) {
+        let dead_ids = self.heap_write().take_dead_monitors();
+        if !dead_ids.is_empty() {
+            self.monitors.free_dead(dead_ids);
+        }
+    }
+
+    /// Wakes up the Reference Handler thread if the collection cleared references.
+    fn notify_reference_pending_list(&self) {
+        if self.heap_read().has_reference_pending_list() {
+            let _pending = self.reference_pending_lock.lock().unwrap();
+            self.reference_pending_cv.notify_all();
+        }
+    }
+
+    /// `Reference.waitForReferencePendingList`, blocks the Reference Handler thread until a
+    /// collection clears references.
+    pub fn wait_for_reference_pending_list(&self, thread: &mut JavaThreadState) {
+        let mut pending = self.reference_pending_lock.lock().unwrap();
+        while !self.heap_read().has_reference_pending_list() {
+            // entered with the lock held, a notification can't be missed
+            self.safepoint.enter_safe_region(thread);
+            drop(self.reference_pending_cv.wait(pending).unwrap());
+            self.safepoint.leave_safe_region(thread.id);
+            pending = self.reference_pending_lock.lock().unwrap();
+        }
+    }
+
+    /// Allocates with `alloc`, if the heap is exhausted a full collection clearing the soft
+    /// references runs and the allocation is retried once before giving up with the
+    /// OutOfMemoryError. Like any safepoint, the caller must not hold references the collector
+    /// can't see.
+    // TODO: preallocate the OutOfMemoryError like hotspot, creating it may fail too
+    pub fn alloc_or_collect(
+        &self,
+        thread: &mut JavaThreadState,
+        alloc: impl Fn(&mut Heap) -> Result<HeapRef, JvmError>,
+    ) -> Result<HeapRef, JvmError> {
+        self.alloc_or_collect_with(thread, Vec::new(), |heap, _| alloc(heap))
+    }
+
+    /// `alloc_or_collect` for allocations that read other objects, like a clone reads its source.
+    /// They are rooted across the collection and `alloc` gets their current addresses.
+    pub fn alloc_or_collect_with(
+        &self,
+        thread: &mut JavaThreadState,
+        objects: Vec<Value>,
+        alloc: impl Fn(&mut Heap, &[Value]) -> Result<HeapRef, JvmError>,
+    ) -> Result<HeapRef, JvmError> {
+        self.retry_after_collection(thread, objects, |objects| {
+            alloc(&mut self.heap_write(), objects)
+        })
+    }
+
+    /// The policy of `alloc_or_collect_with`, for allocations that take the heap lock
+    /// themselves, like a mirror created under the method area lock.
+    fn retry_after_collection(
+        &self,
+        thread: &mut JavaThreadState,
+        objects: Vec<Value>,
+        alloc: impl Fn(&[Value]) -> Result<HeapRef, JvmError>,
+    ) -> Result<HeapRef, JvmError> {
+        let res = alloc(&objects);
+        match res {
+            Err(e) if e.is_out_of_memory() => {
+                let mark = thread.push_handles(objects);
+                let collected = self.collect_garbage(thread, true, true);
+                let objects = thread.pop_handles(mark);
+                collected?;
+                let res = alloc(&objects);
+                if res.as_ref().is_err_and(JvmError::is_out_of_memory) {
+                    self.heap_dump_on_out_of_memory(thread);
+                }
+                res
+            }
+            res => res,
+        }
+    }
+
+    pub fn alloc_string(&self, thread: &mut JavaThreadState, s: &str) -> Result<HeapRef, JvmError> {
+        self.alloc_or_collect(thread, |heap| heap.alloc_string(s))
+    }
+
+    /// The interned string, allocated the first time it is asked for.
+    pub fn get_str_from_pool_or_new(
+        &self,
+        thread: &mut JavaThreadState,
+        val_sym: Symbol,
+    ) -> Result<HeapRef, JvmError> {
+        self.alloc_or_collect(thread, |heap| heap.get_str_from_pool_or_new(val_sym))
+    }
+
+    /// The `java.lang.Class` instance of the class, allocated the first time it is asked for.
+    pub fn get_mirror_ref_or_create(
+        &self,
+        thread: &mut JavaThreadState,
+        class_id: ClassId,
+    ) -> Result<HeapRef, JvmError> {
+        self.retry_after_collection(thread, Vec::new(), |_| {
+            self.method_area_write()
+                .get_mirror_ref_or_create(class_id, &self.heap)
+        })
+    }
+
+    /// Shallow copy for `Object.clone`. The clone gets a header of its own: it isn't locked and
+    /// gets its identity hash when first asked for one.
+    pub fn clone_object(
+        &self,
+        thread: &mut JavaThreadState,
+        obj_ref: HeapRef,
+    ) -> Result<HeapRef, JvmError> {
+        self.alloc_or_collect_with(thread, vec![Value::Ref(obj_ref)], |heap, objects| {
+            heap.clone_object(objects[0].as_obj_ref()?)
+        })
+    }
+
+    /// `-XX:+HeapDumpOnOutOfMemoryError`, reports progress on stdout like hotspot.
+    fn heap_dump_on_out_of_memory(&self, thread: &mut JavaThreadState) {
+        if !self.config.heap_dump_on_out_of_memory
+            || self
+                .heap_dumped_on_out_of_memory
+                .swap(true, Ordering::Relaxed)
+        {
+            return;
+        }
+        self.write_heap_dump(thread);
+    }
+
+    /// Dumps to `-XX:HeapDumpPath`, reports progress on stdout like hotspot. The first dump goes
+    /// to java_pid<pid>.hprof or the given file, the next ones get a .1, .2... suffix.
+    fn write_heap_dump(&self, thread: &mut JavaThreadState) {
+        let file_name = format!("java_pid{}.hprof", std::process::id());
+        let mut path = match &self.config.heap_dump_path {
+            Some(path) if path.is_dir() => path.join(file_name),
+            Some(path) => path.clone(),
+            None => PathBuf::from(file_name),
+        };
+        let count = self.heap_dump_count.fetch_add(1, Ordering::Relaxed);
+        if count > 0 {
+            path.as_mut_os_string().push(format!(".{}", count));
+        }
+        println!("Dumping heap to {} ...", path.display());
+        let start = Instant::now();
+        match self.dump_heap(thread, &path, false) {
+            Ok(size) => println!(
+                "Heap dump file created [{} bytes in {:.3} secs]",
+                size,
+                start.elapsed().as_secs_f64()
+            ),
+            Err(e) => eprintln!(
+                "Unable to dump heap to {}: {}",
+                path.display(),
+                e.into_pretty_string(&self.string_interner)
+            ),
+        }
+    }
+
+    /// Stops the world and writes an HPROF heap dump to `path`, a new file. With `live` a full
+    /// collection runs first, so only reachable objects are dumped. Returns the size of the dump.
+    pub fn dump_heap(
+        &self,
+        thread: &mut JavaThreadState,
+        path: &Path,
+        live: bool,
+    ) -> Result<u64, JvmError> {
+        let mut res = Err(JvmError::Todo(
+            "Another thread stopped the world meanwhile".to_string(),
+        ));
+        let thread_id = thread.id;
+        self.safepoint.stop_the_world(thread, |threads| {
+            let mut method_area = self.method_area_write();
+            let mut heap = self.heap_write();
+            res = if live {
+                self.collect_verified(&mut heap, &mut method_area, threads, true, false)
+            } else {
+                Ok(())
+            }
+            .and_then(|_| heap.dump(&mut method_area, threads, thread_id, path));
+        });
+        self.free_dead_monitors();
+        self.notify_reference_pending_list();
+        res
+    }
+
+    /// `Object.hashCode` and `System.identityHashCode`, the same for the lifetime of the object.
+    pub fn identity_hash(&self, thread: &mut JavaThreadState, obj_ref: HeapRef) -> i32 {
+        self.heap_write()
+            .identity_hash(obj_ref, || thread.identity_hashes.next_hash())
+    }
+
+    /// Wakes the thread up if it waits on a monitor or is parked, the interrupt status itself is
+    /// set by `Thread.interrupt` before.
+    pub fn interrupt_thread(&self, thread_id: ThreadId) {
+        self.monitors.interrupt_waiting(thread_id);
+        if let Some(parker) = self.threads.parker(thread_id) {
+            parker.unpark();
+        }
+    }
+
+    /// `Unsafe.park`, returns right away if the thread is interrupted or was unparked since it
+    /// last parked, otherwise waits for an unpark, an interrupt or the timeout.
+    pub fn park_thread(
+        &self,
+        thread: &mut JavaThreadState,
+        timeout: Option<Duration>,
+    ) -> Result<(), JvmError> {
+        let Some(parker) = self.threads.parker(thread.id) else {
+            return Ok(());
+        };
+        // interrupting unparks too, so an interrupt can't slip in after the check
+        if self.is_thread_interrupted(thread.thread_obj)? {
+            return Ok(());
+        }
+        let thread_id = thread.id;
+        self.safepoint.enter_safe_region(thread);
+        parker.park(timeout);
+        self.safepoint.leave_safe_region(thread_id);
+        Ok(())
+    }
+
+    /// `Thread.sleep`, parks the thread until the time is up. Unparking ends the park early,
+    /// so it parks again for the rest of the time unless the thread was interrupted.
+    pub fn sleep_thread(&self, thread: &mut JavaThreadState, nanos: i64) -> Result<(), JvmError> {
+        let deadline = Instant::now() + Duration::from_nanos(nanos.max(0) as u64);
+        let parker = self.threads.parker(thread.id);
+        loop {
+            // interrupting unparks too, so an interrupt can't slip in after the check
+            if self.is_thread_interrupted(thread.thread_obj)? {
+                self.set_thread_interrupted(thread.thread_obj, false)?;
+                throw_exception!(InterruptedException, "sleep interrupted")?
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            let thread_id = thread.id;
+            self.safepoint.enter_safe_region(thread);
+            match &parker {
+                Some(parker) => parker.park(Some(remaining)),
+                None => std::thread::sleep(remaining),
+            }
+            self.safepoint.leave_safe_region(thread_id);
+        }
+    }
+
+    /// `Unsafe.unpark`, does nothing for threads that aren't alive.
+    pub fn unpark_thread(&self, thread_obj: HeapRef) -> Result<(), JvmError> {
+        let parker = self
+            .get_thread_id(thread_obj)?
+            .and_then(|thread_id| self.threads.parker(thread_id));
+        if let Some(parker) = parker {
+            parker.unpark();
+        }
+        Ok(())
+    }
+
+    /// `Thread.start0`, runs `Thread.run` of the thread object on a new OS thread.
+    pub fn start_thread(&self, thread_obj: HeapRef) -> Result<(), JvmError> {
+        let vm = self
+            .this
+            .upgrade()
+            .ok_or(JvmError::Todo("VM is shutting down".to_string()))?;
+        let thread_id = self.threads.next_id();
+        let name_offset = self.thread_field_offset(&self.br().thread_name_fk)?;
+        let name = self
+            .heap_read()
+            .read_field(thread_obj, name_offset, AllocationType::Reference)?
+            .as_obj_ref()?;
+        let os_thread_name = self.heap_read().get_rust_string_from_java_string(name)?;
+        let thread = JavaThreadState {
+            id: thread_id,
+            thread_obj,
+            group_obj: 0,
+            name,
+            stack: FrameStack::new(&self.config),
+            handles: Vec::new(),
+            identity_hashes: IdentityHashGenerator::new(thread_id),
+        };
+
+        // alive before start0 returns, so join or isAlive right after start see the thread
+        self.set_thread_eetop(thread_obj, thread_id.as_usize() as i64)?;
+        self.set_thread_status(thread_obj, THREAD_STATUS_RUNNABLE)?;
+        let daemon = self.is_thread_daemon(thread_obj)?;
+        self.threads.register(thread_id, daemon);
+        // attached here, so a collection can't start before the new thread polls
+        self.safepoint.attach();
+        let spawned = std::thread::Builder::new()
+            .name(os_thread_name)
+            .spawn(move || vm.run_thread(thread));
+        if spawned.is_err() {
+            self.safepoint.detach();
+            self.threads.unregister(thread_id);
+            self.set_thread_eetop(thread_obj, 0)?;
+            self.set_thread_status(thread_obj, 0)?;
+            throw_exception!(
+                OutOfMemoryError,
+                "unable to create native thread: possibly out of memory or process/resource limits reached"
+            )?
+        }
+        Ok(())
+    }
+
+    fn run_thread(&self, mut thread: JavaThreadState) {
+        if let Err(e) = self.invoke_thread_run(&mut thread) {
+            self.unhandled_exception(&mut thread, e);
+        }
+        if let Err(e) = self.exit_thread(&mut thread) {
+            eprintln!(
+                "Error while terminating thread: {}",
+                e.into_pretty_string(self.interner())
+            );
+        }
+        self.safepoint.detach();
+    }
+
+    fn invoke_thread_run(&self, thread: &mut JavaThreadState) -> Result<(), JvmError> {
+        let thread_class_id = self.heap_read().get_class_id(thread.thread_obj)?;
+        let run_method_id = self
+            .method_area_read()
+            .get_class(&thread_class_id)
+            .get_vtable_method_id(&self.br().thread_run_mk)?;
+        Interpreter::invoke_instance_method(
+            thread,
+            run_method_id,
+            self,
+            vec![Value::Ref(thread.thread_obj)],
+        )?;
+        Ok(())
+    }
+
+    /// Lets `Thread.exit` clean up, then marks the thread terminated and wakes up the threads
+    /// joining it, they wait on the thread object.
+    fn exit_thread(&self, thread: &mut JavaThreadState) -> Result<(), JvmError> {
+        let exit_method_id = self
+            .method_area_read()
+            .get_instance_class(&self.br().get_java_lang_thread_id()?)?
+            .get_special_method_id(&self.br().thread_exit_mk)?;
+        let res = Interpreter::invoke_instance_method(
+            thread,
+            exit_method_id,
+            self,
+            vec![Value::Ref(thread.thread_obj)],
+        );
+
+        let thread_obj = thread.thread_obj;
+        self.monitor_enter(thread, thread_obj)?;
+        self.set_thread_status(thread.thread_obj, THREAD_STATUS_TERMINATED)?;
+        self.set_thread_eetop(thread.thread_obj, 0)?;
+        self.monitor_notify_all(thread.id, thread.thread_obj)?;
+        self.monitor_exit(thread.id, thread.thread_obj)?;
+        self.threads.unregister(thread.id);
+        res.map(|_| ())
+    }
+
+    /// DestroyJavaVM: waits until the main thread is the last non-daemon thread, runs the
+    /// shutdown hooks and terminates the main thread.
+    fn destroy_java_vm(&self, main_thread: &mut JavaThreadState) {
+        self.safepoint.enter_safe_region(main_thread);
+        self.threads.wait_for_non_daemon_threads(main_thread.id);
+        self.safepoint.leave_safe_region(main_thread.id);
+        if let Err(e) = self.run_shutdown_hooks(main_thread) {
+            self.unhandled_exception(main_thread, e);
+        }
+        if let Err(e) = self.exit_thread(main_thread) {
+            eprintln!(
+                "Error while terminating thread: {}",
+                e.into_pretty_string(self.interner())
+            );
+        }
+        self.safepoint.detach();
+        if self.config.print_class_histogram_at_exit {
+            self.print_class_histogram();
+        }
+        self.debug_state.send_event(DebugEvent::VMDeath);
+    }
+
+    /// `Shutdown.halt0`, the hooks already ran on the java side and the other threads are
+    /// stopped together with the process.
+    pub fn halt(&self, status: i32) -> ! {
+        if self.config.print_class_histogram_at_exit {
+            self.print_class_histogram();
+        }
+        self.debug_state.send_event(DebugEvent::VMDeath);
+        std::process::exit(status)
+    }
+
+    /// Instances and bytes per class, like `jmap -histo`. No safepoint is needed, objects only
+    /// move while the heap is locked for a collection.
+    pub fn class_histogram(&self) -> Result<ClassHistogram, JvmError> {
+        let method_area = self.method_area_read();
+        self.heap_read().class_histogram(&method_area)
+    }
+
+    /// SIGQUIT (Ctrl-\), with -XX:+PrintClassHistogram and -XX:+HeapDumpOnCtrlBreak.
+    pub fn handle_quit_signal(&self) {
+        if self.config.print_class_histogram {
+            self.print_class_histogram();
+        }
+        if self.config.heap_dump_on_ctrl_break {
+            // the signal dispatcher isn't a java thread, it can't stop the world
+            self.heap_dump_requested.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn print_class_histogram(&self) {
+        match self.class_histogram() {
+            Ok(histogram) => print!("{}", histogram),
+            Err(e) => eprintln!(
+                "Unable to print class histogram: {}",
+                e.into_pretty_string(self.interner())
+            ),
+        }
+    }
+
+    /// `Shutdown.shutdown` runs the registered hooks, `Runtime.addShutdownHook` ones included.
+    fn run_shutdown_hooks(&self, thread: &mut JavaThreadState) -> Result<(), JvmError> {
+        let shutdown_class_id = self
+            .method_area_write()
+            .get_class_id_or_load(self.br().java_lang_shutdown_sym, thread.id)?;
+        let shutdown_method_id = self
+            .method_area_read()
+            .get_static_method_id(&shutdown_class_id, self.br().shutdown_shutdown_mk)?;
+        Interpreter::invoke_static_method(thread, shutdown_method_id, self, vec![])
+    }
+
+    /// Maps a `java.lang.Thread` to the VM thread through its `eetop` field, `None` if the
+    /// thread wasn't started yet or already terminated.
+    pub fn get_thread_id(&self, thread_obj: HeapRef) -> Result<Option<ThreadId>, JvmError> {
+        let offset = self.thread_field_offset(&self.br().thread_eetop_fk)?;
+        let eetop = self
+            .heap_read()
+            .read_field(thread_obj, offset, AllocationType::Long)?
+            .as_long()?;
+        Ok((eetop != 0).then(|| ThreadId::from_usize(eetop as usize)))
+    }
+
+    fn is_thread_interrupted(&self, thread_obj: HeapRef) -> Result<bool, JvmError> {
+        let offset = self.thread_field_offset(&self.br().thread_interrupted_fk)?;
+        Ok(self
+            .heap_read()
+            .read_field(thread_obj, offset, AllocationType::Boolean)?
+            .as_int()?
+            != 0)
+    }
+
+    fn set_thread_interrupted(&self, thread_obj: HeapRef, value: bool) -> Result<(), JvmError> {
+        let offset = self.thread_field_offset(&self.br().thread_interrupted_fk)?;
+        self.heap_write().write_field(
+            thread_obj,
+            offset,
+            Value::Integer(value as i32),
+            AllocationType::Boolean,
+        )
+    }
+
+    fn set_thread_eetop(&self, thread_obj: HeapRef, eetop: i64) -> Result<(), JvmError> {
+        let offset = self.thread_field_offset(&self.br().thread_eetop_fk)?;
+        self.heap_write()
+            .write_field(thread_obj, offset, Value::Long(eetop), AllocationType::Long)
+    }
+
+    fn set_thread_status(&self, thread_obj: HeapRef, status: i32) -> Result<(), JvmError> {
+        let (holder_ref, status_offset) =
+            self.thread_holder_field(thread_obj, &self.br().thread_holder_thread_status_fk)?;
+        self.heap_write().write_field(
+            holder_ref,
+            status_offset,
+            Value::Integer(status),
+            AllocationType::Int,
+        )
+    }
+
+    fn is_thread_daemon(&self, thread_obj: HeapRef) -> Result<bool, JvmError> {
+        let (holder_ref, daemon_offset) =
+            self.thread_holder_field(thread_obj, &self.br().thread_holder_daemon_fk)?;
+        Ok(self
+            .heap_read()
+            .read_field(holder_ref, daemon_offset, AllocationType::Boolean)?
+            .as_int()?
+            != 0)
+    }
+
+    /// `Thread.FieldHolder` of the thread and the offset of the field in it.
+    fn thread_holder_field(
+        &self,
+        thread_obj: HeapRef,
+        field_key: &FieldKey,
+    ) -> Result<(HeapRef, usize), JvmError> {
+        let holder_offset = self.thread_field_offset(&self.br().thread_holder_fk)?;
+        let holder_ref = self
+            .heap_read()
+            .read_field(thread_obj, holder_offset, AllocationType::Reference)?
+            .as_obj_ref()?;
+        let holder_class_id = self.heap_read().get_class_id(holder_ref)?;
+        let field_offset = self
+            .method_area_read()
+            .get_instance_class(&holder_class_id)?
+            .get_instance_field(field_key)?
+            .offset;
+        Ok((holder_ref, field_offset))
+    }
+
+    fn thread_field_offset(&self, field_key: &FieldKey) -> Result<usize, JvmError> {
+        let thread_class_id = self.br().get_java_lang_thread_id()?;
+        Ok(self
+            .method_area_read()
+            .get_instance_class(&thread_class_id)?
+            .get_instance_field(field_key)?
+            .offset)
+    }
+
     pub fn method_area_read(&self) -> std::sync::RwLockReadGuard<'_, MethodArea> {
         self.method_area.read().unwrap()
     }
@@ -419,41 +1492,138 @@ impl VirtualMachine {
     }
 }
 
-pub fn start(config: VmConfig) -> Result<(), ()> {
+/// Runs the main class and returns the exit status of the VM, `System.exit` ends the process
+/// with its own status instead.
+pub fn start(config: VmConfig) -> i32 {
     let string_interner = Arc::new(ThreadedRodeo::default());
-    let (mut vm, mut main_thread) =
-        VirtualMachine::new(config, string_interner.clone()).map_err(|_| {})?;
+    let Ok((mut vm, mut main_thread)) = VirtualMachine::new(config, string_interner.clone()) else {
+        return 1;
+    };
 
     #[cfg(feature = "log-runtime-traces")]
     log_traces::debug::init(&vm);
 
     let main_class_sym = vm.string_interner.get_or_intern(&vm.config.main_class);
-    let main_class_id = vm
-        .method_area_write()
-        .get_class_id_or_load(main_class_sym, main_thread.id)
-        .map_err(|e| {
+    let app_class_loader = vm.method_area_read().get_app_class_loader_id();
+    let main_class_id = match vm.load_class_with(&mut main_thread, app_class_loader, main_class_sym)
+    {
+        Ok(main_class_id) => main_class_id,
+        Err(e) => {
             eprintln!(
                 "Error: Could not find or load main class {}",
                 vm.config.main_class.replace('/', ".")
             );
             eprintln!("Caused by: {}", e.into_pretty_string(&string_interner));
-        })?;
-    let main_method_id = vm
-        .method_area_read()
-        .get_instance_class(&main_class_id)
-        .unwrap()
-        .get_special_method_id(&vm.br().main_mk)
-        .map_err(|_| JvmError::MainClassNotFound(vm.config.main_class.replace('/', ".")))
-        .unwrap();
+            vm.destroy_java_vm(&mut main_thread);
+            return 1;
+        }
+    };
+    let main_class_name = vm.config.main_class.replace('/', ".");
+    let main_method_id = match vm.find_main_method(main_class_id) {
+        Ok(Some(main_method_id)) => main_method_id,
+        Ok(None) => {
+            eprintln!(
+                "Error: Main method not found in class {}, please define the main method as:\n   \
+                public static void main(String[] args)\n\
+                or a JavaFX application class must extend javafx.application.Application",
+                main_class_name
+            );
+            vm.destroy_java_vm(&mut main_thread);
+            return 1;
+        }
+        Err(e) => {
+            eprintln!(
+                "Error: Could not find main method in class {}",
+                main_class_name
+            );
+            eprintln!("Caused by: {}", e.into_pretty_string(&string_interner));
+            vm.destroy_java_vm(&mut main_thread);
+            return 1;
+        }
+    };
     debug_log_method!(&main_method_id, "Main method found");
 
+    let (is_static_main, main_takes_args) = {
+        let ma = vm.method_area_read();
+        let main_method = ma.get_method(&main_method_id);
+        (
+            main_method.is_static(),
+            main_method.desc == vm.br().main_mk.desc,
+        )
+    };
+    let mut main_method_args = Vec::new();
+    if !is_static_main {
+        let constructor_id = match vm.find_main_constructor(main_class_id) {
+            Ok(Some(constructor_id)) => constructor_id,
+            Ok(None) => {
+                eprintln!(
+                    "Error: no non-private zero argument constructor found in class {0}\n\
+                    remove private from existing constructor or define as:\n   public {0}()",
+                    main_class_name
+                );
+                vm.destroy_java_vm(&mut main_thread);
+                return 1;
+            }
+            Err(e) => {
+                eprintln!(
+                    "Error: Could not instantiate main class {}",
+                    main_class_name
+                );
+                eprintln!("Caused by: {}", e.into_pretty_string(&string_interner));
+                vm.destroy_java_vm(&mut main_thread);
+                return 1;
+            }
+        };
+        match vm.create_main_instance(&mut main_thread, main_class_id, constructor_id) {
+            Ok(instance) => main_method_args.push(Value::Ref(instance)),
+            Err(e) => {
+                vm.unhandled_exception(&mut main_thread, e);
+                vm.destroy_java_vm(&mut main_thread);
+                return 1;
+            }
+        }
+    }
+    if main_takes_args {
+        // the main instance stays rooted while the arguments are allocated
+        let (main_args, rooted) = main_thread.with_handles(main_method_args, |main_thread| {
+            vm.create_main_args(main_thread)
+        });
+        main_method_args = rooted;
+        match main_args {
+            Ok(main_args) => main_method_args.push(Value::Ref(main_args)),
+            Err(e) => {
+                eprintln!("Error: Could not create main method arguments");
+                eprintln!("Caused by: {}", e.into_pretty_string(&string_interner));
+                vm.destroy_java_vm(&mut main_thread);
+                return 1;
+            }
+        }
+    }
+
     // TODO: it works more or less correctly, but should be improved
-    let res = Interpreter::invoke_static_method(&mut main_thread, main_method_id, &mut vm, vec![]);
-    vm.debug_state.send_event(DebugEvent::VMDeath);
-    if let Err(e) = res {
+    let res = if is_static_main {
+        Interpreter::invoke_static_method(
+            &mut main_thread,
+            main_method_id,
+            &mut vm,
+            main_method_args,
+        )
+    } else {
+        Interpreter::invoke_instance_method(
+            &mut main_thread,
+            main_method_id,
+            &mut vm,
+            main_method_args,
+        )
+        .map(|_| ())
+    };
+    // like the java launcher, an uncaught exception in main makes the VM exit with 1
+    let exit_status = if let Err(e) = res {
         vm.unhandled_exception(&mut main_thread, e);
-        Err(())
+        1
     } else {
-        Ok(())
-    }
+        0
+    };
+    vm.destroy_java_vm(&mut main_thread);
+    exit_status
 }
@@ -0,0 +1,33 @@
+use crate::VirtualMachine;
+use crate::keys::FullyQualifiedMethodKey;
+use crate::native::{NativeRegistry, NativeRet};
+use crate::thread::JavaThreadState;
+use crate::vm::Value;
+use std::path::Path;
+
+pub(super) fn do_register_com_sun_management_preregistered_natives(
+    native_registry: &mut NativeRegistry,
+) {
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "com/sun/management/internal/HotSpotDiagnostic",
+            "dumpHeap0",
+            "(Ljava/lang/String;Z)V",
+            &native_registry.string_interner,
+        ),
+        com_sun_management_internal_hot_spot_diagnostic_dump_heap_0,
+    );
+}
+
+/// `HotSpotDiagnosticMXBean.dumpHeap`, also what `jcmd GC.heap_dump` ends up calling.
+fn com_sun_management_internal_hot_spot_diagnostic_dump_heap_0(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let path_ref = args[1].as_obj_ref()?;
+    let live = args[2].as_int()? != 0;
+    let path = vm.heap_read().get_rust_string_from_java_string(path_ref)?;
+    vm.dump_heap(thread, Path::new(&path), live)?;
+    Ok(None)
+}
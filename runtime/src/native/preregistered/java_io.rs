@@ -222,7 +222,7 @@ fn java_io_unix_file_system_init_ids(
 
 fn java_io_unix_file_system_canonicalize_0(
     vm: &VirtualMachine,
-    _thread: &mut JavaThreadState,
+    thread: &mut JavaThreadState,
     args: &[Value],
 ) -> NativeRet {
     let str_ref = args[1].as_obj_ref()?;
@@ -230,7 +230,7 @@ fn java_io_unix_file_system_canonicalize_0(
     match std::fs::canonicalize(&path) {
         Ok(canonical) => {
             let res = canonical.to_string_lossy().to_string();
-            let res_ref = vm.heap_write().alloc_string(&res)?;
+            let res_ref = vm.alloc_string(thread, &res)?;
             Ok(Some(Value::Ref(res_ref)))
         }
         Err(e) => {
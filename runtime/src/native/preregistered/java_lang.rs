@@ -1,3 +1,6 @@
+use crate::error::JvmError;
+use crate::heap::method_area::MethodArea;
+use crate::interpreter::Interpreter;
 use crate::keys::{ClassId, FullyQualifiedMethodKey};
 use crate::native::{NativeRegistry, NativeRet};
 use crate::thread::JavaThreadState;
@@ -56,6 +59,15 @@ pub(super) fn do_register_java_lang_preregistered_natives(native_registry: &mut
         ),
         java_lang_object_hash_code,
     );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Object",
+            "clone",
+            "()Ljava/lang/Object;",
+            &native_registry.string_interner,
+        ),
+        java_lang_object_clone,
+    );
 
     native_registry.register(
         FullyQualifiedMethodKey::new_with_str(
@@ -66,6 +78,33 @@ pub(super) fn do_register_java_lang_preregistered_natives(native_registry: &mut
         ),
         java_lang_runtime_max_memory,
     );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Runtime",
+            "totalMemory",
+            "()J",
+            &native_registry.string_interner,
+        ),
+        java_lang_runtime_total_memory,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Runtime",
+            "freeMemory",
+            "()J",
+            &native_registry.string_interner,
+        ),
+        java_lang_runtime_free_memory,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Runtime",
+            "gc",
+            "()V",
+            &native_registry.string_interner,
+        ),
+        java_lang_runtime_gc,
+    );
     native_registry.register(
         FullyQualifiedMethodKey::new_with_str(
             "java/lang/Runtime",
@@ -75,6 +114,24 @@ pub(super) fn do_register_java_lang_preregistered_natives(native_registry: &mut
         ),
         java_lang_runtime_available_processors,
     );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Shutdown",
+            "beforeHalt",
+            "()V",
+            &native_registry.string_interner,
+        ),
+        java_lang_shutdown_before_halt,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Shutdown",
+            "halt0",
+            "(I)V",
+            &native_registry.string_interner,
+        ),
+        java_lang_shutdown_halt0,
+    );
     native_registry.register(
         FullyQualifiedMethodKey::new_with_str(
             "java/lang/Object",
@@ -84,6 +141,24 @@ pub(super) fn do_register_java_lang_preregistered_natives(native_registry: &mut
         ),
         java_lang_object_notify_all,
     );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Object",
+            "notify",
+            "()V",
+            &native_registry.string_interner,
+        ),
+        java_lang_object_notify,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Object",
+            "wait0",
+            "(J)V",
+            &native_registry.string_interner,
+        ),
+        java_lang_object_wait0,
+    );
     native_registry.register(
         FullyQualifiedMethodKey::new_with_str(
             "java/lang/StackTraceElement",
@@ -137,6 +212,15 @@ pub(super) fn do_register_java_lang_preregistered_natives(native_registry: &mut
             &native_registry.string_interner,
         ),
         java_lang_double_long_bits_to_double,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ClassLoader",
+            "defineClass0",
+            "(Ljava/lang/ClassLoader;Ljava/lang/Class;Ljava/lang/String;[BIILjava/security/ProtectionDomain;ZILjava/lang/Object;)Ljava/lang/Class;",
+            &native_registry.string_interner,
+        ),
+        java_lang_class_loader_define_class_0,
     )
 }
 
@@ -185,38 +269,95 @@ fn java_lang_object_get_class(
 ) -> NativeRet {
     debug!("TODO: Stub: java.lang.Class.getClass");
     let object_ref = args[0].as_obj_ref()?;
-    let target_class_id = {
-        let class_id = vm.heap_read().get_class_id(object_ref)?;
-        //TODO: refactor and rethink how I handle array classes and their mirrors
-        //right now I put on heap for arrays the class id of the element type, but the mirror has to be of the array type
-        if vm.heap_read().is_array(object_ref)? {
-            let class_name_sym = vm.method_area_read().get_class(&class_id).get_name();
-            let raw_name = vm.interner().resolve(&class_name_sym);
-            let array_name = format!("[L{};", raw_name);
-            let array_class_name_sym = vm.interner().get_or_intern(&array_name);
-            vm.method_area_write()
-                .load_array_class(array_class_name_sym, thread.id)?
-        } else {
-            class_id
-        }
-    };
-    let res = vm
-        .method_area_write()
-        .get_mirror_ref_or_create(target_class_id, &vm.heap)?;
+    let target_class_id = vm.get_object_class_id(object_ref, thread.id)?;
+    let res = vm.get_mirror_ref_or_create(thread, target_class_id)?;
     Ok(Some(Value::Ref(res)))
 }
 
+/// Defines classes spun by `MethodHandles.Lookup` (lambda proxies, hidden classes and so on).
+fn java_lang_class_loader_define_class_0(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    // ClassOption.HIDDEN_CLASS / NESTMATE flags, see java.lang.invoke.MethodHandleNatives.Constants
+    const HIDDEN_CLASS: i32 = 0x2;
+
+    let loader_ref = match args[0] {
+        Value::Ref(loader_ref) => Some(loader_ref),
+        _ => None,
+    };
+    let bytes_ref = args[3].as_obj_ref()?;
+    let offset = args[4].as_int()?;
+    let length = args[5].as_int()?;
+    let initialize = args[7].as_int()? != 0;
+    let flags = args[8].as_int()?;
+    let class_data = args[9];
+
+    let data = vm
+        .heap_read()
+        .copy_byte_array_range(bytes_ref, offset, length)?;
+    let cf = MethodArea::parse_class_file(data)?;
+    // the class data is an argument, it isn't rooted in a frame
+    let (defined, rooted) = thread.with_handles(vec![class_data], |thread| {
+        let class_id = vm.define_class(thread, loader_ref, cf, flags & HIDDEN_CLASS != 0)?;
+        let mirror_ref = vm.get_mirror_ref_or_create(thread, class_id)?;
+        Ok::<_, JvmError>((class_id, mirror_ref))
+    });
+    let (class_id, mirror_ref) = defined?;
+    let class_data = rooted[0];
+    if !matches!(class_data, Value::Null) {
+        let class_data_offset = vm
+            .method_area_read()
+            .get_instance_field(
+                &vm.br().get_java_lang_class_id()?,
+                &vm.br().class_class_data_fk,
+            )?
+            .offset;
+        vm.heap_write().write_field(
+            mirror_ref,
+            class_data_offset,
+            class_data,
+            AllocationType::Reference,
+        )?;
+    }
+    if initialize {
+        Interpreter::ensure_initialized(thread, Some(class_id), vm)?;
+    }
+    // looked up again, the initializer may have run a collection that moved the mirror
+    let mirror_ref = vm.get_mirror_ref_or_create(thread, class_id)?;
+    Ok(Some(Value::Ref(mirror_ref)))
+}
+
 fn java_lang_object_hash_code(
-    _vm: &VirtualMachine,
-    _thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
     args: &[Value],
 ) -> NativeRet {
-    debug!("TODO: Stub: java.lang.Object.hashCode");
-    if let Value::Ref(h) = &args[0] {
-        Ok(Some(Value::Integer(*h as i32)))
-    } else {
-        panic!("java.lang.Object.hashCode: expected object as argument");
+    let obj_ref = args[0].as_obj_ref()?;
+    Ok(Some(Value::Integer(vm.identity_hash(thread, obj_ref))))
+}
+
+/// Arrays are cloned by the internal clone, see `Interpreter::invoke_native_method`.
+fn java_lang_object_clone(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let obj_ref = args[0].as_obj_ref()?;
+    let class_id = vm.heap_read().get_class_id(obj_ref)?;
+    let cloneable = vm
+        .method_area_read()
+        .instance_of(class_id, vm.br().java_lang_cloneable_sym);
+    if !cloneable {
+        let class_name = vm.method_area_read().get_class(&class_id).get_name();
+        throw_exception!(
+            CloneNotSupportedException,
+            vm.symbol_to_pretty_string(class_name)
+        )?;
     }
+    let clone_ref = vm.clone_object(thread, obj_ref)?;
+    Ok(Some(Value::Ref(clone_ref)))
 }
 
 /// Fills the backtrace and depth fields of the Throwable object, it contains the VM internal information
@@ -250,56 +391,44 @@ fn java_lang_throwable_fill_in_stack_trace(
     let int_arr_class = vm
         .method_area_write()
         .load_array_class(vm.br().int_array_desc, thread.id)?;
-    let class_id_array = vm.heap_write().alloc_primitive_array(
-        int_arr_class,
-        ArrayType::Int,
-        frames.len() as i32,
-    )?;
-    let method_id_array = vm.heap_write().alloc_primitive_array(
-        int_arr_class,
-        ArrayType::Int,
-        frames.len() as i32,
-    )?;
-    let line_nbr_array = vm.heap_write().alloc_primitive_array(
-        int_arr_class,
-        ArrayType::Int,
-        frames.len() as i32,
-    )?;
-    for (pos, frame) in frames.iter().enumerate() {
-        let class_id = vm
-            .method_area_read()
-            .get_method(&frame.method_id())
-            .class_id()
-            .to_i32();
-        vm.heap_write().write_array_element(
-            class_id_array,
-            pos as i32,
-            Value::Integer(class_id),
-        )?;
-        vm.heap_write().write_array_element(
-            method_id_array,
-            pos as i32,
-            Value::Integer(frame.method_id().to_i32()),
-        )?;
-        vm.heap_write().write_array_element(
-            line_nbr_array,
-            pos as i32,
-            Value::Integer(match frame {
+    let entries = frames
+        .iter()
+        .map(|frame| {
+            let class_id = vm
+                .method_area_read()
+                .get_method(&frame.method_id())
+                .class_id()
+                .to_i32();
+            let pc = match frame {
                 FrameType::JavaFrame(f) => f.pc() as i32,
                 FrameType::NativeFrame(_) => -2,
-            }),
-        )?;
-    }
-    let backtrace_addr = vm
-        .heap_write()
-        .alloc_object_array(vm.br().get_java_lang_object_id()?, 3)?;
-    vm.heap_write()
-        .write_array_element(backtrace_addr, 0, Value::Ref(class_id_array))?;
-    vm.heap_write()
-        .write_array_element(backtrace_addr, 1, Value::Ref(method_id_array))?;
-    vm.heap_write()
-        .write_array_element(backtrace_addr, 2, Value::Ref(line_nbr_array))?;
-    let throwable_addr = match args[0] {
+            };
+            (class_id, frame.method_id().to_i32(), pc)
+        })
+        .collect::<Vec<_>>();
+    let object_class_id = vm.br().get_java_lang_object_id()?;
+    // the arrays are allocated in one go, a collection between them would move the first ones
+    let (backtrace_addr, rooted) = thread.with_handles(vec![args[0]], |thread| {
+        vm.alloc_or_collect(thread, |heap| {
+            let len = entries.len() as i32;
+            let class_id_array = heap.alloc_primitive_array(int_arr_class, ArrayType::Int, len)?;
+            let method_id_array = heap.alloc_primitive_array(int_arr_class, ArrayType::Int, len)?;
+            let line_nbr_array = heap.alloc_primitive_array(int_arr_class, ArrayType::Int, len)?;
+            for (pos, (class_id, method_id, pc)) in entries.iter().enumerate() {
+                let pos = pos as i32;
+                heap.write_array_element(class_id_array, pos, Value::Integer(*class_id))?;
+                heap.write_array_element(method_id_array, pos, Value::Integer(*method_id))?;
+                heap.write_array_element(line_nbr_array, pos, Value::Integer(*pc))?;
+            }
+            let backtrace_addr = heap.alloc_object_array(object_class_id, 3)?;
+            heap.write_array_element(backtrace_addr, 0, Value::Ref(class_id_array))?;
+            heap.write_array_element(backtrace_addr, 1, Value::Ref(method_id_array))?;
+            heap.write_array_element(backtrace_addr, 2, Value::Ref(line_nbr_array))?;
+            Ok(backtrace_addr)
+        })
+    });
+    let backtrace_addr = backtrace_addr?;
+    let throwable_addr = match rooted[0] {
         Value::Ref(h) => h,
         _ => panic!("java.lang.Throwable.fillInStackTrace: expected object"),
     };
@@ -361,8 +490,32 @@ fn java_lang_runtime_max_memory(
     _thread: &mut JavaThreadState,
     _args: &[Value],
 ) -> NativeRet {
-    debug!("TODO: Stub: java.lang.Runtime.maxMemory");
-    Ok(Some(Value::Long(vm.config.max_heap_size as i64)))
+    Ok(Some(Value::Long(vm.heap_read().max_memory() as i64)))
+}
+
+fn java_lang_runtime_total_memory(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Ok(Some(Value::Long(vm.heap_read().total_memory() as i64)))
+}
+
+fn java_lang_runtime_free_memory(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Ok(Some(Value::Long(vm.heap_read().free_memory() as i64)))
+}
+
+fn java_lang_runtime_gc(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    vm.collect_garbage(thread, true, false)?;
+    Ok(None)
 }
 
 fn java_lang_runtime_available_processors(
@@ -376,137 +529,178 @@ fn java_lang_runtime_available_processors(
 
 fn java_lang_stack_trace_element_init_stack_trace_elements(
     vm: &VirtualMachine,
-    _thread: &mut JavaThreadState,
-    _args: &[Value],
+    thread: &mut JavaThreadState,
+    args: &[Value],
 ) -> NativeRet {
     debug!("TODO: Stub: java.lang.StackTraceElement.initStackTraceElements");
-    let elements_array = match &_args[0] {
+    let elements_array = match &args[0] {
         Value::Ref(h) => *h,
         _ => panic!("java.lang.StackTraceElement.initStackTraceElements: expected array"),
     };
-    let object = match &_args[1] {
+    let object = match &args[1] {
         Value::Ref(h) => *h,
         _ => panic!("java.lang.StackTraceElement.initStackTraceElements: expected object"),
     };
-    let depth = match _args[2] {
+    let depth = match args[2] {
         Value::Integer(i) if i >= 0 => i as usize,
         _ => panic!(
             "java.lang.StackTraceElement.initStackTraceElements: expected non-negative depth"
         ),
     };
 
-    // TODO: obviously need to clean this up
-    for i in 0..depth {
-        let i = i as i32;
-        let classes_array = vm.heap_read().read_array_element(object, 0)?.as_obj_ref()?;
-        let class_id = ClassId::from_i32(
-            vm.heap_read()
-                .read_array_element(classes_array, i)?
-                .as_int()?,
-        );
-        let methods_array = vm.heap_read().read_array_element(object, 1)?.as_obj_ref()?;
-        let method_id = MethodId::from_i32(
-            vm.heap_read()
-                .read_array_element(methods_array, i)?
-                .as_int()?,
-        );
-        let cp_array = vm.heap_read().read_array_element(object, 2)?.as_obj_ref()?;
-        let cp = vm.heap_read().read_array_element(cp_array, i)?.as_int()?;
-        let declaring_class_object = vm
-            .method_area_write()
-            .get_mirror_ref_or_create(class_id, &vm.heap)?;
-        let method_sym = vm.method_area_read().get_method(&method_id).name;
-        let class_sym = vm.method_area_read().get_class(&class_id).get_name();
-        let class_source_sym = vm
-            .method_area_read()
-            .get_class(&class_id)
-            .get_source_file()
-            .unwrap_or(vm.interner().get_or_intern("TODO: Unknown Source"));
-        let class_name = vm
-            .heap_write()
-            .alloc_string_from_interned_with_char_mapping(
-                class_sym,
-                Some(&|c| {
-                    if c == '/' { '.' } else { c }
-                }),
-            )?;
-        let method_name = vm.heap_write().alloc_string_from_interned(method_sym)?;
-        let source = vm
-            .heap_write()
-            .alloc_string_from_interned(class_source_sym)?;
-        let line_nbr = vm
-            .method_area_read()
-            .get_method(&method_id)
-            .get_line_number_by_cp(cp)
-            .unwrap_or(-1);
-        let cur_stack_trace_entry = vm
-            .heap_read()
-            .read_array_element(elements_array, i)?
-            .as_obj_ref()?;
-
-        let stack_trace_class_id = vm.heap_read().get_class_id(cur_stack_trace_entry)?;
-        let (a, b, c, d, e) = {
-            let ma = vm.method_area_read();
-            let stack_trace_class = ma.get_instance_class(&stack_trace_class_id)?;
-            (
-                stack_trace_class
-                    .get_instance_field(&vm.br().stack_trace_declaring_class_name_fk)?
-                    .offset,
-                stack_trace_class
-                    .get_instance_field(&vm.br().stack_trace_method_name_fk)?
-                    .offset,
-                stack_trace_class
-                    .get_instance_field(&vm.br().stack_trace_file_name_fk)?
-                    .offset,
-                stack_trace_class
-                    .get_instance_field(&vm.br().stack_trace_line_number_fk)?
-                    .offset,
-                stack_trace_class
-                    .get_instance_field(&vm.br().stack_trace_declaring_class_fk)?
-                    .offset,
-            )
-        };
-
-        vm.heap_write().write_field(
-            cur_stack_trace_entry,
-            a,
-            Value::Ref(class_name),
-            AllocationType::Reference,
-        )?;
-        vm.heap_write().write_field(
-            cur_stack_trace_entry,
-            b,
-            Value::Ref(method_name),
-            AllocationType::Reference,
-        )?;
-        vm.heap_write().write_field(
-            cur_stack_trace_entry,
-            c,
-            Value::Ref(source),
-            AllocationType::Reference,
-        )?;
-        vm.heap_write().write_field(
-            cur_stack_trace_entry,
-            d,
-            Value::Integer(line_nbr),
-            AllocationType::Int,
-        )?;
-        vm.heap_write().write_field(
-            cur_stack_trace_entry,
-            e,
-            Value::Ref(declaring_class_object),
-            AllocationType::Reference,
-        )?;
-    }
+    let mark = thread.push_handles(vec![Value::Ref(elements_array), Value::Ref(object)]);
+    let res = (0..depth as i32).try_for_each(|i| fill_stack_trace_element(vm, thread, mark, i));
+    thread.pop_handles(mark);
+    res?;
     Ok(None)
 }
 
-fn java_lang_object_notify_all(
+/// Fills the element at `index` from the backtrace. The elements array and the backtrace are the
+/// handles at `mark`, they are read again after every allocation since a collection moves them.
+fn fill_stack_trace_element(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    mark: usize,
+    index: i32,
+) -> Result<(), JvmError> {
+    let (class_id, method_id, cp) = {
+        let heap = vm.heap_read();
+        let object = thread.handles[mark + 1].as_obj_ref()?;
+        let classes_array = heap.read_array_element(object, 0)?.as_obj_ref()?;
+        let methods_array = heap.read_array_element(object, 1)?.as_obj_ref()?;
+        let cp_array = heap.read_array_element(object, 2)?.as_obj_ref()?;
+        (
+            ClassId::from_i32(heap.read_array_element(classes_array, index)?.as_int()?),
+            MethodId::from_i32(heap.read_array_element(methods_array, index)?.as_int()?),
+            heap.read_array_element(cp_array, index)?.as_int()?,
+        )
+    };
+    let method_sym = vm.method_area_read().get_method(&method_id).name;
+    let class_sym = vm.method_area_read().get_class(&class_id).get_name();
+    let class_source_sym = vm
+        .method_area_read()
+        .get_class(&class_id)
+        .get_source_file()
+        .unwrap_or(vm.interner().get_or_intern("TODO: Unknown Source"));
+    let line_nbr = vm
+        .method_area_read()
+        .get_method(&method_id)
+        .get_line_number_by_cp(cp)
+        .unwrap_or(-1);
+
+    let cur_stack_trace_entry = |thread: &JavaThreadState| {
+        let elements_array = thread.handles[mark].as_obj_ref()?;
+        vm.heap_read()
+            .read_array_element(elements_array, index)?
+            .as_obj_ref()
+    };
+    let write_field = |thread: &JavaThreadState, offset, value, field_type| {
+        let entry = cur_stack_trace_entry(thread)?;
+        vm.heap_write()
+            .write_field(entry, offset, value, field_type)
+    };
+    let stack_trace_class_id = vm
+        .heap_read()
+        .get_class_id(cur_stack_trace_entry(thread)?)?;
+    let (a, b, c, d, e) = {
+        let ma = vm.method_area_read();
+        let stack_trace_class = ma.get_instance_class(&stack_trace_class_id)?;
+        (
+            stack_trace_class
+                .get_instance_field(&vm.br().stack_trace_declaring_class_name_fk)?
+                .offset,
+            stack_trace_class
+                .get_instance_field(&vm.br().stack_trace_method_name_fk)?
+                .offset,
+            stack_trace_class
+                .get_instance_field(&vm.br().stack_trace_file_name_fk)?
+                .offset,
+            stack_trace_class
+                .get_instance_field(&vm.br().stack_trace_line_number_fk)?
+                .offset,
+            stack_trace_class
+                .get_instance_field(&vm.br().stack_trace_declaring_class_fk)?
+                .offset,
+        )
+    };
+
+    // every allocation may collect, so each result is stored before the next one
+    let declaring_class_object = vm.get_mirror_ref_or_create(thread, class_id)?;
+    write_field(
+        thread,
+        e,
+        Value::Ref(declaring_class_object),
+        AllocationType::Reference,
+    )?;
+    let class_name = vm.alloc_or_collect(thread, |heap| {
+        heap.alloc_string_from_interned_with_char_mapping(
+            class_sym,
+            Some(&|c| {
+                if c == '/' { '.' } else { c }
+            }),
+        )
+    })?;
+    write_field(thread, a, Value::Ref(class_name), AllocationType::Reference)?;
+    let method_name =
+        vm.alloc_or_collect(thread, |heap| heap.alloc_string_from_interned(method_sym))?;
+    write_field(
+        thread,
+        b,
+        Value::Ref(method_name),
+        AllocationType::Reference,
+    )?;
+    let source = vm.alloc_or_collect(thread, |heap| {
+        heap.alloc_string_from_interned(class_source_sym)
+    })?;
+    write_field(thread, c, Value::Ref(source), AllocationType::Reference)?;
+    write_field(thread, d, Value::Integer(line_nbr), AllocationType::Int)
+}
+
+fn java_lang_shutdown_before_halt(
     _vm: &VirtualMachine,
     _thread: &mut JavaThreadState,
     _args: &[Value],
 ) -> NativeRet {
-    debug!("TODO: Stub: java.lang.Object.notifyAll");
+    // hotspot only emits JFR events here
+    Ok(None)
+}
+
+fn java_lang_shutdown_halt0(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    vm.halt(args[0].as_int()?)
+}
+
+fn java_lang_object_notify_all(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let obj_ref = args[0].as_obj_ref()?;
+    vm.monitor_notify_all(thread.id, obj_ref)?;
+    Ok(None)
+}
+
+fn java_lang_object_notify(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let obj_ref = args[0].as_obj_ref()?;
+    vm.monitor_notify(thread.id, obj_ref)?;
+    Ok(None)
+}
+
+fn java_lang_object_wait0(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let obj_ref = args[0].as_obj_ref()?;
+    let timeout_ms = args[1].as_long()?;
+    vm.monitor_wait(thread, obj_ref, timeout_ms)?;
     Ok(None)
 }
 
@@ -536,7 +730,7 @@ fn java_lang_null_pointer_exception_get_extended_npe_message(
 
 fn java_lang_string_intern(
     vm: &VirtualMachine,
-    _thread: &mut JavaThreadState,
+    thread: &mut JavaThreadState,
     args: &[Value],
 ) -> NativeRet {
     debug!("TODO: Stub: java.lang.String.intern");
@@ -548,7 +742,7 @@ fn java_lang_string_intern(
         .heap_read()
         .get_rust_string_from_java_string(string_addr)?;
     let interned = vm.interner().get_or_intern(&string_value);
-    let interned_addr = vm.heap_write().get_str_from_pool_or_new(interned)?;
+    let interned_addr = vm.get_str_from_pool_or_new(thread, interned)?;
     Ok(Some(Value::Ref(interned_addr)))
 }
 
@@ -1,4 +1,5 @@
 use crate::VirtualMachine;
+use crate::error::JvmError;
 use crate::keys::FullyQualifiedMethodKey;
 use crate::native::{NativeRegistry, NativeRet};
 use crate::thread::JavaThreadState;
@@ -16,33 +17,149 @@ pub(super) fn do_register_java_lang_ref_preregistered_natives(
             &native_registry.string_interner,
         ),
         java_lang_ref_reference_refers_to_0,
-    )
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ref/PhantomReference",
+            "refersTo0",
+            "(Ljava/lang/Object;)Z",
+            &native_registry.string_interner,
+        ),
+        java_lang_ref_reference_refers_to_0,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ref/Reference",
+            "clear0",
+            "()V",
+            &native_registry.string_interner,
+        ),
+        java_lang_ref_reference_clear_0,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ref/Reference",
+            "getAndClearReferencePendingList",
+            "()Ljava/lang/ref/Reference;",
+            &native_registry.string_interner,
+        ),
+        java_lang_ref_reference_get_and_clear_reference_pending_list,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ref/Reference",
+            "hasReferencePendingList",
+            "()Z",
+            &native_registry.string_interner,
+        ),
+        java_lang_ref_reference_has_reference_pending_list,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ref/Reference",
+            "waitForReferencePendingList",
+            "()V",
+            &native_registry.string_interner,
+        ),
+        java_lang_ref_reference_wait_for_reference_pending_list,
+    );
+    native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ref/Finalizer",
+            "isFinalizationEnabled",
+            "()Z",
+            &native_registry.string_interner,
+        ),
+        java_lang_ref_finalizer_is_finalization_enabled,
+    );
 }
 
-fn java_lang_ref_reference_refers_to_0(
-    vm: &VirtualMachine,
-    thread: &mut JavaThreadState,
-    args: &[Value],
-) -> NativeRet {
-    let referent_ref = args[0].as_obj_ref()?;
-    let referent_fk = vm.br.reference_referent_fk;
+fn referent_field_offset(vm: &VirtualMachine, thread: &JavaThreadState) -> Result<usize, JvmError> {
     let reference_class_id = vm
         .method_area_write()
         .get_class_id_or_load(vm.br.java_lang_ref_reference_sym, thread.id)?;
-    let referent_field_offset = vm
+    Ok(vm
         .method_area_read()
         .get_instance_class(&reference_class_id)?
-        .get_instance_field(&referent_fk)?
-        .offset;
+        .get_instance_field(&vm.br.reference_referent_fk)?
+        .offset)
+}
+
+fn java_lang_ref_reference_refers_to_0(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let reference_ref = args[0].as_obj_ref()?;
     let referent_value = vm.heap_read().read_field(
-        referent_ref,
+        reference_ref,
+        referent_field_offset(vm, thread)?,
+        AllocationType::Reference,
+    )?;
+    // refersTo(null) tells whether the reference was cleared
+    let o = args[1].as_nullable_obj_ref()?;
+    Ok(Some(Value::Integer(
+        if referent_value.as_nullable_obj_ref()? == o {
+            1
+        } else {
+            0
+        },
+    )))
+}
+
+fn java_lang_ref_reference_clear_0(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let reference_ref = args[0].as_obj_ref()?;
+    let referent_field_offset = referent_field_offset(vm, thread)?;
+    vm.heap_write().write_field(
+        reference_ref,
         referent_field_offset,
+        Value::Null,
         AllocationType::Reference,
     )?;
-    let o = args[1].as_obj_ref()?;
-    Ok(Some(Value::Integer(if referent_value.as_obj_ref()? == o {
-        1
-    } else {
-        0
-    })))
+    Ok(None)
+}
+
+fn java_lang_ref_reference_get_and_clear_reference_pending_list(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Ok(Some(match vm.heap_write().take_reference_pending_list() {
+        0 => Value::Null,
+        head => Value::Ref(head),
+    }))
+}
+
+fn java_lang_ref_reference_has_reference_pending_list(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Ok(Some(Value::Integer(
+        vm.heap_read().has_reference_pending_list() as i32,
+    )))
+}
+
+/// Run by the Reference Handler thread between two batches of pending references.
+fn java_lang_ref_reference_wait_for_reference_pending_list(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    vm.wait_for_reference_pending_list(thread);
+    Ok(None)
+}
+
+/// Objects with a finalize method aren't registered with the Finalizer when allocated, so like
+/// with `--finalization=disabled` the Finalizer thread isn't started.
+fn java_lang_ref_finalizer_is_finalization_enabled(
+    _vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Ok(Some(Value::Integer(0)))
 }
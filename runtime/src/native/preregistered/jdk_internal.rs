@@ -101,33 +101,30 @@ fn jdk_internal_util_system_props_raw_platform_properties(
     let string_class_id = vm
         .method_area_write()
         .get_class_id_or_load(string_class_sym, thread.id)?;
-    let empty_string_stub = vm.heap_write().get_str_from_pool_or_new(empty_string_sym)?;
-    let h = vm.heap_write().alloc_object_array(string_class_id, 40)?;
-    // TODO: fill with real platform properties
-    for i in 0..40 {
-        vm.heap_write()
-            .write_array_element(h, i, Value::Ref(empty_string_stub))?;
-    }
     let encoding_sym = vm.interner().get_or_intern("UTF-8");
-    let enc = vm.heap_write().get_str_from_pool_or_new(encoding_sym)?;
     let line_sep_sym = vm.interner().get_or_intern("\n");
     let file_sep_sym = vm.interner().get_or_intern("/");
     let path_sep_sym = vm.interner().get_or_intern(":");
-    let line_separator_value = vm.heap_write().get_str_from_pool_or_new(line_sep_sym)?;
-    let file_separator_value = vm.heap_write().get_str_from_pool_or_new(file_sep_sym)?;
-    let path_separator_value = vm.heap_write().get_str_from_pool_or_new(path_sep_sym)?;
-    vm.heap_write()
-        .write_array_element(h, 4, Value::Ref(file_separator_value))?;
-    vm.heap_write()
-        .write_array_element(h, 23, Value::Ref(path_separator_value))?;
-    vm.heap_write()
-        .write_array_element(h, 18, Value::Ref(line_separator_value))?;
-    vm.heap_write()
-        .write_array_element(h, 27, Value::Ref(enc))?;
-    vm.heap_write()
-        .write_array_element(h, 29, Value::Ref(enc))?;
-    vm.heap_write()
-        .write_array_element(h, 35, Value::Ref(enc))?;
+    // a collection can only happen before or after the closure, never between its allocations
+    let h = vm.alloc_or_collect(thread, |heap| {
+        let empty_string_stub = heap.get_str_from_pool_or_new(empty_string_sym)?;
+        let enc = heap.get_str_from_pool_or_new(encoding_sym)?;
+        let line_separator_value = heap.get_str_from_pool_or_new(line_sep_sym)?;
+        let file_separator_value = heap.get_str_from_pool_or_new(file_sep_sym)?;
+        let path_separator_value = heap.get_str_from_pool_or_new(path_sep_sym)?;
+        let h = heap.alloc_object_array(string_class_id, 40)?;
+        // TODO: fill with real platform properties
+        for i in 0..40 {
+            heap.write_array_element(h, i, Value::Ref(empty_string_stub))?;
+        }
+        heap.write_array_element(h, 4, Value::Ref(file_separator_value))?;
+        heap.write_array_element(h, 23, Value::Ref(path_separator_value))?;
+        heap.write_array_element(h, 18, Value::Ref(line_separator_value))?;
+        heap.write_array_element(h, 27, Value::Ref(enc))?;
+        heap.write_array_element(h, 29, Value::Ref(enc))?;
+        heap.write_array_element(h, 35, Value::Ref(enc))?;
+        Ok(h)
+    })?;
 
     Ok(Some(Value::Ref(h)))
 }
@@ -143,28 +140,28 @@ fn jdk_internal_util_system_props_raw_vm_properties(
         .method_area_write()
         .get_class_id_or_load(string_class_sym, thread.id)?;
     //TODO: same here, it needs a registry for common interned strings
-    let h = vm.heap_write().alloc_object_array(string_class, 4)?;
-    let java_home_key = vm
-        .heap_write()
-        .get_str_from_pool_or_new(vm.interner().get_or_intern("java.home"))?;
-    let java_home_value = vm.heap_write().get_str_from_pool_or_new(
-        vm.interner()
-            .get_or_intern(vm.config.home.to_str().unwrap()),
-    )?;
-    let sun_page_align_stub = vm
-        .heap_write()
-        .get_str_from_pool_or_new(vm.interner().get_or_intern("sun.nio.PageAlignDirectMemory"))?;
-    let false_str = vm
-        .heap_write()
-        .get_str_from_pool_or_new(vm.interner().get_or_intern("false"))?;
-    vm.heap_write()
-        .write_array_element(h, 0, Value::Ref(java_home_key))?;
-    vm.heap_write()
-        .write_array_element(h, 1, Value::Ref(java_home_value))?;
-    vm.heap_write()
-        .write_array_element(h, 2, Value::Ref(sun_page_align_stub))?;
-    vm.heap_write()
-        .write_array_element(h, 3, Value::Ref(false_str))?;
+    let java_home_key_sym = vm.interner().get_or_intern("java.home");
+    let java_home_value_sym = vm
+        .interner()
+        .get_or_intern(vm.config.home.to_str().unwrap());
+    let sun_page_align_sym = vm.interner().get_or_intern("sun.nio.PageAlignDirectMemory");
+    let false_sym = vm.interner().get_or_intern("false");
+    let h = vm.alloc_or_collect(thread, |heap| {
+        let h = heap.alloc_object_array(string_class, 4)?;
+        for (i, sym) in [
+            java_home_key_sym,
+            java_home_value_sym,
+            sun_page_align_sym,
+            false_sym,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let string_ref = heap.get_str_from_pool_or_new(sym)?;
+            heap.write_array_element(h, i as i32, Value::Ref(string_ref))?;
+        }
+        Ok(h)
+    })?;
     Ok(Some(Value::Ref(h)))
 }
 
@@ -38,9 +38,7 @@ fn jdk_internal_reflect_reflection_get_caller_class(
     let frame_minus_two = thread.stack.peek_frame_at(2)?;
     let method_id = frame_minus_two.method_id();
     let class_id = vm.method_area_read().get_method(&method_id).class_id();
-    let res = vm
-        .method_area_write()
-        .get_mirror_ref_or_create(class_id, &vm.heap)?;
+    let res = vm.get_mirror_ref_or_create(thread, class_id)?;
     Ok(Some(Value::Ref(res)))
 }
 
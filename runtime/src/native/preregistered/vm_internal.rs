@@ -3,7 +3,6 @@ use crate::native::{NativeRegistry, NativeRet};
 use crate::thread::JavaThreadState;
 use crate::vm::Value;
 use crate::{ThreadId, VirtualMachine};
-use tracing_log::log::debug;
 
 pub(super) fn do_register_vm_internal_preregistered_natives(native_registry: &mut NativeRegistry) {
     native_registry.register(
@@ -16,16 +15,13 @@ pub(super) fn do_register_vm_internal_preregistered_natives(native_registry: &mu
     );
 }
 
+/// `Object.clone` of an array, arrays are always cloneable.
 fn vm_internal_clone(
     vm: &VirtualMachine,
-    _thread: &mut JavaThreadState,
+    thread: &mut JavaThreadState,
     args: &[Value],
 ) -> NativeRet {
-    debug!("TODO: Stub: internal clone");
-    let obj = match args[0] {
-        Value::Ref(h) => h,
-        _ => panic!("internal clone: expected object"),
-    };
-    let cloned = vm.heap_write().clone_object(obj)?;
-    Ok(Some(Value::Ref(cloned)))
+    let array_ref = args[0].as_obj_ref()?;
+    let clone_ref = vm.clone_object(thread, array_ref)?;
+    Ok(Some(Value::Ref(clone_ref)))
 }
@@ -195,15 +195,13 @@ fn java_lang_class_get_primitive_class(
     let class_id = vm
         .method_area_write()
         .get_class_id_or_load(vm.interner().get_or_intern(&primitive_name), thread.id)?;
-    let v = vm
-        .method_area_write()
-        .get_mirror_ref_or_create(class_id, &vm.heap)?;
+    let v = vm.get_mirror_ref_or_create(thread, class_id)?;
     Ok(Some(Value::Ref(v)))
 }
 
 fn java_lang_class_init_class_name(
     vm: &VirtualMachine,
-    _thread: &mut JavaThreadState,
+    thread: &mut JavaThreadState,
     args: &[Value],
 ) -> NativeRet {
     let mirror_ref = args
@@ -216,31 +214,34 @@ fn java_lang_class_init_class_name(
     let class_name_fk = vm.br.class_name_fk;
     let target_class_id = vm.method_area_read().get_class_id_by_mirror(&mirror_ref)?;
     let name_sym = vm.method_area_read().get_class(&target_class_id).get_name();
-    let name_ref = vm
-        .heap_write()
-        .alloc_string_from_interned_with_char_mapping(
-            name_sym,
-            Some(&|c| {
-                if c == '/' { '.' } else { c }
-            }),
-        )?;
     let name_field_offset = {
         let ma = vm.method_area_read();
         ma.get_instance_field(&class_class_id, &class_name_fk)?
             .offset
     };
-    vm.heap_write().write_field(
-        mirror_ref,
-        name_field_offset,
-        Value::Ref(name_ref),
-        AllocationType::Reference,
-    )?;
+    // stored in the same go, the mirror may move if the allocation collects
+    let name_ref =
+        vm.alloc_or_collect_with(thread, vec![Value::Ref(mirror_ref)], |heap, objects| {
+            let name_ref = heap.alloc_string_from_interned_with_char_mapping(
+                name_sym,
+                Some(&|c| {
+                    if c == '/' { '.' } else { c }
+                }),
+            )?;
+            heap.write_field(
+                objects[0].as_obj_ref()?,
+                name_field_offset,
+                Value::Ref(name_ref),
+                AllocationType::Reference,
+            )?;
+            Ok(name_ref)
+        })?;
     Ok(Some(Value::Ref(name_ref)))
 }
 
 fn java_lang_class_get_superclass(
     vm: &VirtualMachine,
-    _thread: &mut JavaThreadState,
+    thread: &mut JavaThreadState,
     args: &[Value],
 ) -> NativeRet {
     let mirror_ref = args
@@ -261,9 +262,7 @@ fn java_lang_class_get_superclass(
     if is_interface || is_primitive {
         Ok(Some(Value::Null))
     } else if let Some(super_id) = super_class_id {
-        let super_mirror_ref = vm
-            .method_area_write()
-            .get_mirror_ref_or_create(super_id, &vm.heap)?;
+        let super_mirror_ref = vm.get_mirror_ref_or_create(thread, super_id)?;
         Ok(Some(Value::Ref(super_mirror_ref)))
     } else {
         Ok(Some(Value::Null))
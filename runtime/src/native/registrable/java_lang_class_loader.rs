@@ -1,22 +1,157 @@
+use crate::error::{JavaExceptionKind, JvmError};
+use crate::heap::method_area::MethodArea;
+use crate::keys::FullyQualifiedMethodKey;
 use crate::native::NativeRet;
 use crate::thread::JavaThreadState;
 use crate::vm::Value;
-use crate::{ThreadId, VirtualMachine};
+use crate::{VirtualMachine, throw_exception};
 
 pub(super) fn java_lang_class_loader_register_natives(
     vm: &VirtualMachine,
     _thread: &mut JavaThreadState,
     _args: &[Value],
 ) -> NativeRet {
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ClassLoader",
+            "defineClass1",
+            "(Ljava/lang/ClassLoader;Ljava/lang/String;[BIILjava/security/ProtectionDomain;Ljava/lang/String;)Ljava/lang/Class;",
+            &vm.string_interner,
+        ),
+        java_lang_class_loader_define_class_1,
+    );
+
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ClassLoader",
+            "defineClass2",
+            "(Ljava/lang/ClassLoader;Ljava/lang/String;Ljava/nio/ByteBuffer;IILjava/security/ProtectionDomain;Ljava/lang/String;)Ljava/lang/Class;",
+            &vm.string_interner,
+        ),
+        java_lang_class_loader_define_class_2,
+    );
+
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ClassLoader",
+            "findLoadedClass0",
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+            &vm.string_interner,
+        ),
+        java_lang_class_loader_find_loaded_class_0,
+    );
+
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/ClassLoader",
+            "findBootstrapClass",
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+            &vm.string_interner,
+        ),
+        java_lang_class_loader_find_bootstrap_class,
+    );
+
     Ok(None)
 }
 
-/*
-fn java_lang_class_loader_(
+/// `ClassLoader.defineClass(String, byte[], ...)`, the class belongs to the given loader.
+fn java_lang_class_loader_define_class_1(
     vm: &VirtualMachine,
     thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let loader_ref = args[0].as_obj_ref()?;
+    let bytes_ref = args[2].as_obj_ref()?;
+    let offset = args[3].as_int()?;
+    let length = args[4].as_int()?;
+
+    let data = vm
+        .heap_read()
+        .copy_byte_array_range(bytes_ref, offset, length)?;
+    let cf = MethodArea::parse_class_file(data)?;
+    if let Value::Ref(name_ref) = args[1] {
+        let name = vm
+            .heap_read()
+            .get_rust_string_from_java_string(name_ref)?
+            .replace('.', "/");
+        let actual_name = MethodArea::get_class_file_name(&cf)?;
+        if name != actual_name {
+            throw_exception!(
+                NoClassDefFoundError,
+                "{} (wrong name: {})",
+                name,
+                actual_name
+            )?
+        }
+    }
+    let class_id = vm.define_class(thread, Some(loader_ref), cf, false)?;
+    let mirror_ref = vm.get_mirror_ref_or_create(thread, class_id)?;
+    Ok(Some(Value::Ref(mirror_ref)))
+}
+
+/// Only reached with direct buffers, `defineClass(String, ByteBuffer, ...)` copies heap
+/// buffers into an array and goes through `defineClass1`.
+fn java_lang_class_loader_define_class_2(
+    _vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
     _args: &[Value],
 ) -> NativeRet {
-    Ok(Some(Value::Ref(vm.get_thread(&thread_id).thread_obj)))
+    throw_exception!(
+        UnsupportedOperationException,
+        "Defining classes from direct buffers is not supported"
+    )
+}
+
+/// The class if this loader defined it or initiated its loading, null otherwise.
+fn java_lang_class_loader_find_loaded_class_0(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let loader_ref = args[0].as_obj_ref()?;
+    let name = vm
+        .heap_read()
+        .get_rust_string_from_java_string(args[1].as_obj_ref()?)?
+        .replace('.', "/");
+    let name_sym = vm.interner().get_or_intern(name);
+    let class_id = {
+        let ma = vm.method_area_read();
+        ma.find_class_loader_id(&loader_ref)
+            .and_then(|loader_id| ma.find_loaded_class(Some(loader_id), name_sym))
+    };
+    match class_id {
+        Some(class_id) => Ok(Some(Value::Ref(
+            vm.get_mirror_ref_or_create(thread, class_id)?,
+        ))),
+        None => Ok(Some(Value::Null)),
+    }
+}
+
+/// Loads the class with the bootstrap loader, null when it can't find it. Classes of the platform
+/// modules and of the classpath belong to the platform and app loaders, the bootstrap loader never
+/// looks for them.
+fn java_lang_class_loader_find_bootstrap_class(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let name = vm
+        .heap_read()
+        .get_rust_string_from_java_string(args[0].as_obj_ref()?)?
+        .replace('.', "/");
+    let name_sym = vm.interner().get_or_intern(name);
+    let loaded = vm
+        .method_area_write()
+        .get_class_id_or_load(name_sym, thread.id);
+    match loaded {
+        Ok(class_id) => Ok(Some(Value::Ref(
+            vm.get_mirror_ref_or_create(thread, class_id)?,
+        ))),
+        Err(JvmError::JavaException(exception))
+            if exception.kind == JavaExceptionKind::ClassNotFoundException =>
+        {
+            Ok(Some(Value::Null))
+        }
+        Err(e) => Err(e),
+    }
 }
- */
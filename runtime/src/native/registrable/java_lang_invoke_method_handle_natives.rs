@@ -0,0 +1,469 @@
+use crate::error::JvmError;
+use crate::heap::HeapRef;
+use crate::interpreter::method_handles::{
+    REF_INVOKE_INTERFACE, REF_INVOKE_VIRTUAL, box_long, field_offset, find_method_for_ref_kind,
+    member_name_vmtarget, method_type_to_descriptor, mirror_to_type_descriptor,
+    new_resolved_method_name,
+};
+use crate::keys::{ClassId, FieldKey, FullyQualifiedMethodKey, MethodKey};
+use crate::native::NativeRet;
+use crate::thread::JavaThreadState;
+use crate::vm::Value;
+use crate::{VirtualMachine, throw_exception};
+use lagertha_common::jtype::AllocationType;
+use tracing_log::log::debug;
+
+// MemberName flags, see java.lang.invoke.MethodHandleNatives.Constants
+const MN_IS_METHOD: i32 = 0x00010000;
+const MN_IS_CONSTRUCTOR: i32 = 0x00020000;
+const MN_IS_FIELD: i32 = 0x00040000;
+const MN_REFERENCE_KIND_SHIFT: i32 = 24;
+const MN_REFERENCE_KIND_MASK: i32 = 0x0F;
+const ACCESS_FLAGS_MASK: i32 = 0xFFFF;
+const ACC_STATIC: i32 = 0x0008;
+
+pub(super) fn java_lang_invoke_method_handle_natives_register_natives(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "init",
+            "(Ljava/lang/invoke/MemberName;Ljava/lang/Object;)V",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_init,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "expand",
+            "(Ljava/lang/invoke/MemberName;)V",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_expand,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "resolve",
+            "(Ljava/lang/invoke/MemberName;Ljava/lang/Class;IZ)Ljava/lang/invoke/MemberName;",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_resolve,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "objectFieldOffset",
+            "(Ljava/lang/invoke/MemberName;)J",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_object_field_offset,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "staticFieldOffset",
+            "(Ljava/lang/invoke/MemberName;)J",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_static_field_offset,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "staticFieldBase",
+            "(Ljava/lang/invoke/MemberName;)Ljava/lang/Object;",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_static_field_base,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "getMemberVMInfo",
+            "(Ljava/lang/invoke/MemberName;)Ljava/lang/Object;",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_get_member_vm_info,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "setCallSiteTargetNormal",
+            "(Ljava/lang/invoke/CallSite;Ljava/lang/invoke/MethodHandle;)V",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_set_call_site_target,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "setCallSiteTargetVolatile",
+            "(Ljava/lang/invoke/CallSite;Ljava/lang/invoke/MethodHandle;)V",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_set_call_site_target,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "clearCallSiteContext",
+            "(Ljava/lang/invoke/MethodHandleNatives$CallSiteContext;)V",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_clear_call_site_context,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/invoke/MethodHandleNatives",
+            "getNamedCon",
+            "(I[Ljava/lang/Object;)I",
+            &vm.string_interner,
+        ),
+        java_lang_invoke_method_handle_natives_get_named_con,
+    );
+
+    Ok(None)
+}
+
+struct MemberNameParts {
+    clazz: HeapRef,
+    name: Option<HeapRef>,
+    ty: Option<HeapRef>,
+    flags: i32,
+}
+
+fn read_member_name(
+    vm: &VirtualMachine,
+    member_name: HeapRef,
+) -> Result<MemberNameParts, JvmError> {
+    let br = vm.br();
+    let clazz_offset = field_offset(vm, member_name, &br.member_name_clazz_fk)?;
+    let name_offset = field_offset(vm, member_name, &br.member_name_name_fk)?;
+    let type_offset = field_offset(vm, member_name, &br.member_name_type_fk)?;
+    let flags_offset = field_offset(vm, member_name, &br.member_name_flags_fk)?;
+    let heap = vm.heap_read();
+    Ok(MemberNameParts {
+        clazz: heap
+            .read_field(member_name, clazz_offset, AllocationType::Reference)?
+            .as_obj_ref()?,
+        name: heap
+            .read_field(member_name, name_offset, AllocationType::Reference)?
+            .as_nullable_obj_ref()?,
+        ty: heap
+            .read_field(member_name, type_offset, AllocationType::Reference)?
+            .as_nullable_obj_ref()?,
+        flags: heap
+            .read_field(member_name, flags_offset, AllocationType::Int)?
+            .as_int()?,
+    })
+}
+
+/// `MemberName.type` is either a descriptor string, a `Class` (fields) or a `MethodType` (methods)
+fn member_type_descriptor(vm: &VirtualMachine, ty: HeapRef) -> Result<String, JvmError> {
+    let class_id = vm.heap_read().get_class_id(ty)?;
+    if class_id == vm.br().get_java_lang_string_id()? {
+        vm.heap_read().get_rust_string_from_java_string(ty)
+    } else if class_id == vm.br().get_java_lang_class_id()? {
+        mirror_to_type_descriptor(vm, ty)
+    } else {
+        method_type_to_descriptor(vm, ty)
+    }
+}
+
+fn member_field_key(
+    vm: &VirtualMachine,
+    parts: &MemberNameParts,
+) -> Result<(ClassId, FieldKey), JvmError> {
+    let (Some(name), Some(ty)) = (parts.name, parts.ty) else {
+        return throw_exception!(InternalError, "MemberName without name or type");
+    };
+    let class_id = vm.method_area_read().get_class_id_by_mirror(&parts.clazz)?;
+    let name = vm.heap_read().get_rust_string_from_java_string(name)?;
+    let desc = member_type_descriptor(vm, ty)?;
+    let field_key = FieldKey {
+        name: vm.interner().get_or_intern(name),
+        desc: vm.interner().get_or_intern(desc),
+    };
+    Ok((class_id, field_key))
+}
+
+fn java_lang_invoke_method_handle_natives_init(
+    _vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Err(JvmError::Todo(
+        "java.lang.invoke.MethodHandleNatives.init: reflective members are not supported yet"
+            .to_string(),
+    ))
+}
+
+fn java_lang_invoke_method_handle_natives_expand(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let member_name = args[0].as_obj_ref()?;
+    let parts = read_member_name(vm, member_name)?;
+    if parts.flags & (MN_IS_METHOD | MN_IS_CONSTRUCTOR) == 0 {
+        return Ok(None);
+    }
+    let method_id = member_name_vmtarget(vm, member_name)?;
+    let (name_sym, desc_sym) = {
+        let ma = vm.method_area_read();
+        let method = ma.get_method(&method_id);
+        (method.name, method.desc)
+    };
+    let name_offset = field_offset(vm, member_name, &vm.br().member_name_name_fk)?;
+    let type_offset = field_offset(vm, member_name, &vm.br().member_name_type_fk)?;
+    // stored as they are interned, interning may collect and move the member name
+    vm.alloc_or_collect_with(thread, vec![Value::Ref(member_name)], |heap, objects| {
+        let member_name = objects[0].as_obj_ref()?;
+        if parts.name.is_none() {
+            let name_ref = heap.get_str_from_pool_or_new(name_sym)?;
+            heap.write_field(
+                member_name,
+                name_offset,
+                Value::Ref(name_ref),
+                AllocationType::Reference,
+            )?;
+        }
+        if parts.ty.is_none() {
+            let desc_ref = heap.get_str_from_pool_or_new(desc_sym)?;
+            heap.write_field(
+                member_name,
+                type_offset,
+                Value::Ref(desc_ref),
+                AllocationType::Reference,
+            )?;
+        }
+        Ok(member_name)
+    })?;
+    Ok(None)
+}
+
+fn java_lang_invoke_method_handle_natives_resolve(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let mut member_name = args[0].as_obj_ref()?;
+    let speculative_resolve = args[3].as_int()? != 0;
+    let parts = read_member_name(vm, member_name)?;
+    let ref_kind = (parts.flags >> MN_REFERENCE_KIND_SHIFT) & MN_REFERENCE_KIND_MASK;
+
+    let flags = if parts.flags & (MN_IS_METHOD | MN_IS_CONSTRUCTOR) != 0 {
+        let (class_id, FieldKey { name, desc }) = member_field_key(vm, &parts)?;
+        let method_key = MethodKey { name, desc };
+        let Some(method_id) = find_method_for_ref_kind(vm, class_id, ref_kind, &method_key)? else {
+            if speculative_resolve {
+                return Ok(Some(Value::Null));
+            }
+            let class_sym = vm.method_area_read().get_class(&class_id).get_name();
+            return throw_exception!(NoSuchMethodError, method_key: method_key, class_sym: class_sym);
+        };
+        let method_offset = field_offset(vm, member_name, &vm.br().member_name_method_fk)?;
+        // the member name is an argument, it isn't rooted in a frame
+        let (resolved_ref, rooted) = thread.with_handles(vec![Value::Ref(member_name)], |thread| {
+            new_resolved_method_name(thread, vm, method_id)
+        });
+        member_name = rooted[0].as_obj_ref()?;
+        vm.heap_write().write_field(
+            member_name,
+            method_offset,
+            Value::Ref(resolved_ref?),
+            AllocationType::Reference,
+        )?;
+        let (method_flags, declared_in_interface) = {
+            let ma = vm.method_area_read();
+            let method = ma.get_method(&method_id);
+            (
+                method.get_raw_flags(),
+                ma.get_class(&method.class_id()).is_interface(),
+            )
+        };
+        let ref_kind = match ref_kind {
+            REF_INVOKE_VIRTUAL if declared_in_interface => REF_INVOKE_INTERFACE,
+            REF_INVOKE_INTERFACE if !declared_in_interface => REF_INVOKE_VIRTUAL,
+            other => other,
+        };
+        (method_flags & ACCESS_FLAGS_MASK)
+            | (parts.flags & (MN_IS_METHOD | MN_IS_CONSTRUCTOR))
+            | (ref_kind << MN_REFERENCE_KIND_SHIFT)
+    } else if parts.flags & MN_IS_FIELD != 0 {
+        let (class_id, field_key) = member_field_key(vm, &parts)?;
+        let field_flags = {
+            let ma = vm.method_area_read();
+            if let Ok(field) = ma.get_instance_field(&class_id, &field_key) {
+                Some(field.flags.get_raw_i32())
+            } else if ma
+                .resolve_static_field_actual_class_id(class_id, &field_key)
+                .is_ok()
+            {
+                Some(ACC_STATIC)
+            } else {
+                None
+            }
+        };
+        let Some(field_flags) = field_flags else {
+            if speculative_resolve {
+                return Ok(Some(Value::Null));
+            }
+            return throw_exception!(
+                NoSuchFieldError,
+                vm.interner().resolve(&field_key.name).to_string()
+            );
+        };
+        (field_flags & ACCESS_FLAGS_MASK) | (parts.flags & !ACCESS_FLAGS_MASK)
+    } else {
+        return throw_exception!(InternalError, "Unrecognized MemberName format");
+    };
+
+    let flags_offset = field_offset(vm, member_name, &vm.br().member_name_flags_fk)?;
+    vm.heap_write().write_field(
+        member_name,
+        flags_offset,
+        Value::Integer(flags),
+        AllocationType::Int,
+    )?;
+    Ok(Some(Value::Ref(member_name)))
+}
+
+fn java_lang_invoke_method_handle_natives_object_field_offset(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let member_name = args[0].as_obj_ref()?;
+    let parts = read_member_name(vm, member_name)?;
+    let (class_id, field_key) = member_field_key(vm, &parts)?;
+    let offset = vm
+        .method_area_read()
+        .get_instance_field(&class_id, &field_key)?
+        .offset;
+    Ok(Some(Value::Long(offset as i64)))
+}
+
+/// Static fields live in their class, not on the heap. `Unsafe` reaches them with the mirror of
+/// the declaring class as base and the tagged offset of `StaticField::unsafe_offset`.
+fn java_lang_invoke_method_handle_natives_static_field_offset(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let parts = read_member_name(vm, args[0].as_obj_ref()?)?;
+    let (class_id, field_key) = member_field_key(vm, &parts)?;
+    let offset = vm
+        .method_area_read()
+        .resolve_static_field(class_id, &field_key)?
+        .1
+        .unsafe_offset();
+    Ok(Some(Value::Long(offset)))
+}
+
+fn java_lang_invoke_method_handle_natives_static_field_base(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let parts = read_member_name(vm, args[0].as_obj_ref()?)?;
+    let (class_id, field_key) = member_field_key(vm, &parts)?;
+    let holder_id = vm
+        .method_area_read()
+        .resolve_static_field(class_id, &field_key)?
+        .0;
+    let mirror_ref = vm.get_mirror_ref_or_create(thread, holder_id)?;
+    Ok(Some(Value::Ref(mirror_ref)))
+}
+
+/// Returns `{vmindex, vmtarget}`, only used by assertions in MemberName.
+fn java_lang_invoke_method_handle_natives_get_member_vm_info(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let member_name = args[0].as_obj_ref()?;
+    let parts = read_member_name(vm, member_name)?;
+    let ref_kind = (parts.flags >> MN_REFERENCE_KIND_SHIFT) & MN_REFERENCE_KIND_MASK;
+    let (vmindex, vmtarget) = if parts.flags & MN_IS_FIELD != 0 {
+        let (class_id, field_key) = member_field_key(vm, &parts)?;
+        let ma = vm.method_area_read();
+        let offset = match ma.get_instance_field(&class_id, &field_key) {
+            Ok(field) => field.offset as i64,
+            Err(_) => ma
+                .resolve_static_field(class_id, &field_key)
+                .map(|(_, field)| field.unsafe_offset())
+                .unwrap_or(0),
+        };
+        (offset, parts.clazz)
+    } else if ref_kind == REF_INVOKE_VIRTUAL || ref_kind == REF_INVOKE_INTERFACE {
+        let method_id = member_name_vmtarget(vm, member_name)?;
+        let ma = vm.method_area_read();
+        let method = ma.get_method(&method_id);
+        let key = MethodKey {
+            name: method.name,
+            desc: method.desc,
+        };
+        let vtable_index = ma
+            .get_instance_class(&method.class_id())
+            .ok()
+            .and_then(|class| class.get_vtable_index().ok()?.get(&key).copied())
+            .unwrap_or(0);
+        (vtable_index as i64, member_name)
+    } else {
+        (-1, member_name)
+    };
+    // rooted while the index is boxed, that runs java code
+    let mark = thread.push_handles(vec![Value::Ref(vmtarget)]);
+    let vmindex_ref = box_long(thread, vm, vmindex);
+    let vmtarget = thread.pop_handles(mark)[0];
+    let vmindex_ref = vmindex_ref?;
+    let object_class_id = vm.br().get_java_lang_object_id()?;
+    let info_ref = vm.alloc_or_collect_with(
+        thread,
+        vec![Value::Ref(vmindex_ref), vmtarget],
+        |heap, objects| {
+            let info_ref = heap.alloc_object_array(object_class_id, 2)?;
+            heap.write_array_element(info_ref, 0, objects[0])?;
+            heap.write_array_element(info_ref, 1, objects[1])?;
+            Ok(info_ref)
+        },
+    )?;
+    Ok(Some(Value::Ref(info_ref)))
+}
+
+fn java_lang_invoke_method_handle_natives_set_call_site_target(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let call_site = args[0].as_obj_ref()?;
+    let target_offset = field_offset(vm, call_site, &vm.br().call_site_target_fk)?;
+    vm.heap_write()
+        .write_field(call_site, target_offset, args[1], AllocationType::Reference)?;
+    Ok(None)
+}
+
+fn java_lang_invoke_method_handle_natives_clear_call_site_context(
+    _vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    debug!("TODO: Stub: java.lang.invoke.MethodHandleNatives.clearCallSiteContext");
+    Ok(None)
+}
+
+/// Used only by `MethodHandleNatives.verifyConstants` to cross-check constants with the VM,
+/// leaving the box empty ends the check right away.
+fn java_lang_invoke_method_handle_natives_get_named_con(
+    _vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Ok(Some(Value::Integer(0)))
+}
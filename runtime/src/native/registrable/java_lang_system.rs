@@ -106,16 +106,15 @@ fn java_lang_system_set_err_0(
 }
 
 fn java_lang_system_identity_hash_code(
-    _vm: &VirtualMachine,
-    _thread: &mut JavaThreadState,
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
     args: &[Value],
 ) -> NativeRet {
-    debug!("TODO: Stub: java.lang.System.identityHashCode");
-    if let Value::Ref(h) = &args[0] {
-        Ok(Some(Value::Integer(*h as i32)))
-    } else {
-        panic!("java.lang.System.identityHashCode: expected object as argument");
-    }
+    let hash = match args[0].as_nullable_obj_ref()? {
+        Some(obj_ref) => vm.identity_hash(thread, obj_ref),
+        None => 0,
+    };
+    Ok(Some(Value::Integer(hash)))
 }
 
 fn java_lang_system_set_in_0(
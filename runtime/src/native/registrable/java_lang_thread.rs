@@ -18,6 +18,69 @@ pub(super) fn java_lang_thread_register_natives(
         ),
         java_lang_thread_current_thread,
     );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Thread",
+            "start0",
+            "()V",
+            &vm.string_interner,
+        ),
+        java_lang_thread_start0,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Thread",
+            "interrupt0",
+            "()V",
+            &vm.string_interner,
+        ),
+        java_lang_thread_interrupt0,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Thread",
+            "clearInterruptEvent",
+            "()V",
+            &vm.string_interner,
+        ),
+        java_lang_thread_clear_interrupt_event,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Thread",
+            "setPriority0",
+            "(I)V",
+            &vm.string_interner,
+        ),
+        java_lang_thread_set_priority0,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Thread",
+            "sleepNanos0",
+            "(J)V",
+            &vm.string_interner,
+        ),
+        java_lang_thread_sleep_nanos0,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Thread",
+            "yield0",
+            "()V",
+            &vm.string_interner,
+        ),
+        java_lang_thread_yield0,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "java/lang/Thread",
+            "holdsLock",
+            "(Ljava/lang/Object;)Z",
+            &vm.string_interner,
+        ),
+        java_lang_thread_holds_lock,
+    );
     Ok(None)
 }
 
@@ -28,3 +91,74 @@ fn java_lang_thread_current_thread(
 ) -> NativeRet {
     Ok(Some(Value::Ref(thread.thread_obj)))
 }
+
+fn java_lang_thread_start0(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    vm.start_thread(args[0].as_obj_ref()?)?;
+    Ok(None)
+}
+
+/// Called by `Thread.interrupt` after the interrupted field is set, wakes the thread up if it waits.
+/// A thread that isn't alive only keeps the interrupted field.
+fn java_lang_thread_interrupt0(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    if let Some(thread_id) = vm.get_thread_id(args[0].as_obj_ref()?)? {
+        vm.interrupt_thread(thread_id);
+    }
+    Ok(None)
+}
+
+/// Only resets the windows interrupt event in hotspot, there is nothing to clear here.
+fn java_lang_thread_clear_interrupt_event(
+    _vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Ok(None)
+}
+
+/// The priority is kept in the thread object, like hotspot on linux by default the OS threads
+/// all run with the same priority.
+fn java_lang_thread_set_priority0(
+    _vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    Ok(None)
+}
+
+/// Throws `InterruptedException` if the thread is interrupted before or while it sleeps.
+fn java_lang_thread_sleep_nanos0(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    vm.sleep_thread(thread, args[0].as_long()?)?;
+    Ok(None)
+}
+
+fn java_lang_thread_yield0(
+    _vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    _args: &[Value],
+) -> NativeRet {
+    std::thread::yield_now();
+    Ok(None)
+}
+
+fn java_lang_thread_holds_lock(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let obj_ref = args[0].as_obj_ref()?;
+    Ok(Some(Value::Integer(
+        vm.monitor_holds_lock(thread.id, obj_ref) as i32,
+    )))
+}
@@ -1,12 +1,15 @@
 use crate::error::JvmError;
-use crate::heap::Heap;
+use crate::heap::{Heap, HeapRef};
 use crate::interpreter::Interpreter;
 use crate::keys::FullyQualifiedMethodKey;
 use crate::native::NativeRet;
+use crate::rt::ClassLike;
+use crate::rt::field::StaticField;
 use crate::thread::JavaThreadState;
 use crate::vm::Value;
 use crate::{ThreadId, VirtualMachine};
 use lagertha_common::jtype::AllocationType;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing_log::log::debug;
 
 pub(super) fn jdk_internal_misc_unsafe_register_natives(
@@ -106,6 +109,15 @@ pub(super) fn jdk_internal_misc_unsafe_register_natives(
         ),
         jdk_internal_misc_unsafe_ensure_class_initialized_0,
     );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "shouldBeInitialized0",
+            "(Ljava/lang/Class;)Z",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_should_be_initialized_0,
+    );
     vm.native_registry.register(
         FullyQualifiedMethodKey::new_with_str(
             "jdk/internal/misc/Unsafe",
@@ -143,6 +155,96 @@ pub(super) fn jdk_internal_misc_unsafe_register_natives(
         ),
         jdk_internal_misc_unsafe_get_int,
     );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "park",
+            "(ZJ)V",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_park,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "unpark",
+            "(Ljava/lang/Object;)V",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_unpark,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "getReference",
+            "(Ljava/lang/Object;J)Ljava/lang/Object;",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_get_reference,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "putReference",
+            "(Ljava/lang/Object;JLjava/lang/Object;)V",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_put_reference,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "putReferenceVolatile",
+            "(Ljava/lang/Object;JLjava/lang/Object;)V",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_put_reference,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "putInt",
+            "(Ljava/lang/Object;JI)V",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_put_int,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "putIntVolatile",
+            "(Ljava/lang/Object;JI)V",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_put_int,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "putLong",
+            "(Ljava/lang/Object;JJ)V",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_put_long,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "getBoolean",
+            "(Ljava/lang/Object;J)Z",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_get_boolean,
+    );
+    vm.native_registry.register(
+        FullyQualifiedMethodKey::new_with_str(
+            "jdk/internal/misc/Unsafe",
+            "putBoolean",
+            "(Ljava/lang/Object;JZ)V",
+            &vm.string_interner,
+        ),
+        jdk_internal_misc_unsafe_put_boolean,
+    );
 
     Ok(None)
 }
@@ -170,6 +272,22 @@ fn jdk_internal_misc_unsafe_ensure_class_initialized_0(
     Ok(None)
 }
 
+fn jdk_internal_misc_unsafe_should_be_initialized_0(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let mirror_ref = args[1].as_obj_ref()?;
+    let ma = vm.method_area_read();
+    let class_id = ma.get_class_id_by_mirror(&mirror_ref)?;
+    // arrays and primitives have no initialization
+    let should_be_initialized = ma
+        .get_class_like(&class_id)
+        .map(|class| !class.is_initialized_or_initializing())
+        .unwrap_or(false);
+    Ok(Some(Value::Integer(should_be_initialized as i32)))
+}
+
 fn jdk_internal_misc_unsafe_get_int_volatile(
     vm: &VirtualMachine,
     _thread: &mut JavaThreadState,
@@ -178,10 +296,7 @@ fn jdk_internal_misc_unsafe_get_int_volatile(
     debug!("TODO: Stub: jdk.internal.misc.Unsafe.getIntVolatile");
     let base = args[1].as_obj_ref()?;
     let off = args[2].as_long()?;
-    let value = vm
-        .heap_read()
-        .read_field(base, off as usize, AllocationType::Int)?
-        .as_int()?;
+    let value = read_unsafe(vm, base, off, AllocationType::Int)?.as_int()?;
     Ok(Some(Value::Integer(value)))
 }
 
@@ -192,10 +307,7 @@ fn jdk_internal_misc_unsafe_get_long(
 ) -> NativeRet {
     let base = args[1].as_obj_ref()?;
     let off = args[2].as_long()?;
-    let value = vm
-        .heap_read()
-        .read_field(base, off as usize, AllocationType::Long)?
-        .as_long()?;
+    let value = read_unsafe(vm, base, off, AllocationType::Long)?.as_long()?;
     Ok(Some(Value::Long(value)))
 }
 
@@ -206,10 +318,7 @@ fn jdk_internal_misc_unsafe_get_int(
 ) -> NativeRet {
     let base = args[1].as_obj_ref()?;
     let off = args[2].as_long()?;
-    let value = vm
-        .heap_read()
-        .read_field(base, off as usize, AllocationType::Int)?
-        .as_int()?;
+    let value = read_unsafe(vm, base, off, AllocationType::Int)?.as_int()?;
     Ok(Some(Value::Integer(value)))
 }
 
@@ -244,11 +353,11 @@ fn jdk_internal_misc_unsafe_compare_and_set_int(
         _ => panic!("compareAndSetInt: expected int new_value"),
     };
 
-    let current = vm
-        .heap_read()
-        .read_field(object, offset, AllocationType::Int)?;
+    // The compare and the store must happen under one guard, other threads may CAS the same field
+    let mut heap = vm.heap_write();
+    let current = heap.read_field(object, offset, AllocationType::Int)?;
     if current == Value::Integer(expected) {
-        vm.heap_write().write_field(
+        heap.write_field(
             object,
             offset,
             Value::Integer(new_value),
@@ -282,17 +391,11 @@ fn jdk_internal_misc_unsafe_compare_and_set_long(
         Value::Long(l) => l,
         _ => panic!("jdk.internal.misc.Unsafe.compareAndSetLong: expected long new value"),
     };
-    let object_field_value = vm
-        .heap_read()
-        .read_field(object, offset, AllocationType::Long)?;
+    let mut heap = vm.heap_write();
+    let object_field_value = heap.read_field(object, offset, AllocationType::Long)?;
     if let Value::Long(current_value) = object_field_value {
         if current_value == expected {
-            vm.heap_write().write_field(
-                object,
-                offset,
-                Value::Long(new_value),
-                AllocationType::Long,
-            )?;
+            heap.write_field(object, offset, Value::Long(new_value), AllocationType::Long)?;
             Ok(Some(Value::Integer(1)))
         } else {
             Ok(Some(Value::Integer(0)))
@@ -318,11 +421,7 @@ fn jdk_internal_misc_unsafe_get_reference_volatile(
         Value::Long(x) => x,
         _ => panic!("Unsafe.getReferenceVolatile expects a long offset"),
     };
-    Ok(Some(vm.heap_read().read_field(
-        base,
-        off as usize,
-        AllocationType::Reference,
-    )?))
+    Ok(Some(read_unsafe(vm, base, off, AllocationType::Reference)?))
 }
 
 fn jdk_internal_misc_unsafe_object_field_offset_1(
@@ -408,20 +507,14 @@ fn jdk_internal_misc_unsafe_compare_and_set_reference(
         _ => panic!("compareAndSetReference: expected object or null"),
     };
     let new_value = match args[4] {
-        Value::Ref(h) => h,
-        _ => panic!("compareAndSetReference: expected object"),
+        v @ (Value::Ref(_) | Value::Null) => v,
+        _ => panic!("compareAndSetReference: expected object or null"),
     };
 
-    let current = vm
-        .heap_read()
-        .read_field(object, offset, AllocationType::Reference)?;
+    let mut heap = vm.heap_write();
+    let current = heap.read_field(object, offset, AllocationType::Reference)?;
     if current == expected {
-        vm.heap_write().write_field(
-            object,
-            offset,
-            Value::Ref(new_value),
-            AllocationType::Reference,
-        )?;
+        heap.write_field(object, offset, new_value, AllocationType::Reference)?;
         Ok(Some(Value::Integer(1)))
     } else {
         Ok(Some(Value::Integer(0)))
@@ -449,7 +542,162 @@ fn jdk_internal_misc_unsafe_put_byte(
         .ok_or(JvmError::Todo("putByte: missing 3 argument".to_string()))?
         .as_int()?;
 
-    vm.heap_write()
-        .write_field(object, offset, Value::Integer(value), AllocationType::Byte)?;
+    write_unsafe(
+        vm,
+        object,
+        offset as i64,
+        Value::Integer(value),
+        AllocationType::Byte,
+    )?;
+    Ok(None)
+}
+
+/// `time` is a deadline in milliseconds since the epoch if `isAbsolute`, a timeout in nanoseconds
+/// otherwise, 0 parks without a timeout.
+fn jdk_internal_misc_unsafe_park(
+    vm: &VirtualMachine,
+    thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let is_absolute = args[1].as_int()? != 0;
+    let time = args[2].as_long()?;
+    if time < 0 || (is_absolute && time == 0) {
+        return Ok(None);
+    }
+    let timeout = if is_absolute {
+        let deadline = UNIX_EPOCH + Duration::from_millis(time as u64);
+        match deadline.duration_since(SystemTime::now()) {
+            Ok(timeout) => Some(timeout),
+            // already passed
+            Err(_) => return Ok(None),
+        }
+    } else {
+        (time > 0).then(|| Duration::from_nanos(time as u64))
+    };
+    vm.park_thread(thread, timeout)?;
+    Ok(None)
+}
+
+fn jdk_internal_misc_unsafe_unpark(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    if let Some(thread_obj) = args[1].as_nullable_obj_ref()? {
+        vm.unpark_thread(thread_obj)?;
+    }
+    Ok(None)
+}
+
+fn jdk_internal_misc_unsafe_get_reference(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let base = args[1].as_obj_ref()?;
+    let off = args[2].as_long()?;
+    Ok(Some(read_unsafe(vm, base, off, AllocationType::Reference)?))
+}
+
+fn jdk_internal_misc_unsafe_get_boolean(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let base = args[1].as_obj_ref()?;
+    let off = args[2].as_long()?;
+    Ok(Some(read_unsafe(vm, base, off, AllocationType::Boolean)?))
+}
+
+/// `putReference` and `putReferenceVolatile`, every store is sequentially consistent here.
+fn jdk_internal_misc_unsafe_put_reference(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let base = args[1].as_obj_ref()?;
+    let off = args[2].as_long()?;
+    write_unsafe(vm, base, off, args[3], AllocationType::Reference)?;
+    Ok(None)
+}
+
+/// `putInt` and `putIntVolatile`.
+fn jdk_internal_misc_unsafe_put_int(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let base = args[1].as_obj_ref()?;
+    let off = args[2].as_long()?;
+    write_unsafe(vm, base, off, args[3], AllocationType::Int)?;
     Ok(None)
 }
+
+fn jdk_internal_misc_unsafe_put_long(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let base = args[1].as_obj_ref()?;
+    let off = args[2].as_long()?;
+    write_unsafe(vm, base, off, args[3], AllocationType::Long)?;
+    Ok(None)
+}
+
+fn jdk_internal_misc_unsafe_put_boolean(
+    vm: &VirtualMachine,
+    _thread: &mut JavaThreadState,
+    args: &[Value],
+) -> NativeRet {
+    let base = args[1].as_obj_ref()?;
+    let off = args[2].as_long()?;
+    write_unsafe(vm, base, off, args[3], AllocationType::Boolean)?;
+    Ok(None)
+}
+
+/// Reads the field or element at `offset` of `base`. A static field offset, see
+/// `StaticField::unsafe_offset`, reads the static field of the class `base` is the mirror of.
+fn read_unsafe(
+    vm: &VirtualMachine,
+    base: HeapRef,
+    offset: i64,
+    field_type: AllocationType,
+) -> Result<Value, JvmError> {
+    match StaticField::slot_from_unsafe_offset(offset) {
+        Some(slot) => {
+            let ma = vm.method_area_read();
+            let class_id = ma.get_class_id_by_mirror(&base)?;
+            let value = *ma
+                .get_static_field_by_slot(&class_id, slot)?
+                .value
+                .read()
+                .unwrap();
+            Ok(value)
+        }
+        None => vm.heap_read().read_field(base, offset as usize, field_type),
+    }
+}
+
+/// Writes the field or element at `offset` of `base`, or a static field like `read_unsafe`.
+fn write_unsafe(
+    vm: &VirtualMachine,
+    base: HeapRef,
+    offset: i64,
+    value: Value,
+    field_type: AllocationType,
+) -> Result<(), JvmError> {
+    match StaticField::slot_from_unsafe_offset(offset) {
+        Some(slot) => {
+            let ma = vm.method_area_read();
+            let class_id = ma.get_class_id_by_mirror(&base)?;
+            *ma.get_static_field_by_slot(&class_id, slot)?
+                .value
+                .write()
+                .unwrap() = value;
+            Ok(())
+        }
+        None => vm
+            .heap_write()
+            .write_field(base, offset as usize, value, field_type),
+    }
+}
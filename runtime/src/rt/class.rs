@@ -1,6 +1,7 @@
 use crate::error::JvmError;
+use crate::heap::HeapRef;
 use crate::heap::method_area::MethodArea;
-use crate::keys::{ClassId, FieldKey, MethodKey, ThreadId};
+use crate::keys::{ClassId, ClassLoaderId, FieldKey, MethodKey, ThreadId};
 use crate::rt::constant_pool::RuntimeConstantPool;
 use crate::rt::field::{InstanceField, StaticField};
 use crate::rt::method::Method;
@@ -188,6 +189,7 @@ impl InstanceClass {
                     flags: field.access_flags,
                     value: RwLock::new(descriptor.into()),
                     descriptor: descriptor_id,
+                    slot: static_fields.len(),
                 };
                 static_fields.insert(field_key, static_field);
             } else {
@@ -224,6 +226,7 @@ impl InstanceClass {
         this_id: ClassId,
         super_id: Option<ClassId>,
         method_area: &mut MethodArea,
+        loader: Option<ClassLoaderId>,
         thread_id: ThreadId,
     ) -> Result<(), JvmError> {
         let mut interface_ids = super_id
@@ -237,7 +240,8 @@ impl InstanceClass {
         for interface in interfaces {
             let cp = &method_area.get_instance_class(&this_id)?.cp;
             let interface_name = cp.get_class_sym(&interface, method_area.interner())?;
-            let interface_id = method_area.get_class_id_or_load(interface_name, thread_id)?;
+            let interface_id =
+                method_area.get_class_id_or_load_with(loader, interface_name, thread_id)?;
             interface_ids.insert(interface_id);
             direct_interfaces.insert(interface_id);
 
@@ -337,6 +341,7 @@ impl InstanceClass {
         mut cf: ClassFile,
         method_area: &mut MethodArea,
         super_id: Option<ClassId>,
+        loader: Option<ClassLoaderId>,
         thread_id: ThreadId,
     ) -> Result<ClassId, JvmError> {
         let runtime_cp = Self::prepare_cp(cf.cp, &mut cf.attributes);
@@ -352,7 +357,14 @@ impl InstanceClass {
         Self::link_fields(cf.fields, this_id, super_id, method_area)?;
         let (vtable, vtable_index) =
             Self::prepare_methods(cf.methods, this_id, super_id, method_area)?;
-        Self::link_interfaces(cf.interfaces, this_id, super_id, method_area, thread_id)?;
+        Self::link_interfaces(
+            cf.interfaces,
+            this_id,
+            super_id,
+            method_area,
+            loader,
+            thread_id,
+        )?;
         Self::link_itable_and_vtable(this_id, super_id, method_area, vtable, vtable_index)?;
 
         let this = method_area.get_instance_class(&this_id)?;
@@ -408,6 +420,10 @@ impl InstanceClass {
         throw_exception!(NoSuchMethodError, method_key: *key, class_sym: self.name())
     }
 
+    pub fn get_declared_method_id_opt(&self, key: &MethodKey) -> Option<MethodId> {
+        self.get_declared_methods().ok()?.get(key).copied()
+    }
+
     pub fn get_special_method_id_opt(&self, key: &MethodKey) -> Option<MethodId> {
         if let Some(method_id) = self.get_declared_methods().ok()?.get(key) {
             return Some(*method_id);
@@ -533,6 +549,11 @@ impl InstanceClass {
             .set(itable)
             .map_err(|_| JvmError::Todo("Itable already initialized".to_string()))
     }
+
+    pub(crate) fn visit_refs(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        self.base.visit_refs(visitor);
+        self.cp.visit_refs(visitor);
+    }
 }
 
 impl ClassLike for InstanceClass {
@@ -1,7 +1,10 @@
 use crate::error::JvmError;
+use crate::heap::HeapRef;
 use crate::keys::{FieldKey, MethodKey};
-use crate::{Symbol, throw_exception};
+use crate::rt::constant_pool::string_concat::StringConcatRecipe;
+use crate::{MethodId, Symbol, throw_exception};
 use once_cell::sync::OnceCell;
+use std::sync::Arc;
 
 pub(crate) struct Utf8Entry {
     pub value: String,
@@ -65,6 +68,8 @@ pub(crate) struct MethodEntry {
     pub class_idx: u16,
     pub nat_idx: u16,
     pub class_sym: OnceCell<Symbol>,
+    // only used by signature polymorphic methods (MethodHandle.invoke, VarHandle.get...)
+    pub invoker: OnceCell<LinkedCallSite>,
 }
 
 impl MethodEntry {
@@ -73,6 +78,7 @@ impl MethodEntry {
             class_idx,
             nat_idx,
             class_sym: OnceCell::new(),
+            invoker: OnceCell::new(),
         }
     }
 }
@@ -136,6 +142,51 @@ pub enum MethodHandleEntryView {
     InvokeInterface(MethodEntryView),
 }
 
+impl MethodHandleEntryView {
+    /// Reference kind as defined in JVMS 5.4.3.5 (1 - REF_getField, ..., 9 - REF_invokeInterface)
+    pub fn ref_kind(&self) -> i32 {
+        match self {
+            MethodHandleEntryView::GetField(_) => 1,
+            MethodHandleEntryView::GetStatic(_) => 2,
+            MethodHandleEntryView::PutField(_) => 3,
+            MethodHandleEntryView::PutStatic(_) => 4,
+            MethodHandleEntryView::InvokeVirtual(_) => 5,
+            MethodHandleEntryView::InvokeStatic(_) => 6,
+            MethodHandleEntryView::InvokeSpecial(_) => 7,
+            MethodHandleEntryView::NewInvokeSpecial(_) => 8,
+            MethodHandleEntryView::InvokeInterface(_) => 9,
+        }
+    }
+
+    pub fn class_sym(&self) -> Symbol {
+        match self {
+            MethodHandleEntryView::GetField(f)
+            | MethodHandleEntryView::GetStatic(f)
+            | MethodHandleEntryView::PutField(f)
+            | MethodHandleEntryView::PutStatic(f) => f.class_sym,
+            MethodHandleEntryView::InvokeVirtual(m)
+            | MethodHandleEntryView::InvokeStatic(m)
+            | MethodHandleEntryView::InvokeSpecial(m)
+            | MethodHandleEntryView::NewInvokeSpecial(m)
+            | MethodHandleEntryView::InvokeInterface(m) => m.class_sym,
+        }
+    }
+
+    pub fn name_and_type(&self) -> NameAndTypeEntryView {
+        match self {
+            MethodHandleEntryView::GetField(f)
+            | MethodHandleEntryView::GetStatic(f)
+            | MethodHandleEntryView::PutField(f)
+            | MethodHandleEntryView::PutStatic(f) => f.name_and_type,
+            MethodHandleEntryView::InvokeVirtual(m)
+            | MethodHandleEntryView::InvokeStatic(m)
+            | MethodHandleEntryView::InvokeSpecial(m)
+            | MethodHandleEntryView::NewInvokeSpecial(m)
+            | MethodHandleEntryView::InvokeInterface(m) => m.name_and_type,
+        }
+    }
+}
+
 impl FieldEntryView {
     pub fn new(class_sym: Symbol, name_and_type: NameAndTypeEntryView) -> Self {
         Self {
@@ -148,6 +199,9 @@ impl FieldEntryView {
 pub(crate) struct InvokeDynamicEntry {
     pub bootstrap_idx: u16,
     pub nat_idx: u16,
+    pub call_site: OnceCell<LinkedCallSite>,
+    // None if the call site is not a string concatenation that can be done natively
+    pub string_concat_recipe: OnceCell<Option<Arc<StringConcatRecipe>>>,
 }
 
 impl InvokeDynamicEntry {
@@ -155,6 +209,31 @@ impl InvokeDynamicEntry {
         Self {
             bootstrap_idx,
             nat_idx,
+            call_site: OnceCell::new(),
+            string_concat_recipe: OnceCell::new(),
+        }
+    }
+}
+
+/// Result of linking an invokedynamic or a signature polymorphic call site through
+/// `java.lang.invoke.MethodHandleNatives`: the adapter method to call and the optional
+/// appendix (usually the bound `CallSite` target), passed as the trailing argument.
+#[derive(Debug, Copy, Clone)]
+pub struct LinkedCallSite {
+    pub adapter: MethodId,
+    pub appendix: Option<HeapRef>,
+}
+
+pub(crate) struct MethodTypeEntry {
+    pub descriptor_idx: u16,
+    pub descriptor_sym: OnceCell<Symbol>,
+}
+
+impl MethodTypeEntry {
+    pub fn new(descriptor_idx: u16) -> Self {
+        Self {
+            descriptor_idx,
+            descriptor_sym: OnceCell::new(),
         }
     }
 }
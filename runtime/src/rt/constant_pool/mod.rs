@@ -1,16 +1,20 @@
 use crate::error::JvmError;
+use crate::heap::HeapRef;
 use crate::rt::constant_pool::entry::{
     ClassEntry, FieldEntry, FieldEntryView, InvokeDynamicEntry, InvokeDynamicEntryView,
-    MethodEntry, MethodEntryView, MethodHandleEntryView, NameAndTypeEntry, NameAndTypeEntryView,
-    StringEntry, Utf8Entry,
+    LinkedCallSite, MethodEntry, MethodEntryView, MethodHandleEntryView, MethodTypeEntry,
+    NameAndTypeEntry, NameAndTypeEntryView, StringEntry, Utf8Entry,
 };
+use crate::rt::constant_pool::string_concat::StringConcatRecipe;
 use crate::{Symbol, build_exception, throw_exception};
 use lagertha_classfile::attribute::class::BootstrapMethodEntry;
 use lagertha_classfile::constant::ConstantInfo;
 use lasso::ThreadedRodeo;
 use std::fmt::Display;
+use std::sync::Arc;
 
 pub mod entry;
+pub mod string_concat;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum RuntimeConstantType {
@@ -84,7 +88,7 @@ pub enum RuntimeConstant {
     InvokeDynamic(InvokeDynamicEntry),
     InterfaceMethod(MethodEntry),
     NameAndType(NameAndTypeEntry),
-    MethodType,
+    MethodType(MethodTypeEntry),
     MethodHandle(MethodHandleType), // TODO: use our own struct
 }
 
@@ -104,7 +108,7 @@ impl RuntimeConstant {
             RuntimeConstant::InterfaceMethod(_) => RuntimeConstantType::InterfaceMethod,
             RuntimeConstant::NameAndType(_) => RuntimeConstantType::NameAndType,
             RuntimeConstant::InvokeDynamic(_) => RuntimeConstantType::InvokeDynamic,
-            RuntimeConstant::MethodType => RuntimeConstantType::MethodType,
+            RuntimeConstant::MethodType(_) => RuntimeConstantType::MethodType,
             RuntimeConstant::MethodHandle(_) => RuntimeConstantType::MethodHandle,
         }
     }
@@ -148,7 +152,9 @@ impl RuntimeConstantPool {
                         dynamic_info.name_and_type_index,
                     ))
                 }
-                ConstantInfo::MethodType(_) => RuntimeConstant::MethodType,
+                ConstantInfo::MethodType(idx) => {
+                    RuntimeConstant::MethodType(MethodTypeEntry::new(idx))
+                }
                 // TODO: handle could have already mapped MethodHandleKind enum instead of u8
                 ConstantInfo::MethodHandle(handle) => {
                     let method_handle_type = match handle.reference_kind {
@@ -199,6 +205,9 @@ impl RuntimeConstantPool {
             RuntimeConstant::Field(_) => {
                 self.get_field_view(idx, interner)?;
             }
+            RuntimeConstant::MethodType(_) => {
+                self.get_method_type_sym(idx, interner)?;
+            }
             _ => {}
         };
         Ok(entry)
@@ -423,6 +432,92 @@ impl RuntimeConstantPool {
         }
     }
 
+    pub fn get_linked_call_site(&self, idx: &u16) -> Result<Option<LinkedCallSite>, JvmError> {
+        match self.entry(idx)? {
+            RuntimeConstant::InvokeDynamic(entry) => Ok(entry.call_site.get().copied()),
+            RuntimeConstant::Method(entry) => Ok(entry.invoker.get().copied()),
+            other => throw_exception!(
+                IncompatibleClassChangeError,
+                pool_idx: *idx,
+                expected: RuntimeConstantType::InvokeDynamic,
+                actual: other.get_type()
+            ),
+        }
+    }
+
+    /// Caches the linked call site, if another thread was faster the first linked one is returned
+    pub fn set_linked_call_site(
+        &self,
+        idx: &u16,
+        call_site: LinkedCallSite,
+    ) -> Result<LinkedCallSite, JvmError> {
+        let cell = match self.entry(idx)? {
+            RuntimeConstant::InvokeDynamic(entry) => &entry.call_site,
+            RuntimeConstant::Method(entry) => &entry.invoker,
+            other => throw_exception!(
+                IncompatibleClassChangeError,
+                pool_idx: *idx,
+                expected: RuntimeConstantType::InvokeDynamic,
+                actual: other.get_type()
+            )?,
+        };
+        Ok(*cell.get_or_init(|| call_site))
+    }
+
+    /// Appendixes of the linked call sites.
+    pub(crate) fn visit_refs(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        for entry in &mut self.entries {
+            let call_site = match entry {
+                RuntimeConstant::InvokeDynamic(entry) => entry.call_site.get_mut(),
+                RuntimeConstant::Method(entry) | RuntimeConstant::InterfaceMethod(entry) => {
+                    entry.invoker.get_mut()
+                }
+                _ => None,
+            };
+            if let Some(appendix) = call_site.and_then(|call_site| call_site.appendix.as_mut()) {
+                visitor(appendix);
+            }
+        }
+    }
+
+    pub fn get_or_init_string_concat_recipe(
+        &self,
+        idx: &u16,
+        init: impl FnOnce() -> Result<Option<StringConcatRecipe>, JvmError>,
+    ) -> Result<Option<Arc<StringConcatRecipe>>, JvmError> {
+        match self.entry(idx)? {
+            RuntimeConstant::InvokeDynamic(entry) => entry
+                .string_concat_recipe
+                .get_or_try_init(|| Ok(init()?.map(Arc::new)))
+                .cloned(),
+            other => throw_exception!(
+                IncompatibleClassChangeError,
+                pool_idx: *idx,
+                expected: RuntimeConstantType::InvokeDynamic,
+                actual: other.get_type()
+            ),
+        }
+    }
+
+    pub fn get_method_type_sym(
+        &self,
+        idx: &u16,
+        interner: &ThreadedRodeo,
+    ) -> Result<Symbol, JvmError> {
+        match self.entry(idx)? {
+            RuntimeConstant::MethodType(entry) => entry
+                .descriptor_sym
+                .get_or_try_init(|| self.get_utf8_sym(&entry.descriptor_idx, interner))
+                .copied(),
+            other => throw_exception!(
+                IncompatibleClassChangeError,
+                pool_idx: *idx,
+                expected: RuntimeConstantType::MethodType,
+                actual: other.get_type()
+            ),
+        }
+    }
+
     pub fn get_string_sym(&self, idx: &u16, interner: &ThreadedRodeo) -> Result<Symbol, JvmError> {
         match self.entry(idx)? {
             RuntimeConstant::String(entry) => entry
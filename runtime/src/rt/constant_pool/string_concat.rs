@@ -0,0 +1,80 @@
+// Recipe tags, see java.lang.invoke.StringConcatFactory
+const TAG_ARG: char = '\u{1}';
+const TAG_CONST: char = '\u{2}';
+
+/// Parsed recipe of a `StringConcatFactory.makeConcatWithConstants` call site, constants are
+/// already folded into the literals.
+#[derive(Debug)]
+pub struct StringConcatRecipe {
+    pub(crate) parts: Vec<StringConcatPart>,
+    pub(crate) args: Vec<StringConcatArg>,
+}
+
+#[derive(Debug)]
+pub(crate) enum StringConcatPart {
+    // UTF-16 code units, the result is built from them so that lone surrogates survive
+    Literal(Vec<u16>),
+    // index into the call site arguments
+    Argument(usize),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum StringConcatArg {
+    Int,
+    Char,
+    Boolean,
+    Long,
+    Float,
+    Double,
+    Reference,
+}
+
+impl StringConcatArg {
+    pub(crate) fn from_descriptor(desc: &str) -> Self {
+        match desc {
+            "C" => StringConcatArg::Char,
+            "Z" => StringConcatArg::Boolean,
+            "B" | "S" | "I" => StringConcatArg::Int,
+            "J" => StringConcatArg::Long,
+            "F" => StringConcatArg::Float,
+            "D" => StringConcatArg::Double,
+            _ => StringConcatArg::Reference,
+        }
+    }
+}
+
+impl StringConcatRecipe {
+    /// Returns `None` if the recipe doesn't match the call site, the bootstrap method reports
+    /// it then.
+    pub(crate) fn parse(
+        recipe: &str,
+        constants: Vec<String>,
+        args: Vec<StringConcatArg>,
+    ) -> Option<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut constants = constants.into_iter();
+        let mut next_arg = 0;
+        for c in recipe.chars() {
+            match c {
+                TAG_ARG => {
+                    if !literal.is_empty() {
+                        let units = std::mem::take(&mut literal).encode_utf16().collect();
+                        parts.push(StringConcatPart::Literal(units));
+                    }
+                    parts.push(StringConcatPart::Argument(next_arg));
+                    next_arg += 1;
+                }
+                TAG_CONST => literal.push_str(&constants.next()?),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(StringConcatPart::Literal(literal.encode_utf16().collect()));
+        }
+        if next_arg != args.len() || constants.next().is_some() {
+            return None;
+        }
+        Some(Self { parts, args })
+    }
+}
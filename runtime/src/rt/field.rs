@@ -11,9 +11,29 @@ pub struct InstanceField {
     pub declaring_class: ClassId,
 }
 
+// set in the offsets `Unsafe` gets for static fields, so they can't be mistaken for offsets of
+// instance fields of the class mirror they are used with
+const UNSAFE_STATIC_OFFSET_TAG: usize = 1 << 40;
+
 #[derive(Debug)]
 pub struct StaticField {
     pub flags: FieldFlags,
     pub descriptor: FieldDescriptorId,
     pub value: RwLock<Value>,
+    // position among the static fields of the class
+    pub slot: usize,
+}
+
+impl StaticField {
+    /// Offset for `Unsafe` accesses, with the mirror of the declaring class as base.
+    pub fn unsafe_offset(&self) -> i64 {
+        (UNSAFE_STATIC_OFFSET_TAG | self.slot) as i64
+    }
+
+    /// The slot of the static field an `Unsafe` offset stands for, `None` for the offsets of
+    /// heap fields and elements.
+    pub fn slot_from_unsafe_offset(offset: i64) -> Option<usize> {
+        let offset = offset as usize;
+        (offset & UNSAFE_STATIC_OFFSET_TAG != 0).then_some(offset & !UNSAFE_STATIC_OFFSET_TAG)
+    }
 }
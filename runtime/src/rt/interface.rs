@@ -1,7 +1,8 @@
 use crate::MethodId;
 use crate::error::JvmError;
+use crate::heap::HeapRef;
 use crate::heap::method_area::MethodArea;
-use crate::keys::{ClassId, FieldKey, MethodKey, ThreadId};
+use crate::keys::{ClassId, ClassLoaderId, FieldKey, MethodKey, ThreadId};
 use crate::rt::constant_pool::RuntimeConstantPool;
 use crate::rt::field::StaticField;
 use crate::rt::method::Method;
@@ -106,6 +107,7 @@ impl InterfaceClass {
                 flags: field.access_flags,
                 value: RwLock::new(method_area.get_field_descriptor(&descriptor_id).into()),
                 descriptor: descriptor_id,
+                slot: static_fields.len(),
             };
             static_fields.insert(field_key, static_field);
         }
@@ -121,6 +123,7 @@ impl InterfaceClass {
         this_id: ClassId,
         super_id: Option<ClassId>,
         method_area: &mut MethodArea,
+        loader: Option<ClassLoaderId>,
         thread_id: ThreadId,
     ) -> Result<(), JvmError> {
         let mut interface_ids = super_id
@@ -134,7 +137,8 @@ impl InterfaceClass {
         for interface in interfaces {
             let cp = &method_area.get_interface_class(&this_id)?.cp;
             let interface_name = cp.get_class_sym(&interface, method_area.interner())?;
-            let interface_id = method_area.get_class_id_or_load(interface_name, thread_id)?;
+            let interface_id =
+                method_area.get_class_id_or_load_with(loader, interface_name, thread_id)?;
             interface_ids.insert(interface_id);
             direct_interfaces.insert(interface_id);
 
@@ -170,6 +174,7 @@ impl InterfaceClass {
         mut cf: ClassFile,
         method_area: &mut MethodArea,
         super_id: Option<ClassId>,
+        loader: Option<ClassLoaderId>,
         thread_id: ThreadId,
     ) -> Result<ClassId, JvmError> {
         let cp = Self::prepare_cp(cf.cp, &mut cf.attributes);
@@ -177,7 +182,14 @@ impl InterfaceClass {
 
         Self::link_methods(cf.methods, this_id, method_area)?;
         Self::link_fields(cf.fields, this_id, method_area)?;
-        Self::link_interfaces(cf.interfaces, this_id, super_id, method_area, thread_id)?;
+        Self::link_interfaces(
+            cf.interfaces,
+            this_id,
+            super_id,
+            method_area,
+            loader,
+            thread_id,
+        )?;
 
         Ok(this_id)
     }
@@ -186,6 +198,11 @@ impl InterfaceClass {
         self.methods.set(methods).unwrap()
     }
 
+    pub(crate) fn visit_refs(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        self.base.visit_refs(visitor);
+        self.cp.visit_refs(visitor);
+    }
+
     pub fn get_methods(&self) -> &HashMap<MethodKey, MethodId> {
         self.methods.get().unwrap()
     }
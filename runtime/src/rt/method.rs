@@ -1,13 +1,13 @@
 use crate::error::JvmError;
 use crate::keys::{ClassId, MethodDescriptorId};
 use crate::{Symbol, throw_exception};
-use lagertha_common::error::LinkageError;
 use lagertha_classfile::attribute::method::code::{
     CodeAttributeInfo, LineNumberEntry, LocalVariableEntry, LocalVariableTypeEntry, StackMapFrame,
 };
 use lagertha_classfile::attribute::method::{CodeAttribute, ExceptionTableEntry, MethodAttribute};
 use lagertha_classfile::flags::MethodFlags;
 use lagertha_classfile::method::MethodInfo;
+use lagertha_common::error::LinkageError;
 use std::cell::OnceCell;
 
 pub struct CodeBody {
@@ -84,6 +84,18 @@ impl Method {
         self.flags.is_native()
     }
 
+    pub fn is_private(&self) -> bool {
+        self.flags.is_private()
+    }
+
+    pub fn is_synchronized(&self) -> bool {
+        self.flags.is_synchronized()
+    }
+
+    pub fn get_raw_flags(&self) -> i32 {
+        self.flags.get_raw_i32()
+    }
+
     pub fn descriptor_id(&self) -> MethodDescriptorId {
         self.descriptor_id
     }
@@ -8,8 +8,8 @@ use crate::rt::field::{InstanceField, StaticField};
 use crate::rt::interface::InterfaceClass;
 use crate::vm::Value;
 use crate::{MethodId, Symbol};
-use lagertha_common::jtype::PrimitiveType;
 use lagertha_classfile::flags::ClassFlags;
+use lagertha_common::jtype::PrimitiveType;
 use once_cell::sync::OnceCell;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
@@ -84,6 +84,10 @@ pub trait ClassLike {
         Ok(*static_field.value.read().unwrap())
     }
 
+    fn get_static_fields(&self) -> Result<&HashMap<FieldKey, StaticField>, JvmError> {
+        self.base().get_static_fields()
+    }
+
     fn get_interfaces(&self) -> Result<&HashSet<ClassId>, JvmError> {
         self.base().get_interfaces()
     }
@@ -201,6 +205,18 @@ impl BaseClass {
             "BaseClass static_fields not set".to_string(),
         ))
     }
+
+    /// Class mirror and static reference fields.
+    pub(crate) fn visit_refs(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        if let Some(mirror_ref) = self.mirror_ref.get_mut() {
+            visitor(mirror_ref);
+        }
+        if let Some(static_fields) = self.static_fields.get_mut() {
+            for static_field in static_fields.values_mut() {
+                static_field.value.get_mut().unwrap().visit_ref(visitor);
+            }
+        }
+    }
 }
 
 // TODO: something like that...
@@ -383,6 +399,20 @@ impl JvmClass {
         }
     }
 
+    /// References the class keeps alive: its mirror, static fields and linked call sites.
+    pub fn visit_refs(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        let mirror_ref = match self {
+            JvmClass::Instance(ic) => return ic.visit_refs(visitor),
+            JvmClass::Interface(i) => return i.visit_refs(visitor),
+            JvmClass::PrimitiveArray(pac) => pac.mirror_ref.get_mut(),
+            JvmClass::InstanceArray(oac) => oac.mirror_ref.get_mut(),
+            JvmClass::Primitive(pc) => pc.mirror_ref.get_mut(),
+        };
+        if let Some(mirror_ref) = mirror_ref {
+            visitor(mirror_ref);
+        }
+    }
+
     pub fn get_super_id(&self) -> Option<ClassId> {
         match self {
             JvmClass::Instance(i) => i.get_super(),
@@ -419,7 +449,8 @@ impl JvmClass {
 
 pub struct PrimitiveClass {
     pub name: Symbol,
-    pub primitive_type: PrimitiveType,
+    // None for `void`, it has a class mirror (Void.TYPE) but it is not a value type
+    pub primitive_type: Option<PrimitiveType>,
     pub(crate) mirror_ref: OnceCell<HeapRef>,
 }
 
@@ -427,7 +458,15 @@ impl PrimitiveClass {
     pub fn new(name: Symbol, primitive_type: PrimitiveType) -> Self {
         Self {
             name,
-            primitive_type,
+            primitive_type: Some(primitive_type),
+            mirror_ref: OnceCell::new(),
+        }
+    }
+
+    pub fn new_void(name: Symbol) -> Self {
+        Self {
+            name,
+            primitive_type: None,
             mirror_ref: OnceCell::new(),
         }
     }
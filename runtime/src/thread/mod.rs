@@ -1,6 +1,15 @@
 use crate::heap::HeapRef;
 use crate::keys::ThreadId;
+use crate::vm::Value;
 use crate::vm::stack::FrameStack;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// JVMTI thread states, java.lang.Thread keeps them in FieldHolder.threadStatus
+pub const THREAD_STATUS_RUNNABLE: i32 = 0x0001 | 0x0004; // alive and runnable
+pub const THREAD_STATUS_TERMINATED: i32 = 0x0002;
 
 pub struct JavaThreadState {
     pub id: ThreadId,
@@ -8,4 +17,165 @@ pub struct JavaThreadState {
     pub group_obj: HeapRef, // TODO: Once cell?
     pub name: HeapRef,
     pub stack: FrameStack,
+    // references the VM holds across calls that may collect garbage (upcalls into java,
+    // blocking), they are roots like the ones in the frames
+    pub handles: Vec<Value>,
+    pub identity_hashes: IdentityHashGenerator,
+}
+
+impl JavaThreadState {
+    /// Roots the values until `pop_handles` is called with the returned mark.
+    pub fn push_handles(&mut self, values: Vec<Value>) -> usize {
+        let mark = self.handles.len();
+        self.handles.extend(values);
+        mark
+    }
+
+    pub fn pop_handles(&mut self, mark: usize) -> Vec<Value> {
+        self.handles.split_off(mark)
+    }
+
+    /// Runs `f`, which may collect garbage, with the values rooted. Returns its result and the
+    /// current values.
+    pub fn with_handles<T>(
+        &mut self,
+        values: Vec<Value>,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> (T, Vec<Value>) {
+        let mark = self.push_handles(values);
+        let res = f(self);
+        (res, self.pop_handles(mark))
+    }
+
+    pub fn visit_refs(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        visitor(&mut self.thread_obj);
+        visitor(&mut self.name);
+        if self.group_obj != 0 {
+            visitor(&mut self.group_obj);
+        }
+        self.stack.visit_refs(visitor);
+        for handle in &mut self.handles {
+            handle.visit_ref(visitor);
+        }
+    }
+}
+
+/// Marsaglia's xor-shift generator of identity hashes, like hotspot's default (hashCode=5).
+/// Every thread has its own, so hashing doesn't contend.
+pub struct IdentityHashGenerator {
+    x: u32,
+    y: u32,
+    z: u32,
+    w: u32,
+}
+
+impl IdentityHashGenerator {
+    pub fn new(thread_id: ThreadId) -> Self {
+        Self {
+            // hotspot seeds x with os::random, a mix of the thread id is enough to tell the
+            // threads apart
+            x: (thread_id.as_usize() as u32).wrapping_mul(0x9e37_79b9) ^ 0x2545_f491,
+            y: 842502087,
+            z: 0x8767,
+            w: 273326509,
+        }
+    }
+
+    /// A 31 bit hash like in hotspot, never 0 which stands for no hash yet.
+    pub fn next_hash(&mut self) -> u32 {
+        let mut t = self.x;
+        t ^= t << 11;
+        self.x = self.y;
+        self.y = self.z;
+        self.z = self.w;
+        let v = (self.w ^ (self.w >> 19)) ^ (t ^ (t >> 8));
+        self.w = v;
+        match v & 0x7fff_ffff {
+            0 => 0xbad,
+            hash => hash,
+        }
+    }
+}
+
+struct RegisteredThread {
+    daemon: bool,
+    parker: Arc<Parker>,
+}
+
+/// `LockSupport.park`/`unpark` permit of a thread, unparking a thread that isn't parked makes
+/// its next park return right away.
+#[derive(Default)]
+pub struct Parker {
+    permit: Mutex<bool>,
+    cv: Condvar,
+}
+
+impl Parker {
+    /// Consumes the permit, waits for it first if there is none. Like in java, the wait may
+    /// end early for no reason.
+    pub fn park(&self, timeout: Option<Duration>) {
+        let mut permit = self.permit.lock().unwrap();
+        if !*permit {
+            permit = match timeout {
+                Some(timeout) => self.cv.wait_timeout(permit, timeout).unwrap().0,
+                None => self.cv.wait(permit).unwrap(),
+            };
+        }
+        *permit = false;
+    }
+
+    pub fn unpark(&self) {
+        *self.permit.lock().unwrap() = true;
+        self.cv.notify_one();
+    }
+}
+
+/// Java threads that are currently alive, the main thread included.
+#[derive(Default)]
+pub struct ThreadRegistry {
+    threads: Mutex<HashMap<ThreadId, RegisteredThread>>,
+    // signaled every time a thread terminates
+    terminated_cv: Condvar,
+    next_index: AtomicUsize,
+}
+
+impl ThreadRegistry {
+    pub fn next_id(&self) -> ThreadId {
+        ThreadId::from_index(self.next_index.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub fn register(&self, thread_id: ThreadId, daemon: bool) {
+        self.threads.lock().unwrap().insert(
+            thread_id,
+            RegisteredThread {
+                daemon,
+                parker: Arc::default(),
+            },
+        );
+    }
+
+    /// The parker of the thread, none once it terminated.
+    pub fn parker(&self, thread_id: ThreadId) -> Option<Arc<Parker>> {
+        self.threads
+            .lock()
+            .unwrap()
+            .get(&thread_id)
+            .map(|thread| thread.parker.clone())
+    }
+
+    pub fn unregister(&self, thread_id: ThreadId) {
+        self.threads.lock().unwrap().remove(&thread_id);
+        self.terminated_cv.notify_all();
+    }
+
+    /// Blocks until the given thread is the last non-daemon thread alive.
+    pub fn wait_for_non_daemon_threads(&self, thread_id: ThreadId) {
+        let mut threads = self.threads.lock().unwrap();
+        while threads
+            .iter()
+            .any(|(id, thread)| *id != thread_id && !thread.daemon)
+        {
+            threads = self.terminated_cv.wait(threads).unwrap();
+        }
+    }
 }
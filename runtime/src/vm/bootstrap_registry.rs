@@ -9,6 +9,7 @@ pub struct BootstrapRegistry {
     pub clinit_mk: MethodKey,
     pub no_arg_constructor_mk: MethodKey,
     pub main_mk: MethodKey,
+    pub main_no_args_mk: MethodKey,
     pub system_init_phase1_mk: MethodKey,
     pub system_init_phase2_mk: MethodKey,
     pub system_init_phase3_mk: MethodKey,
@@ -17,6 +18,19 @@ pub struct BootstrapRegistry {
     pub thread_thread_group_and_name_constructor_mk: MethodKey,
     pub thread_group_uncaught_exception_mk: MethodKey,
     pub thread_get_thread_group_mk: MethodKey,
+    pub thread_run_mk: MethodKey,
+    pub thread_exit_mk: MethodKey,
+    pub class_loader_load_class_mk: MethodKey,
+    pub class_loader_get_platform_class_loader_mk: MethodKey,
+    pub class_loader_get_system_class_loader_mk: MethodKey,
+    pub shutdown_shutdown_mk: MethodKey,
+    pub mhn_link_call_site_mk: MethodKey,
+    pub mhn_link_method_mk: MethodKey,
+    pub mhn_link_method_handle_constant_mk: MethodKey,
+    pub mhn_find_method_handle_type_mk: MethodKey,
+    pub string_value_of_object_mk: MethodKey,
+    pub string_value_of_float_mk: MethodKey,
+    pub string_value_of_double_mk: MethodKey,
 
     // Common field keys
     pub class_name_fk: FieldKey,
@@ -33,7 +47,26 @@ pub struct BootstrapRegistry {
     pub stack_trace_line_number_fk: FieldKey,
     pub stack_trace_declaring_class_name_fk: FieldKey,
     pub reference_referent_fk: FieldKey,
+    pub reference_discovered_fk: FieldKey,
     pub file_path_fk: FieldKey,
+    pub class_class_data_fk: FieldKey,
+    pub class_class_loader_fk: FieldKey,
+    pub member_name_clazz_fk: FieldKey,
+    pub member_name_name_fk: FieldKey,
+    pub member_name_type_fk: FieldKey,
+    pub member_name_flags_fk: FieldKey,
+    pub member_name_method_fk: FieldKey,
+    pub method_handle_form_fk: FieldKey,
+    pub lambda_form_vmentry_fk: FieldKey,
+    pub method_type_rtype_fk: FieldKey,
+    pub method_type_ptypes_fk: FieldKey,
+    pub call_site_target_fk: FieldKey,
+    pub thread_eetop_fk: FieldKey,
+    pub thread_interrupted_fk: FieldKey,
+    pub thread_name_fk: FieldKey,
+    pub thread_holder_fk: FieldKey,
+    pub thread_holder_thread_status_fk: FieldKey,
+    pub thread_holder_daemon_fk: FieldKey,
 
     // Common class names (interned)
     pub java_lang_object_sym: Symbol,
@@ -41,10 +74,23 @@ pub struct BootstrapRegistry {
     pub java_lang_throwable_sym: Symbol,
     pub java_lang_string_sym: Symbol,
     pub java_lang_system_sym: Symbol,
+    pub java_lang_class_loader_sym: Symbol,
     pub java_lang_thread_sym: Symbol,
     pub java_lang_thread_group_sym: Symbol,
+    pub java_lang_shutdown_sym: Symbol,
     pub java_lang_ref_reference_sym: Symbol,
+    pub java_lang_ref_soft_reference_sym: Symbol,
+    pub java_lang_ref_weak_reference_sym: Symbol,
+    pub java_lang_ref_phantom_reference_sym: Symbol,
     pub java_io_file_sym: Symbol,
+    pub java_lang_cloneable_sym: Symbol,
+    pub java_io_serializable_sym: Symbol,
+    pub java_lang_invoke_method_handle_natives_sym: Symbol,
+    pub java_lang_invoke_method_handle_sym: Symbol,
+    pub java_lang_invoke_var_handle_sym: Symbol,
+    pub java_lang_invoke_member_name_sym: Symbol,
+    pub java_lang_invoke_resolved_method_name_sym: Symbol,
+    pub java_lang_invoke_string_concat_factory_sym: Symbol,
 
     // Primitive name symbols
     pub int_sym: Symbol,
@@ -63,6 +109,7 @@ pub struct BootstrapRegistry {
     pub main_sym: Symbol,
     pub arraycopy_sym: Symbol,
     pub clone_sym: Symbol,
+    pub make_concat_with_constants_sym: Symbol,
 
     // Common descriptors (interned)
     pub void_desc: Symbol,         // ()V
@@ -104,6 +151,8 @@ impl BootstrapRegistry {
         let int_desc = interner.get_or_intern("I");
         let boolean_desc = interner.get_or_intern("Z");
         let desc_print_stream_sym = interner.get_or_intern("Ljava/io/PrintStream;");
+        let member_name_desc = interner.get_or_intern("Ljava/lang/invoke/MemberName;");
+        let class_array_desc = interner.get_or_intern("[Ljava/lang/Class;");
 
         // Primitive type names
         let int_sym = interner.get_or_intern("int");
@@ -133,6 +182,10 @@ impl BootstrapRegistry {
                 name: main_sym,
                 desc: interner.get_or_intern("([Ljava/lang/String;)V"),
             },
+            main_no_args_mk: MethodKey {
+                name: main_sym,
+                desc: void_desc,
+            },
             system_init_phase1_mk: MethodKey {
                 name: interner.get_or_intern("initPhase1"),
                 desc: void_desc,
@@ -165,6 +218,66 @@ impl BootstrapRegistry {
                 name: interner.get_or_intern("getThreadGroup"),
                 desc: interner.get_or_intern("()Ljava/lang/ThreadGroup;"),
             },
+            thread_run_mk: MethodKey {
+                name: interner.get_or_intern("run"),
+                desc: void_desc,
+            },
+            thread_exit_mk: MethodKey {
+                name: interner.get_or_intern("exit"),
+                desc: void_desc,
+            },
+            class_loader_load_class_mk: MethodKey {
+                name: interner.get_or_intern("loadClass"),
+                desc: interner.get_or_intern("(Ljava/lang/String;)Ljava/lang/Class;"),
+            },
+            class_loader_get_platform_class_loader_mk: MethodKey {
+                name: interner.get_or_intern("getPlatformClassLoader"),
+                desc: interner.get_or_intern("()Ljava/lang/ClassLoader;"),
+            },
+            class_loader_get_system_class_loader_mk: MethodKey {
+                name: interner.get_or_intern("getSystemClassLoader"),
+                desc: interner.get_or_intern("()Ljava/lang/ClassLoader;"),
+            },
+            shutdown_shutdown_mk: MethodKey {
+                name: interner.get_or_intern("shutdown"),
+                desc: void_desc,
+            },
+            mhn_link_call_site_mk: MethodKey {
+                name: interner.get_or_intern("linkCallSite"),
+                desc: interner.get_or_intern(
+                    "(Ljava/lang/Object;Ljava/lang/Object;Ljava/lang/Object;Ljava/lang/Object;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/invoke/MemberName;",
+                ),
+            },
+            mhn_link_method_mk: MethodKey {
+                name: interner.get_or_intern("linkMethod"),
+                desc: interner.get_or_intern(
+                    "(Ljava/lang/Class;ILjava/lang/Class;Ljava/lang/String;Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/invoke/MemberName;",
+                ),
+            },
+            mhn_link_method_handle_constant_mk: MethodKey {
+                name: interner.get_or_intern("linkMethodHandleConstant"),
+                desc: interner.get_or_intern(
+                    "(Ljava/lang/Class;ILjava/lang/Class;Ljava/lang/String;Ljava/lang/Object;)Ljava/lang/invoke/MethodHandle;",
+                ),
+            },
+            mhn_find_method_handle_type_mk: MethodKey {
+                name: interner.get_or_intern("findMethodHandleType"),
+                desc: interner.get_or_intern(
+                    "(Ljava/lang/Class;[Ljava/lang/Class;)Ljava/lang/invoke/MethodType;",
+                ),
+            },
+            string_value_of_object_mk: MethodKey {
+                name: interner.get_or_intern("valueOf"),
+                desc: interner.get_or_intern("(Ljava/lang/Object;)Ljava/lang/String;"),
+            },
+            string_value_of_float_mk: MethodKey {
+                name: interner.get_or_intern("valueOf"),
+                desc: interner.get_or_intern("(F)Ljava/lang/String;"),
+            },
+            string_value_of_double_mk: MethodKey {
+                name: interner.get_or_intern("valueOf"),
+                desc: interner.get_or_intern("(D)Ljava/lang/String;"),
+            },
 
             // Field keys
             class_name_fk: FieldKey {
@@ -183,6 +296,10 @@ impl BootstrapRegistry {
                 name: interner.get_or_intern("referent"),
                 desc: object_desc,
             },
+            reference_discovered_fk: FieldKey {
+                name: interner.get_or_intern("discovered"),
+                desc: interner.get_or_intern("Ljava/lang/ref/Reference;"),
+            },
             throwable_depth_fk: FieldKey {
                 name: interner.get_or_intern("depth"),
                 desc: int_desc,
@@ -227,6 +344,78 @@ impl BootstrapRegistry {
                 name: interner.get_or_intern("path"),
                 desc: string_desc,
             },
+            class_class_data_fk: FieldKey {
+                name: interner.get_or_intern("classData"),
+                desc: object_desc,
+            },
+            class_class_loader_fk: FieldKey {
+                name: interner.get_or_intern("classLoader"),
+                desc: interner.get_or_intern("Ljava/lang/ClassLoader;"),
+            },
+            member_name_clazz_fk: FieldKey {
+                name: interner.get_or_intern("clazz"),
+                desc: class_desc,
+            },
+            member_name_name_fk: FieldKey {
+                name: name_field,
+                desc: string_desc,
+            },
+            member_name_type_fk: FieldKey {
+                name: interner.get_or_intern("type"),
+                desc: object_desc,
+            },
+            member_name_flags_fk: FieldKey {
+                name: interner.get_or_intern("flags"),
+                desc: int_desc,
+            },
+            member_name_method_fk: FieldKey {
+                name: interner.get_or_intern("method"),
+                desc: interner.get_or_intern("Ljava/lang/invoke/ResolvedMethodName;"),
+            },
+            method_handle_form_fk: FieldKey {
+                name: interner.get_or_intern("form"),
+                desc: interner.get_or_intern("Ljava/lang/invoke/LambdaForm;"),
+            },
+            lambda_form_vmentry_fk: FieldKey {
+                name: interner.get_or_intern("vmentry"),
+                desc: member_name_desc,
+            },
+            method_type_rtype_fk: FieldKey {
+                name: interner.get_or_intern("rtype"),
+                desc: class_desc,
+            },
+            method_type_ptypes_fk: FieldKey {
+                name: interner.get_or_intern("ptypes"),
+                desc: class_array_desc,
+            },
+            call_site_target_fk: FieldKey {
+                name: interner.get_or_intern("target"),
+                desc: interner.get_or_intern("Ljava/lang/invoke/MethodHandle;"),
+            },
+            thread_eetop_fk: FieldKey {
+                name: interner.get_or_intern("eetop"),
+                desc: interner.get_or_intern("J"),
+            },
+            thread_interrupted_fk: FieldKey {
+                name: interner.get_or_intern("interrupted"),
+                desc: boolean_desc,
+            },
+            thread_name_fk: FieldKey {
+                name: name_field,
+                desc: string_desc,
+            },
+            thread_holder_fk: FieldKey {
+                name: interner.get_or_intern("holder"),
+                desc: interner.get_or_intern("Ljava/lang/Thread$FieldHolder;"),
+            },
+            thread_holder_thread_status_fk: FieldKey {
+                name: interner.get_or_intern("threadStatus"),
+                desc: int_desc,
+            },
+            thread_holder_daemon_fk: FieldKey {
+                name: interner.get_or_intern("daemon"),
+                desc: boolean_desc,
+            },
 
             // Class names
             java_lang_object_sym: interner.get_or_intern("java/lang/Object"),
@@ -234,10 +423,28 @@ impl BootstrapRegistry {
             java_lang_throwable_sym: interner.get_or_intern("java/lang/Throwable"),
             java_lang_string_sym: interner.get_or_intern("java/lang/String"),
             java_lang_system_sym: interner.get_or_intern("java/lang/System"),
+            java_lang_class_loader_sym: interner.get_or_intern("java/lang/ClassLoader"),
             java_lang_thread_sym: interner.get_or_intern("java/lang/Thread"),
             java_lang_thread_group_sym: interner.get_or_intern("java/lang/ThreadGroup"),
+            java_lang_shutdown_sym: interner.get_or_intern("java/lang/Shutdown"),
             java_lang_ref_reference_sym: interner.get_or_intern("java/lang/ref/Reference"),
+            java_lang_ref_soft_reference_sym: interner.get_or_intern("java/lang/ref/SoftReference"),
+            java_lang_ref_weak_reference_sym: interner.get_or_intern("java/lang/ref/WeakReference"),
+            java_lang_ref_phantom_reference_sym: interner
+                .get_or_intern("java/lang/ref/PhantomReference"),
             java_io_file_sym: interner.get_or_intern("java/io/File"),
+            java_lang_cloneable_sym: interner.get_or_intern("java/lang/Cloneable"),
+            java_io_serializable_sym: interner.get_or_intern("java/io/Serializable"),
+            java_lang_invoke_method_handle_natives_sym: interner
+                .get_or_intern("java/lang/invoke/MethodHandleNatives"),
+            java_lang_invoke_method_handle_sym: interner
+                .get_or_intern("java/lang/invoke/MethodHandle"),
+            java_lang_invoke_var_handle_sym: interner.get_or_intern("java/lang/invoke/VarHandle"),
+            java_lang_invoke_member_name_sym: interner.get_or_intern("java/lang/invoke/MemberName"),
+            java_lang_invoke_resolved_method_name_sym: interner
+                .get_or_intern("java/lang/invoke/ResolvedMethodName"),
+            java_lang_invoke_string_concat_factory_sym: interner
+                .get_or_intern("java/lang/invoke/StringConcatFactory"),
 
             // Method names
             init_sym,
@@ -245,6 +452,7 @@ impl BootstrapRegistry {
             main_sym,
             arraycopy_sym: interner.get_or_intern("arraycopy"),
             clone_sym: interner.get_or_intern("clone"),
+            make_concat_with_constants_sym: interner.get_or_intern("makeConcatWithConstants"),
 
             // Descriptors
             void_desc,
@@ -4,6 +4,9 @@ use crate::throw_exception;
 use lagertha_common::jtype::{JavaType, PrimitiveType};
 
 pub mod bootstrap_registry;
+pub mod monitor;
+pub mod safepoint;
+pub mod signal;
 pub mod stack;
 pub mod throw;
 
@@ -19,6 +22,13 @@ pub enum Value {
 }
 
 impl Value {
+    /// Lets the collector see (and update) the reference, if the value is one.
+    pub fn visit_ref(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        if let Value::Ref(heap_ref) = self {
+            visitor(heap_ref);
+        }
+    }
+
     pub fn as_nullable_obj_ref(&self) -> Result<Option<HeapRef>, JvmError> {
         match self {
             Value::Ref(addr) => Ok(Some(*addr)),
@@ -0,0 +1,240 @@
+use crate::error::JvmError;
+use crate::heap::{Heap, HeapRef};
+use crate::keys::ThreadId;
+use crate::throw_exception;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
+
+/// Java object monitor (JVMS 2.11.10), reentrant and owned by at most one thread.
+/// Monitors are inflated lazily, the first time an object is locked, and the object header
+/// keeps the index of its monitor in the [`MonitorTable`].
+pub struct Monitor {
+    state: Mutex<MonitorState>,
+    entry_cv: Condvar,
+    wait_cv: Condvar,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    owner: Option<ThreadId>,
+    recursions: u32,
+    // threads in Object.wait that weren't notified yet, in arrival order
+    wait_set: VecDeque<ThreadId>,
+    // threads interrupted while waiting (or about to wait) on this monitor
+    interrupted: HashSet<ThreadId>,
+}
+
+impl Monitor {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(MonitorState::default()),
+            entry_cv: Condvar::new(),
+            wait_cv: Condvar::new(),
+        }
+    }
+
+    /// Blocks until the monitor is free or already owned by the thread.
+    pub fn enter(&self, thread_id: ThreadId) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            match state.owner {
+                None => {
+                    state.owner = Some(thread_id);
+                    state.recursions = 1;
+                    return;
+                }
+                Some(owner) if owner == thread_id => {
+                    state.recursions += 1;
+                    return;
+                }
+                Some(_) => state = self.entry_cv.wait(state).unwrap(),
+            }
+        }
+    }
+
+    pub fn exit(&self, thread_id: ThreadId) -> Result<(), JvmError> {
+        let mut state = self.owned_state(thread_id)?;
+        state.recursions -= 1;
+        if state.recursions == 0 {
+            state.owner = None;
+            self.entry_cv.notify_one();
+        }
+        Ok(())
+    }
+
+    /// Releases the monitor completely and waits to be notified, interrupted or for the timeout
+    /// (`None` waits forever), then reacquires it with the same recursion count.
+    pub fn wait(&self, thread_id: ThreadId, timeout: Option<Duration>) -> Result<(), JvmError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut state = self.owned_state(thread_id)?;
+        let recursions = state.recursions;
+        state.owner = None;
+        state.recursions = 0;
+        state.wait_set.push_back(thread_id);
+        self.entry_cv.notify_one();
+
+        while !state.interrupted.contains(&thread_id) && state.wait_set.contains(&thread_id) {
+            match deadline {
+                None => state = self.wait_cv.wait(state).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+                    state = self.wait_cv.wait_timeout(state, deadline - now).unwrap().0;
+                }
+            }
+        }
+        state.wait_set.retain(|id| *id != thread_id);
+        state.interrupted.remove(&thread_id);
+
+        while state.owner.is_some() {
+            state = self.entry_cv.wait(state).unwrap();
+        }
+        state.owner = Some(thread_id);
+        state.recursions = recursions;
+        Ok(())
+    }
+
+    pub fn notify(&self, thread_id: ThreadId) -> Result<(), JvmError> {
+        let mut state = self.owned_state(thread_id)?;
+        if state.wait_set.pop_front().is_some() {
+            // all waiters share the condvar, the notified one finds itself out of the wait set
+            self.wait_cv.notify_all();
+        }
+        Ok(())
+    }
+
+    pub fn notify_all(&self, thread_id: ThreadId) -> Result<(), JvmError> {
+        let mut state = self.owned_state(thread_id)?;
+        state.wait_set.clear();
+        self.wait_cv.notify_all();
+        Ok(())
+    }
+
+    /// Wakes the thread up if it waits on this monitor, the wakeup isn't lost if the thread
+    /// is only about to wait.
+    pub fn interrupt(&self, thread_id: ThreadId) {
+        let mut state = self.state.lock().unwrap();
+        state.interrupted.insert(thread_id);
+        self.wait_cv.notify_all();
+    }
+
+    fn clear_interrupt(&self, thread_id: ThreadId) {
+        self.state.lock().unwrap().interrupted.remove(&thread_id);
+    }
+
+    /// Neither owned nor waited on, nothing would notice if it went away.
+    fn is_idle(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.owner.is_none() && state.wait_set.is_empty()
+    }
+
+    fn is_owned_by(&self, thread_id: ThreadId) -> bool {
+        self.state.lock().unwrap().owner == Some(thread_id)
+    }
+
+    pub fn check_owner(&self, thread_id: ThreadId) -> Result<(), JvmError> {
+        self.owned_state(thread_id).map(|_| ())
+    }
+
+    fn owned_state(&self, thread_id: ThreadId) -> Result<MutexGuard<'_, MonitorState>, JvmError> {
+        let state = self.state.lock().unwrap();
+        if state.owner != Some(thread_id) {
+            throw_exception!(IllegalMonitorStateException, "current thread is not owner")?
+        }
+        Ok(state)
+    }
+}
+
+#[derive(Default)]
+pub struct MonitorTable {
+    monitors: Mutex<Monitors>,
+    // monitor each thread is waiting on, so it can be woken up by Thread.interrupt
+    waiting: Mutex<HashMap<ThreadId, Arc<Monitor>>>,
+}
+
+#[derive(Default)]
+struct Monitors {
+    // indexed by monitor id - 1, `None` once the monitor is freed
+    slots: Vec<Option<Arc<Monitor>>>,
+    // ids of the freed slots, reused by the next inflations
+    free_ids: Vec<u32>,
+    // monitors of dead objects that were still in use when the collector found them
+    retired_ids: Vec<u32>,
+}
+
+impl MonitorTable {
+    /// Returns the monitor of the object, inflating it on first use.
+    pub fn get_or_inflate(&self, heap: &RwLock<Heap>, obj_ref: HeapRef) -> Arc<Monitor> {
+        // the table lock is held while inflating, so two threads can't inflate the same object
+        let mut monitors = self.monitors.lock().unwrap();
+        if let Some(monitor_id) = heap.read().unwrap().get_monitor_id(obj_ref) {
+            // only the monitors of dead objects are freed
+            return monitors.slots[monitor_id as usize - 1].clone().unwrap();
+        }
+        let monitor = Arc::new(Monitor::new());
+        let monitor_id = match monitors.free_ids.pop() {
+            Some(monitor_id) => {
+                monitors.slots[monitor_id as usize - 1] = Some(monitor.clone());
+                monitor_id
+            }
+            None => {
+                monitors.slots.push(Some(monitor.clone()));
+                monitors.slots.len() as u32
+            }
+        };
+        heap.write().unwrap().set_monitor_id(obj_ref, monitor_id);
+        monitor
+    }
+
+    /// Whether the thread owns the monitor of the object, an object without a monitor was never
+    /// locked so nobody owns it.
+    pub fn is_owned_by(&self, heap: &RwLock<Heap>, obj_ref: HeapRef, thread_id: ThreadId) -> bool {
+        let monitors = self.monitors.lock().unwrap();
+        heap.read()
+            .unwrap()
+            .get_monitor_id(obj_ref)
+            .and_then(|monitor_id| monitors.slots[monitor_id as usize - 1].as_ref())
+            .is_some_and(|monitor| monitor.is_owned_by(thread_id))
+    }
+
+    /// Frees the monitors of the objects a collection found dead (`Heap::take_dead_monitors`),
+    /// so their ids are reused. A monitor still owned or waited on is retried after the next
+    /// collection.
+    pub fn free_dead(&self, dead_ids: Vec<u32>) {
+        let mut monitors = self.monitors.lock().unwrap();
+        let retired_ids = std::mem::take(&mut monitors.retired_ids);
+        for monitor_id in retired_ids.into_iter().chain(dead_ids) {
+            let index = monitor_id as usize - 1;
+            let is_idle = monitors.slots[index]
+                .as_ref()
+                .is_some_and(|monitor| monitor.is_idle());
+            if is_idle {
+                monitors.slots[index] = None;
+                monitors.free_ids.push(monitor_id);
+            } else {
+                monitors.retired_ids.push(monitor_id);
+            }
+        }
+    }
+
+    pub fn set_waiting(&self, thread_id: ThreadId, monitor: Arc<Monitor>) {
+        self.waiting.lock().unwrap().insert(thread_id, monitor);
+    }
+
+    pub fn clear_waiting(&self, thread_id: ThreadId) {
+        // the map stays locked, so a racing interrupt can't flag the monitor once it's cleared
+        let mut waiting = self.waiting.lock().unwrap();
+        if let Some(monitor) = waiting.remove(&thread_id) {
+            monitor.clear_interrupt(thread_id);
+        }
+    }
+
+    pub fn interrupt_waiting(&self, thread_id: ThreadId) {
+        if let Some(monitor) = self.waiting.lock().unwrap().get(&thread_id) {
+            monitor.interrupt(thread_id);
+        }
+    }
+}
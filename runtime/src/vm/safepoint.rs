@@ -0,0 +1,105 @@
+use crate::keys::ThreadId;
+use crate::thread::JavaThreadState;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+
+/// Brings all java threads to a stop, so the collector sees every reference they hold.
+/// An attached thread is either running, then it stops at its next poll, or it is in a safe
+/// region (blocked on a monitor, waiting for a class initialization...) where it doesn't touch
+/// the heap. Stopped threads and threads in safe regions publish their state for the collector.
+#[derive(Default)]
+pub struct Safepoint {
+    requested: AtomicBool,
+    state: Mutex<SafepointState>,
+    // signaled when a thread stops, detaches or the world is resumed
+    cv: Condvar,
+}
+
+#[derive(Default)]
+struct SafepointState {
+    // attached threads that are neither stopped nor in a safe region
+    running: usize,
+    stopped: HashMap<ThreadId, StoppedThread>,
+}
+
+// The owning thread doesn't touch its state until it leaves the safe region, and it can't leave
+// while the world is stopped, so the collector is the only one using the pointer then.
+struct StoppedThread(*mut JavaThreadState);
+
+unsafe impl Send for StoppedThread {}
+
+impl Safepoint {
+    /// Counts the thread in, it must poll from now on.
+    pub fn attach(&self) {
+        self.state.lock().unwrap().running += 1;
+    }
+
+    pub fn detach(&self) {
+        self.state.lock().unwrap().running -= 1;
+        self.cv.notify_all();
+    }
+
+    /// Stops the thread while another one has the world stopped.
+    pub fn poll(&self, thread: &mut JavaThreadState) {
+        if self.requested.load(Ordering::Acquire) {
+            self.enter_safe_region(thread);
+            self.leave_safe_region(thread.id);
+        }
+    }
+
+    /// The thread won't touch the heap or its state until `leave_safe_region`, a stop of the
+    /// world doesn't wait for it meanwhile.
+    pub fn enter_safe_region(&self, thread: &mut JavaThreadState) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .stopped
+            .insert(thread.id, StoppedThread(thread as *mut JavaThreadState));
+        state.running -= 1;
+        self.cv.notify_all();
+    }
+
+    /// Blocks while the world is stopped.
+    pub fn leave_safe_region(&self, thread_id: ThreadId) {
+        let mut state = self.state.lock().unwrap();
+        while self.requested.load(Ordering::Acquire) {
+            state = self.cv.wait(state).unwrap();
+        }
+        state.stopped.remove(&thread_id);
+        state.running += 1;
+    }
+
+    /// Runs `f` once every attached thread, the given one included, is stopped or in a safe
+    /// region. If another thread stops the world first, this one just stops for it and `f`
+    /// isn't run.
+    pub fn stop_the_world(
+        &self,
+        thread: &mut JavaThreadState,
+        f: impl FnOnce(&mut [&mut JavaThreadState]),
+    ) {
+        let mut state = self.state.lock().unwrap();
+        if self.requested.load(Ordering::Acquire) {
+            drop(state);
+            self.poll(thread);
+            return;
+        }
+        self.requested.store(true, Ordering::Release);
+        state.running -= 1;
+        while state.running > 0 {
+            state = self.cv.wait(state).unwrap();
+        }
+
+        let mut threads = state
+            .stopped
+            .values()
+            // SAFETY: the threads are stopped, see StoppedThread
+            .map(|stopped| unsafe { &mut *stopped.0 })
+            .collect::<Vec<_>>();
+        threads.push(thread);
+        f(&mut threads);
+
+        state.running += 1;
+        self.requested.store(false, Ordering::Release);
+        self.cv.notify_all();
+    }
+}
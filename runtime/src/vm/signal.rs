@@ -0,0 +1,29 @@
+use crate::VirtualMachine;
+use std::sync::Weak;
+
+/// Handles SIGQUIT (Ctrl-\): prints a class histogram or requests a heap dump, like hotspot with
+/// -XX:+PrintClassHistogram and -XX:+HeapDumpOnCtrlBreak. The signal is blocked and a dispatcher
+/// thread waits for it, so none of it runs in a signal handler. Must run before any other thread is started, they inherit the mask.
+pub fn start_signal_dispatcher(vm: Weak<VirtualMachine>) {
+    let mut signals = unsafe { std::mem::zeroed::<libc::sigset_t>() };
+    unsafe {
+        libc::sigemptyset(&mut signals);
+        libc::sigaddset(&mut signals, libc::SIGQUIT);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &signals, std::ptr::null_mut());
+    }
+    std::thread::Builder::new()
+        .name("Signal Dispatcher".to_string())
+        .spawn(move || {
+            loop {
+                let mut signal = 0;
+                if unsafe { libc::sigwait(&signals, &mut signal) } != 0 {
+                    return;
+                }
+                let Some(vm) = vm.upgrade() else {
+                    return;
+                };
+                vm.handle_quit_signal();
+            }
+        })
+        .expect("failed to spawn the signal dispatcher thread");
+}
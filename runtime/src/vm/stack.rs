@@ -25,11 +25,13 @@ impl FrameType {
 #[derive(Clone)]
 pub struct NativeFrame {
     method_id: MethodId,
+    // kept for the collector, natives get them as a slice
+    args: Vec<Value>,
 }
 
 impl NativeFrame {
-    pub fn new(method_id: MethodId) -> Self {
-        Self { method_id }
+    pub fn new(method_id: MethodId, args: Vec<Value>) -> Self {
+        Self { method_id, args }
     }
 }
 
@@ -344,6 +346,30 @@ impl FrameStack {
     pub fn dup_top(&mut self) -> Result<(), JvmError> {
         self.push_operand(*self.peek_operand()?)
     }
+
+    /// Locals, operands and locked monitors of every frame, arguments of native frames.
+    pub fn visit_refs(&mut self, visitor: &mut dyn FnMut(&mut HeapRef)) {
+        for frame in &mut self.frames {
+            match frame {
+                FrameType::JavaFrame(f) => {
+                    for local in f.locals.iter_mut().flatten() {
+                        local.visit_ref(visitor);
+                    }
+                    for operand in &mut f.operands {
+                        operand.visit_ref(visitor);
+                    }
+                    for obj_ref in &mut f.locked_monitors {
+                        visitor(obj_ref);
+                    }
+                }
+                FrameType::NativeFrame(f) => {
+                    for arg in &mut f.args {
+                        arg.visit_ref(visitor);
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// https://docs.oracle.com/javase/specs/jvms/se25/html/jvms-2.html#jvms-2.6
@@ -353,6 +379,8 @@ pub struct JavaFrame {
     operands: Vec<Value>,
     pc: usize,
     method_id: MethodId,
+    // objects locked by monitorenter in this frame, released if an exception unwinds it
+    locked_monitors: Vec<HeapRef>,
 }
 
 impl JavaFrame {
@@ -382,6 +410,7 @@ impl JavaFrame {
             operands: Vec::with_capacity(max_stack as usize),
             pc: 0,
             method_id,
+            locked_monitors: Vec::new(),
         }
     }
 
@@ -411,4 +440,18 @@ impl JavaFrame {
     pub fn pc(&self) -> usize {
         self.pc
     }
+
+    pub fn add_locked_monitor(&mut self, obj_ref: HeapRef) {
+        self.locked_monitors.push(obj_ref);
+    }
+
+    pub fn remove_locked_monitor(&mut self, obj_ref: HeapRef) {
+        if let Some(pos) = self.locked_monitors.iter().rposition(|r| *r == obj_ref) {
+            self.locked_monitors.remove(pos);
+        }
+    }
+
+    pub fn locked_monitors(&self) -> &[HeapRef] {
+        &self.locked_monitors
+    }
 }
@@ -42,6 +42,15 @@ macro_rules! build_exception {
             )
         )
     };
+    ($kind:ident, from: $from:expr, to: $to:expr) => {
+        crate::error::JvmError::JavaException(
+            crate::error::JavaExceptionFromJvm::with_class_cast(
+                crate::error::JavaExceptionKind::$kind,
+                $from,
+                $to,
+            )
+        )
+    };
 }
 
 #[macro_export]
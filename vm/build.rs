@@ -5,16 +5,20 @@ use walkdir::WalkDir;
 
 const JAVA_FIXTURES_ROOT: &str = "tests/testdata/java";
 const COMPILED_FIXTURES_ROOT: &str = "tests/testdata/compiled";
+const CLASSPATH_FIXTURES_ROOT: &str = "tests/testdata/classpath";
 
 fn set_rebuild_when_changed() {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed={}", JAVA_FIXTURES_ROOT);
+    println!("cargo:rerun-if-changed={}", CLASSPATH_FIXTURES_ROOT);
     println!("cargo:rerun-if-env-changed=JAVA_HOME")
 }
 
 fn main() {
     set_rebuild_when_changed();
     compile_test_fixtures();
+    package_test_jars();
+    compile_classpath_fixtures();
 }
 
 fn remove_compiled_dir_if_exists() {
@@ -22,28 +26,27 @@ fn remove_compiled_dir_if_exists() {
 }
 
 fn compile_test_fixtures() {
-    let java_files = collect_vm_java_fixtures();
+    let java_files = collect_vm_java_fixtures(JAVA_FIXTURES_ROOT);
     if java_files.is_empty() {
         panic!("No Java files found in fixtures.");
     }
 
-    let javac = std::env::var("JAVA_HOME")
-        .map(|j| Path::new(&j).join("bin/javac"))
-        .ok()
-        .filter(|p| p.exists())
-        .map(|p| p.into_os_string())
-        .unwrap_or_else(|| "javac".into());
-
     remove_compiled_dir_if_exists();
 
+    compile(&java_files, COMPILED_FIXTURES_ROOT);
+}
+
+fn compile(java_files: &[PathBuf], output_dir: &str) {
+    let javac = jdk_tool("javac");
+
     let mut cmd = Command::new(javac);
     cmd.arg("-encoding")
         .arg("UTF-8")
         .arg("-g")
         .arg("-d")
-        .arg(COMPILED_FIXTURES_ROOT);
+        .arg(output_dir);
 
-    for file in &java_files {
+    for file in java_files {
         cmd.arg(file);
     }
 
@@ -53,8 +56,89 @@ fn compile_test_fixtures() {
     }
 }
 
-fn collect_vm_java_fixtures() -> Vec<PathBuf> {
-    let mut java_files: Vec<PathBuf> = WalkDir::new(JAVA_FIXTURES_ROOT)
+fn jdk_tool(name: &str) -> std::ffi::OsString {
+    std::env::var("JAVA_HOME")
+        .map(|j| Path::new(&j).join("bin").join(name))
+        .ok()
+        .filter(|p| p.exists())
+        .map(|p| p.into_os_string())
+        .unwrap_or_else(|| name.into())
+}
+
+/// Packages the `launcher.jar` fixture classes next to the compiled ones: deflated and stored
+/// archives for the classpath, and an executable `app.jar` that finds the classes it uses in
+/// `lib/greeter.jar` through the Class-Path of its manifest.
+fn package_test_jars() {
+    let jars_dir = format!("{}/jars", COMPILED_FIXTURES_ROOT);
+    fs::create_dir_all(format!("{}/lib", jars_dir)).expect("Failed to create the jars dir");
+    let all_classes = ["-C", COMPILED_FIXTURES_ROOT, "launcher/jar"];
+    create_jar(&format!("{}/deflated.jar", jars_dir), &all_classes);
+    create_jar(
+        &format!("{}/stored.jar", jars_dir),
+        &[&["--no-compress"], &all_classes[..]].concat(),
+    );
+
+    create_jar(
+        &format!("{}/lib/greeter.jar", jars_dir),
+        &[
+            "-C",
+            COMPILED_FIXTURES_ROOT,
+            "launcher/jar/Greeter.class",
+            "-C",
+            COMPILED_FIXTURES_ROOT,
+            "launcher/jar/Greeter$Loud.class",
+        ],
+    );
+    let manifest_path = format!("{}/app.mf", jars_dir);
+    fs::write(
+        &manifest_path,
+        "Main-Class: launcher.jar.JarMain\nClass-Path: lib/greeter.jar\n",
+    )
+    .expect("Failed to write the manifest");
+    create_jar(
+        &format!("{}/app.jar", jars_dir),
+        &[
+            "--manifest",
+            &manifest_path,
+            "-C",
+            COMPILED_FIXTURES_ROOT,
+            "launcher/jar/JarMain.class",
+        ],
+    );
+}
+
+/// Compiles the `first` and `second` classpath fixtures to their own directories and JARs, both
+/// have a `classpath.order.Shadowed` class. `Absent`, used by the main class of `first`, is
+/// removed to be missing from every entry.
+fn compile_classpath_fixtures() {
+    let jars_dir = format!("{}/jars", COMPILED_FIXTURES_ROOT);
+    for name in ["first", "second"] {
+        let output_dir = format!("{}/classpath/{}", COMPILED_FIXTURES_ROOT, name);
+        let java_files = collect_vm_java_fixtures(&format!("{}/{}", CLASSPATH_FIXTURES_ROOT, name));
+        compile(&java_files, &output_dir);
+        let _ = fs::remove_file(format!("{}/classpath/order/Absent.class", output_dir));
+        create_jar(
+            &format!("{}/{}.jar", jars_dir, name),
+            &["-C", &output_dir, "classpath"],
+        );
+    }
+}
+
+fn create_jar(jar_path: &str, args: &[&str]) {
+    let output = Command::new(jdk_tool("jar"))
+        .arg("--create")
+        .arg("--file")
+        .arg(jar_path)
+        .args(args)
+        .output()
+        .expect("Failed to run jar");
+    if !output.status.success() {
+        panic!("jar failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+fn collect_vm_java_fixtures(root: &str) -> Vec<PathBuf> {
+    let mut java_files: Vec<PathBuf> = WalkDir::new(root)
         .into_iter()
         .filter_map(Result::ok)
         .map(|e| e.into_path())
@@ -1,5 +1,6 @@
 use clap::Parser;
-use lagertha_runtime::VmConfig;
+use lagertha_runtime::{JarManifest, VmConfig};
+use std::path::PathBuf;
 use tracing_log::log::debug;
 
 #[derive(Parser, Debug)]
@@ -11,7 +12,7 @@ pub struct Args {
         visible_alias = "cp",
         visible_alias = "class-path",
         value_delimiter = ';',
-        help = "Classpath entries (only dirs, no jars(todo)); use ';' as separator"
+        help = "Classpath entries, directories or JAR/ZIP archives; use ';' as separator"
     )]
     pub class_path: Vec<String>,
     #[arg(
@@ -21,10 +22,191 @@ pub struct Args {
     )]
     pub jdwp_port: Option<u16>,
     #[arg(
+        short = 'X',
+        value_name = "OPTION",
+        help = "Non-standard options: -Xms<size> initial heap size, -Xmx<size> maximum heap size, \
+        -Xmn<size> nursery size; sizes in bytes or with a k, m or g suffix. \
+        -XX:+HeapDumpOnOutOfMemoryError, -XX:+HeapDumpOnCtrlBreak on SIGQUIT, \
+        -XX:HeapDumpPath=<file or dir>, \
+        -XX:+PrintClassHistogram on SIGQUIT, -XX:+PrintClassHistogramAtExit, \
+        -XX:+VerifyHeap before and after every GC, the heap isn't verified between collections"
+    )]
+    pub non_standard_options: Vec<String>,
+    #[arg(
+        long = "jar",
+        value_name = "JARFILE",
+        help = "Runs the Main-Class of the JAR manifest, -jar works too. The classpath is the JAR \
+        and the Class-Path entries of its manifest, the ones given with -c are ignored; \
+        the arguments after the JAR are passed to the main method"
+    )]
+    pub jar: Option<PathBuf>,
+    #[arg(
+        required_unless_present = "jar",
         help = "Main class to run from path that matches the package structure \
         (e.g. com.example.Main or com/example/Main for com/example/Main.class)"
     )]
-    pub main_class_path: String,
+    pub main_class_path: Option<String>,
+    #[arg(
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        help = "Arguments passed to the main method"
+    )]
+    pub main_args: Vec<String>,
+}
+
+const DEFAULT_INITIAL_HEAP_SIZE: usize = 8 * 1024 * 1024;
+const DEFAULT_MAX_HEAP_SIZE: usize = 256 * 1024 * 1024;
+const DEFAULT_NURSERY_SIZE: usize = 256 * 1024;
+
+struct NonStandardOptions {
+    initial_heap_size: usize,
+    max_heap_size: usize,
+    nursery_size: usize,
+    heap_dump_on_out_of_memory: bool,
+    heap_dump_path: Option<PathBuf>,
+    heap_dump_on_ctrl_break: bool,
+    print_class_histogram: bool,
+    print_class_histogram_at_exit: bool,
+    verify_heap: bool,
+}
+
+/// Options given with -X, without it. The initial heap size defaults to at most the maximum one
+/// and the nursery to at most a quarter of the initial heap.
+fn parse_non_standard_options(options: &[String]) -> Result<NonStandardOptions, String> {
+    let (mut initial, mut max, mut nursery) = (None, None, None);
+    let (mut heap_dump_on_out_of_memory, mut heap_dump_on_ctrl_break) = (false, false);
+    let mut heap_dump_path = None;
+    let (mut print_class_histogram, mut print_class_histogram_at_exit) = (false, false);
+    let mut verify_heap = false;
+    for option in options {
+        if let Some(enabled) = parse_boolean_flag(option, "HeapDumpOnOutOfMemoryError") {
+            heap_dump_on_out_of_memory = enabled;
+        } else if let Some(enabled) = parse_boolean_flag(option, "HeapDumpOnCtrlBreak") {
+            heap_dump_on_ctrl_break = enabled;
+        } else if let Some(enabled) = parse_boolean_flag(option, "PrintClassHistogram") {
+            print_class_histogram = enabled;
+        } else if let Some(enabled) = parse_boolean_flag(option, "PrintClassHistogramAtExit") {
+            print_class_histogram_at_exit = enabled;
+        } else if let Some(enabled) = parse_boolean_flag(option, "VerifyHeap") {
+            verify_heap = enabled;
+        } else if let Some(path) = option.strip_prefix("X:HeapDumpPath=") {
+            heap_dump_path = Some(PathBuf::from(path));
+        } else if let Some(size) = option.strip_prefix("ms") {
+            initial = Some(parse_memory_size(size)?);
+        } else if let Some(size) = option.strip_prefix("mx") {
+            max = Some(parse_memory_size(size)?);
+        } else if let Some(size) = option.strip_prefix("mn") {
+            nursery = Some(parse_memory_size(size)?);
+        } else {
+            return Err(format!("Unrecognized option: -X{}", option));
+        }
+    }
+
+    let max = max.unwrap_or(DEFAULT_MAX_HEAP_SIZE);
+    let initial = initial.unwrap_or(DEFAULT_INITIAL_HEAP_SIZE.min(max));
+    let nursery = nursery.unwrap_or(DEFAULT_NURSERY_SIZE.min(initial / 4));
+    if initial > max {
+        return Err(
+            "Initial heap size set to a larger value than the maximum heap size".to_string(),
+        );
+    }
+    if nursery >= initial {
+        return Err("Nursery size must be smaller than the initial heap size".to_string());
+    }
+    Ok(NonStandardOptions {
+        initial_heap_size: initial,
+        max_heap_size: max,
+        nursery_size: nursery,
+        heap_dump_on_out_of_memory,
+        heap_dump_path,
+        heap_dump_on_ctrl_break,
+        print_class_histogram,
+        print_class_histogram_at_exit,
+        verify_heap,
+    })
+}
+
+/// -XX:+<name> enables a flag and -XX:-<name> disables it.
+fn parse_boolean_flag(option: &str, name: &str) -> Option<bool> {
+    match option.strip_prefix("X:")?.strip_suffix(name)? {
+        "+" => Some(true),
+        "-" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses sizes like `512k`, `64m`, `1g` or a plain number of bytes.
+fn parse_memory_size(size: &str) -> Result<usize, String> {
+    let (digits, multiplier) = match size.chars().last().map(|c| c.to_ascii_lowercase()) {
+        Some('k') => (&size[..size.len() - 1], 1024),
+        Some('m') => (&size[..size.len() - 1], 1024 * 1024),
+        Some('g') => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .filter(|value| *value > 0)
+        .ok_or_else(|| format!("Invalid memory size: {}", size))
+}
+
+/// The java launcher takes `-jar <file>`, which clap reads as `-j ar`. Before the main class it
+/// is rewritten to `--jar`, and what follows the JAR is left to the main method like in java.
+fn rewrite_jar_option(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    // options that take their value as the next argument
+    const OPTIONS_WITH_VALUE: [&str; 7] = [
+        "-c",
+        "--classpath",
+        "--cp",
+        "--class-path",
+        "-j",
+        "--jdwp-port",
+        "-X",
+    ];
+    let mut args = args.into_iter();
+    // the program name
+    let mut rewritten = args.next().into_iter().collect::<Vec<_>>();
+    while let Some(arg) = args.next() {
+        if arg == "-jar" {
+            rewritten.push("--jar".to_string());
+            rewritten.extend(args.next());
+            rewritten.push("--".to_string());
+            rewritten.extend(args);
+            break;
+        }
+        let is_main_class = !arg.starts_with('-');
+        let takes_value = OPTIONS_WITH_VALUE.contains(&arg.as_str());
+        rewritten.push(arg);
+        if is_main_class {
+            rewritten.extend(args);
+            break;
+        }
+        if takes_value {
+            rewritten.extend(args.next());
+        }
+    }
+    rewritten
+}
+
+/// With `-jar`, the main class and the classpath come from the manifest, and the first
+/// positional argument is the first one of the main method.
+fn resolve_main_class(args: &mut Args) -> Result<String, String> {
+    let Some(jar) = args.jar.take() else {
+        let main_class_path = args.main_class_path.take().ok_or("No main class given")?;
+        return Ok(main_class_path.replace('.', "/"));
+    };
+    if let Some(first_arg) = args.main_class_path.take() {
+        args.main_args.insert(0, first_arg);
+    }
+    let manifest = JarManifest::read(&jar)
+        .map_err(|e| format!("Invalid or corrupt jarfile {}: {}", jar.display(), e))?;
+    let main_class = manifest
+        .main_class
+        .ok_or_else(|| format!("no main manifest attribute, in {}", jar.display()))?;
+    args.class_path = vec![jar.to_string_lossy().into_owned()];
+    args.class_path.extend(manifest.class_path);
+    Ok(main_class.replace('.', "/"))
 }
 
 fn create_vm_configuration(mut args: Args, main_class: String) -> Result<VmConfig, String> {
@@ -35,7 +217,8 @@ fn create_vm_configuration(mut args: Args, main_class: String) -> Result<VmConfi
             .expect("cannot get current dir");
         args.class_path.push(current_dir);
     }
-    let home = std::path::PathBuf::from(&java_home);
+    let options = parse_non_standard_options(&args.non_standard_options)?;
+    let home = PathBuf::from(&java_home);
     let release_file = format!("{}/release", java_home);
 
     let contents = std::fs::read_to_string(release_file).expect("cannot read release file");
@@ -45,10 +228,18 @@ fn create_vm_configuration(mut args: Args, main_class: String) -> Result<VmConfi
             return Ok(VmConfig {
                 home,
                 main_class,
+                main_args: args.main_args,
                 version: value.trim_matches('"').to_string(),
                 class_path: args.class_path,
-                initial_heap_size: 0,
-                max_heap_size: 0,
+                initial_heap_size: options.initial_heap_size,
+                max_heap_size: options.max_heap_size,
+                nursery_size: options.nursery_size,
+                heap_dump_on_out_of_memory: options.heap_dump_on_out_of_memory,
+                heap_dump_path: options.heap_dump_path,
+                heap_dump_on_ctrl_break: options.heap_dump_on_ctrl_break,
+                print_class_histogram: options.print_class_histogram,
+                print_class_histogram_at_exit: options.print_class_histogram_at_exit,
+                verify_heap: options.verify_heap,
                 frame_stack_size: 256,
                 jdwp_port: args.jdwp_port,
             });
@@ -61,19 +252,17 @@ fn create_vm_configuration(mut args: Args, main_class: String) -> Result<VmConfi
 fn main() {
     #[cfg(feature = "log-runtime-traces")]
     common::utils::telemetry::init_tracing();
-    let args = Args::parse();
+    let mut args = Args::parse_from(rewrite_jar_option(std::env::args()));
     debug!("Provided command line arguments: {:?}", args);
 
-    let main_class = args.main_class_path.replace('.', "/");
-
-    let vm_config = match create_vm_configuration(args, main_class) {
+    let vm_config = match resolve_main_class(&mut args)
+        .and_then(|main_class| create_vm_configuration(args, main_class))
+    {
         Ok(config) => config,
         Err(e) => {
             eprintln!("Error creating VM configuration: {}", e);
-            return;
+            std::process::exit(1);
         }
     };
-    if let Err(_) = runtime::start(vm_config) {
-        std::process::exit(1);
-    }
+    std::process::exit(runtime::start(vm_config));
 }
@@ -1,10 +1,24 @@
 use assert_cmd::Command;
+use assert_cmd::cargo::CommandCargoExt;
 use insta::with_settings;
 use rstest::rstest;
+use std::io::{BufRead, BufReader, Read};
 use std::path::{Path, PathBuf};
+use std::process::Stdio;
 
 const DISPLAY_SNAPSHOT_PATH: &str = "../snapshots";
 
+/// The classes compiled by build.rs.
+fn compiled_dir() -> PathBuf {
+    let current_dir = std::env::current_dir().expect("Cannot get current dir");
+    current_dir.join("tests/testdata/compiled")
+}
+
+/// The vm binary, requires cargo build.
+fn vm() -> Command {
+    Command::cargo_bin("vm").unwrap()
+}
+
 fn transform_absolute_path_to_package(path: &Path) -> PathBuf {
     let marker = Path::new("tests/testdata/compiled");
     let components = path.components().collect::<Vec<_>>();
@@ -42,11 +56,9 @@ fn non_error_cases(
     #[files("**/*OkMain.class")]
     path: PathBuf,
 ) {
-    // requires cargo build
-    let current_dir = std::env::current_dir().expect("Cannot get current dir");
-    let class_path = current_dir.join("tests/testdata/compiled");
+    let class_path = compiled_dir();
     let main_class_path = transform_absolute_path_to_package(&path);
-    let mut cmd = Command::cargo_bin("vm").unwrap();
+    let mut cmd = vm();
     cmd.arg("-c").arg(class_path).arg(&main_class_path);
 
     let output = cmd.assert().success().get_output().clone();
@@ -78,11 +90,9 @@ fn error_cases(
     path: PathBuf,
 ) {
     // given
-    // requires cargo build
-    let current_dir = std::env::current_dir().expect("Cannot get current dir");
-    let class_path = current_dir.join("tests/testdata/compiled");
+    let class_path = compiled_dir();
     let main_class_path = transform_absolute_path_to_package(&path);
-    let mut cmd = Command::cargo_bin("vm").unwrap();
+    let mut cmd = vm();
     cmd.arg("-c").arg(class_path).arg(&main_class_path);
 
     // when
@@ -107,3 +117,225 @@ fn error_cases(
         }
     );
 }
+
+#[test]
+fn exit_status_is_passed_through() {
+    let class_path = compiled_dir();
+    let mut cmd = vm();
+    cmd.arg("-c")
+        .arg(class_path)
+        .arg("lifecycle/exit_code/ExitCodeErrMain");
+
+    cmd.assert().code(3);
+}
+
+#[test]
+fn configuration_error_exits_with_failure() {
+    let class_path = compiled_dir();
+    let mut cmd = vm();
+    cmd.args(["-Xms16m", "-Xmx4m"])
+        .arg("-c")
+        .arg(class_path)
+        .arg("lifecycle/exit_code/ExitCodeErrMain");
+
+    cmd.assert().code(1).stderr(
+        "Error creating VM configuration: \
+         Initial heap size set to a larger value than the maximum heap size\n",
+    );
+}
+
+#[test]
+fn program_arguments_are_passed_to_main() {
+    let class_path = compiled_dir();
+    let mut cmd = vm();
+    cmd.arg("-c")
+        .arg(class_path)
+        .arg("launcher/main_args/MainArgsOkMain")
+        .args(["first", "second arg", "--third"]);
+
+    cmd.assert()
+        .success()
+        .stdout("3\nfirst\nsecond arg\n--third\n");
+}
+
+#[rstest]
+#[case::deflated("deflated.jar")]
+#[case::stored("stored.jar")]
+fn classes_are_loaded_from_jars(#[case] jar_name: &str) {
+    let jar_path = compiled_dir().join("jars").join(jar_name);
+    let mut cmd = vm();
+    cmd.arg("-c").arg(jar_path).arg("launcher.jar.JarMain");
+
+    cmd.assert()
+        .success()
+        .stdout("Hello from jar\nHELLO FROM ARCHIVE!\n");
+}
+
+#[rstest]
+#[case::directories(&["classpath/first", "classpath/second"], "first")]
+#[case::jar_then_directory(&["jars/first.jar", "classpath/second"], "first")]
+#[case::directory_then_jar(&["classpath/second", "jars/first.jar"], "second")]
+#[case::jars(&["jars/second.jar", "jars/first.jar"], "second")]
+fn first_classpath_entry_with_the_class_wins(#[case] entries: &[&str], #[case] origin: &str) {
+    let compiled_dir = compiled_dir();
+    let class_path = entries
+        .iter()
+        .map(|entry| compiled_dir.join(entry).to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join(";");
+    let mut cmd = vm();
+    cmd.arg("-c")
+        .arg(class_path)
+        .arg("classpath.order.ClasspathOrderMain");
+
+    cmd.assert()
+        .success()
+        .stdout(format!("{}\nAbsent not found\nAbsent not found\n", origin));
+}
+
+#[test]
+fn executable_jar_is_run_with_its_manifest() {
+    let jar_path = compiled_dir().join("jars/app.jar");
+    let mut cmd = vm();
+    // the classes of Class-Path: lib/greeter.jar aren't in app.jar, and what follows the JAR
+    // goes to main even if it looks like an option
+    cmd.arg("-jar").arg(jar_path).args(["-c", "second"]);
+
+    cmd.assert()
+        .success()
+        .stdout("Hello from jar\nHELLO FROM ARCHIVE!\n-c\nsecond\n");
+}
+
+#[test]
+fn heap_is_limited_by_max_heap_size() {
+    let class_path = compiled_dir();
+    let mut cmd = vm();
+    cmd.args(["-Xms4m", "-Xmx16m", "-Xmn1m"])
+        .arg("-c")
+        .arg(class_path)
+        .arg("gc/heap_limit/HeapLimitMain");
+
+    cmd.assert()
+        .success()
+        .stdout("16\ntrue\ntrue\nJava heap space\ntrue\n");
+}
+
+#[test]
+fn heap_is_dumped_on_out_of_memory() {
+    let class_path = compiled_dir();
+    let dump_path = std::env::temp_dir().join(format!("heap_dump_{}.hprof", std::process::id()));
+    let _ = std::fs::remove_file(&dump_path);
+    let mut cmd = vm();
+    cmd.args(["-Xms4m", "-Xmx16m", "-Xmn1m"])
+        .arg("-XX:+HeapDumpOnOutOfMemoryError")
+        .arg(format!("-XX:HeapDumpPath={}", dump_path.display()))
+        .arg("-c")
+        .arg(class_path)
+        .arg("gc/heap_limit/HeapLimitMain");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).expect("Stdout is not UTF-8");
+    assert!(stdout.contains(&format!("Dumping heap to {} ...", dump_path.display())));
+    let dump = std::fs::read(&dump_path).expect("Heap dump not written");
+    std::fs::remove_file(&dump_path).expect("Cannot remove heap dump");
+    assert!(dump.starts_with(b"JAVA PROFILE 1.0.2\0"));
+    let main_class_name = b"gc/heap_limit/HeapLimitMain";
+    assert!(
+        dump.windows(main_class_name.len())
+            .any(|window| window == main_class_name)
+    );
+}
+
+#[test]
+fn heap_is_dumped_on_ctrl_break() {
+    let class_path = compiled_dir();
+    let dump_path =
+        std::env::temp_dir().join(format!("heap_dump_ctrl_break_{}.hprof", std::process::id()));
+    let _ = std::fs::remove_file(&dump_path);
+    // not vm(), the assert_cmd Command only runs to completion and the signal is sent while the
+    // vm runs
+    let mut child = std::process::Command::cargo_bin("vm")
+        .unwrap()
+        .arg("-XX:+HeapDumpOnCtrlBreak")
+        .arg(format!("-XX:HeapDumpPath={}", dump_path.display()))
+        .arg("-c")
+        .arg(class_path)
+        .arg("gc/heap_dump/HeapDumpMain")
+        .arg(&dump_path)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Cannot start the vm");
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut ready = String::new();
+    stdout.read_line(&mut ready).expect("Cannot read stdout");
+    assert_eq!(ready, "ready\n");
+
+    // what Ctrl-\ sends in a terminal
+    let kill_status = std::process::Command::new("kill")
+        .arg("-QUIT")
+        .arg(child.id().to_string())
+        .status()
+        .expect("Cannot run kill");
+    assert!(kill_status.success());
+    let mut rest = String::new();
+    stdout
+        .read_to_string(&mut rest)
+        .expect("Cannot read stdout");
+    assert!(child.wait().expect("Cannot wait for the vm").success());
+
+    assert!(rest.starts_with(&format!("Dumping heap to {} ...\n", dump_path.display())));
+    assert!(rest.ends_with("true\n"));
+    let dump = std::fs::read(&dump_path).expect("Heap dump not written");
+    std::fs::remove_file(&dump_path).expect("Cannot remove heap dump");
+    assert!(dump.starts_with(b"JAVA PROFILE 1.0.2\0"));
+    let main_class_name = b"gc/heap_dump/HeapDumpMain";
+    assert!(
+        dump.windows(main_class_name.len())
+            .any(|window| window == main_class_name)
+    );
+}
+
+#[test]
+fn heap_verifies_across_collections() {
+    let class_path = compiled_dir();
+    let mut cmd = vm();
+    cmd.args(["-Xms4m", "-Xmx16m", "-Xmn1m"])
+        .arg("-XX:+VerifyHeap")
+        .arg("-c")
+        .arg(class_path)
+        .arg("gc/generations/GenerationsOkMain");
+
+    cmd.assert()
+        .success()
+        .stdout("42\n104950\n20\n4999950000\nAll generation tests passed\n")
+        .stderr("");
+}
+
+#[test]
+fn class_histogram_is_printed_at_exit() {
+    let class_path = compiled_dir();
+    let mut cmd = vm();
+    cmd.arg("-XX:+PrintClassHistogramAtExit")
+        .arg("-c")
+        .arg(class_path)
+        .arg("gc/histogram/HistogramMain");
+
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let stdout = String::from_utf8(output).expect("Stdout is not UTF-8");
+    let instances_of = |class_name: &str| {
+        stdout
+            .lines()
+            .find(|line| line.ends_with(&format!("  {}", class_name)))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .map(|instances| instances.to_string())
+    };
+    assert!(stdout.starts_with("1000\n"));
+    assert_eq!(
+        instances_of("gc.histogram.HistogramMain$Node").as_deref(),
+        Some("1000")
+    );
+    assert_eq!(
+        instances_of("[Lgc.histogram.HistogramMain$Node;").as_deref(),
+        Some("1")
+    );
+}
@@ -0,0 +1,7 @@
+package classpath.order;
+
+public class Absent {
+    public static String origin() {
+        return "absent";
+    }
+}
@@ -0,0 +1,16 @@
+package classpath.order;
+
+// Run with the first and second classpath entries in either order, Shadowed is in both
+public class ClasspathOrderMain {
+    public static void main(String[] args) {
+        System.out.println(Shadowed.origin());
+        // removed from the classpath after compilation, looked up twice to miss twice
+        for (int i = 0; i < 2; i++) {
+            try {
+                System.out.println(Absent.origin());
+            } catch (NoClassDefFoundError e) {
+                System.out.println("Absent not found");
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+package classpath.order;
+
+public class Shadowed {
+    public static String origin() {
+        return "second";
+    }
+}
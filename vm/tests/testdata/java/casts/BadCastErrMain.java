@@ -0,0 +1,9 @@
+package casts.bad_cast;
+
+public class BadCastErrMain {
+    public static void main(String[] args) {
+        Object obj = Integer.valueOf(42);
+        String s = (String) obj;
+        System.out.println(s);
+    }
+}
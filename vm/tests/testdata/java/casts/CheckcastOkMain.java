@@ -0,0 +1,79 @@
+package casts.checkcast;
+
+import java.io.Serializable;
+
+public class CheckcastOkMain {
+    interface Shape {
+    }
+
+    static class Circle implements Shape {
+    }
+
+    static class Square implements Shape {
+    }
+
+    public static void main(String[] args) {
+        test_upcasts();
+        test_array_covariance();
+        test_null_cast();
+        test_bad_class_cast();
+        test_bad_interface_cast();
+        test_bad_array_cast();
+        System.out.println("All checkcast tests passed");
+    }
+
+    static void test_upcasts() {
+        Object obj = new Circle();
+        Shape shape = (Shape) obj;
+        Circle circle = (Circle) shape;
+        System.out.println(circle == obj);
+    }
+
+    static void test_array_covariance() {
+        Object strings = new String[] {"a", "b"};
+        Object[] objects = (Object[]) strings;
+        Cloneable cloneable = (Cloneable) strings;
+        Serializable serializable = (Serializable) new int[2];
+        Object nested = new int[][] {new int[1], new int[3]};
+        int[][] matrix = (int[][]) nested;
+        System.out.println(objects.length);
+        System.out.println(matrix[1].length);
+        System.out.println(cloneable != null && serializable != null);
+    }
+
+    static void test_null_cast() {
+        Object obj = null;
+        String s = (String) obj;
+        System.out.println(s == null);
+    }
+
+    static void test_bad_class_cast() {
+        try {
+            Object obj = new Circle();
+            Square square = (Square) obj;
+            System.out.println("Unreachable");
+        } catch (ClassCastException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static void test_bad_interface_cast() {
+        try {
+            Object obj = "not a shape";
+            Shape shape = (Shape) obj;
+            System.out.println("Unreachable");
+        } catch (ClassCastException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static void test_bad_array_cast() {
+        try {
+            Object objects = new Object[1];
+            String[] strings = (String[]) objects;
+            System.out.println("Unreachable");
+        } catch (ClassCastException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+}
@@ -0,0 +1,147 @@
+package classloading.app;
+
+import java.io.ByteArrayInputStream;
+import java.io.FileNotFoundException;
+import java.io.InputStream;
+import java.lang.invoke.MethodHandle;
+import java.lang.invoke.MethodHandles;
+import java.lang.invoke.MethodType;
+import java.net.URL;
+import java.net.URLClassLoader;
+import java.net.URLConnection;
+import java.net.URLStreamHandler;
+import java.util.Base64;
+import java.util.HashMap;
+import java.util.Map;
+
+public class AppClassLoaderOkMain {
+    static final String SHARED_NAME = "classloading.app.Shared";
+    static final String CALLER_NAME = "classloading.app.Caller";
+
+    // javac -g:none --release 8 of Shared.java, the same class as the classpath one
+    static final byte[] SHARED_CLASS = Base64.getDecoder().decode(
+            "yv66vgAAADQACgoAAgADBwAEDAAFAAYBABBqYXZhL2xhbmcvT2JqZWN0AQAGPGluaXQ+AQADKClW"
+                    + "BwAIAQAXY2xhc3Nsb2FkaW5nL2FwcC9TaGFyZWQBAARDb2RlACEABwACAAAAAAABAAEABQAGAAEA"
+                    + "CQAAABEAAQABAAAABSq3AAGxAAAAAAAA");
+
+    // javac -g:none --release 8, kept off the classpath so only the test loaders can define it:
+    //
+    // package classloading.app;
+    //
+    // public class Caller {
+    //     public static void call() {
+    //         Holder.accept(new Shared());
+    //     }
+    // }
+    static final byte[] CALLER_CLASS = Base64.getDecoder().decode(
+            "yv66vgAAADQAFAoAAgADBwAEDAAFAAYBABBqYXZhL2xhbmcvT2JqZWN0AQAGPGluaXQ+AQADKClW"
+                    + "BwAIAQAXY2xhc3Nsb2FkaW5nL2FwcC9TaGFyZWQKAAcAAwoACwAMBwANDAAOAA8BABdjbGFzc2xv"
+                    + "YWRpbmcvYXBwL0hvbGRlcgEABmFjY2VwdAEAHChMY2xhc3Nsb2FkaW5nL2FwcC9TaGFyZWQ7KVYH"
+                    + "ABEBABdjbGFzc2xvYWRpbmcvYXBwL0NhbGxlcgEABENvZGUBAARjYWxsACEAEAACAAAAAAACAAEA"
+                    + "BQAGAAEAEgAAABEAAQABAAAABSq3AAGxAAAAAAAJABMABgABABIAAAAXAAIAAAAAAAu7AAdZtwAJ"
+                    + "uAAKsQAAAAAAAA==");
+
+    public static void main(String[] args) throws Throwable {
+        test_app_classes_have_the_system_loader();
+        test_url_loader_delegates_to_the_app_loader();
+        test_url_loader_without_parent_is_isolated();
+        test_loader_constraint_is_checked();
+        System.out.println("All app class loader tests passed");
+    }
+
+    static void test_app_classes_have_the_system_loader() {
+        ClassLoader loader = AppClassLoaderOkMain.class.getClassLoader();
+        System.out.println(loader != null);
+        System.out.println(loader == ClassLoader.getSystemClassLoader());
+        System.out.println(Shared.class.getClassLoader() == loader);
+        System.out.println(String.class.getClassLoader() == null);
+    }
+
+    static void test_url_loader_delegates_to_the_app_loader() throws Exception {
+        URLClassLoader loader = new URLClassLoader(new URL[] {memoryUrl()});
+        System.out.println(loader.getParent() == ClassLoader.getSystemClassLoader());
+        System.out.println(loader.loadClass(SHARED_NAME) == Shared.class);
+    }
+
+    static void test_url_loader_without_parent_is_isolated() throws Exception {
+        URLClassLoader loader = new URLClassLoader(new URL[] {memoryUrl()}, null);
+        Class<?> shared = loader.loadClass(SHARED_NAME);
+        System.out.println(shared != Shared.class);
+        System.out.println(shared.getClassLoader() == loader);
+        System.out.println(loader.loadClass("java.lang.String") == String.class);
+        try {
+            // on the classpath, but the bootstrap loader doesn't see it
+            loader.loadClass("classloading.app.Holder");
+            System.out.println("no exception");
+        } catch (ClassNotFoundException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static void test_loader_constraint_is_checked() throws Throwable {
+        Holder.accept(new Shared());
+        ChildFirstLoader loader = new ChildFirstLoader(memoryUrl());
+        MethodHandle call = MethodHandles.lookup()
+                .findStatic(loader.loadClass(CALLER_NAME), "call", MethodType.methodType(void.class));
+        try {
+            // Caller passes its own Shared to Holder, which takes the one of the app loader
+            call.invokeExact();
+            System.out.println("no exception");
+        } catch (LinkageError e) {
+            System.out.println(e.getClass().getName());
+        }
+    }
+
+    static URL memoryUrl() throws Exception {
+        return new URL("mem", "", -1, "/", new MemoryHandler());
+    }
+
+    // serves the class files above, the VM has no file streams for URLClassLoader to read
+    static class MemoryHandler extends URLStreamHandler {
+        static final Map<String, byte[]> CLASSES = new HashMap<>();
+
+        static {
+            CLASSES.put("/classloading/app/Shared.class", SHARED_CLASS);
+            CLASSES.put("/classloading/app/Caller.class", CALLER_CLASS);
+        }
+
+        @Override
+        protected URLConnection openConnection(URL url) throws FileNotFoundException {
+            byte[] bytes = CLASSES.get(url.getPath());
+            if (bytes == null) {
+                throw new FileNotFoundException(url.toString());
+            }
+            return new URLConnection(url) {
+                @Override
+                public void connect() {
+                }
+
+                @Override
+                public InputStream getInputStream() {
+                    return new ByteArrayInputStream(bytes);
+                }
+            };
+        }
+    }
+
+    // child-first for Shared and Caller, everything else comes from the app loader
+    static class ChildFirstLoader extends URLClassLoader {
+        ChildFirstLoader(URL url) {
+            super(new URL[] {url});
+        }
+
+        @Override
+        protected Class<?> loadClass(String name, boolean resolve) throws ClassNotFoundException {
+            synchronized (getClassLoadingLock(name)) {
+                Class<?> c = findLoadedClass(name);
+                if (c == null && (name.equals(SHARED_NAME) || name.equals(CALLER_NAME))) {
+                    c = findClass(name);
+                }
+                if (c == null) {
+                    c = super.loadClass(name, resolve);
+                }
+                return c;
+            }
+        }
+    }
+}
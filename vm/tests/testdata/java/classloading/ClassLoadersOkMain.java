@@ -0,0 +1,146 @@
+package classloading.loaders;
+
+import java.lang.invoke.MethodHandle;
+import java.lang.invoke.MethodHandles;
+import java.lang.invoke.MethodType;
+import java.util.Base64;
+
+public class ClassLoadersOkMain {
+    static final String PLUGIN_NAME = "plugin.Plugin";
+
+    // javac -g:none --release 8, kept off the classpath so only the test loaders can define it:
+    //
+    // package plugin;
+    //
+    // public class Plugin {
+    //     private static int calls;
+    //
+    //     public static int next() {
+    //         calls = Math.addExact(calls, 1);
+    //         return calls;
+    //     }
+    // }
+    static final byte[] PLUGIN_CLASS = Base64.getDecoder().decode(
+            "yv66vgAAADQAFgoAAgADBwAEDAAFAAYBABBqYXZhL2xhbmcvT2JqZWN0AQAGPGluaXQ+AQADKClW"
+                    + "CQAIAAkHAAoMAAsADAEADXBsdWdpbi9QbHVnaW4BAAVjYWxscwEAAUkKAA4ADwcAEAwAEQASAQAO"
+                    + "amF2YS9sYW5nL01hdGgBAAhhZGRFeGFjdAEABShJSSlJAQAEQ29kZQEABG5leHQBAAMoKUkAIQAI"
+                    + "AAIAAAABAAoACwAMAAAAAgABAAUABgABABMAAAARAAEAAQAAAAUqtwABsQAAAAAACQAUABUAAQAT"
+                    + "AAAAGgACAAAAAAAOsgAHBLgADbMAB7IAB6wAAAAAAAA=");
+
+    public static void main(String[] args) throws Throwable {
+        test_bootstrap_classes_have_no_loader();
+        test_loader_delegates_to_bootstrap();
+        test_defined_class_belongs_to_its_loader();
+        test_loaded_class_is_found_again();
+        test_same_name_in_two_loaders();
+        test_static_state_is_per_loader();
+        test_duplicate_definition_fails();
+        test_wrong_name_fails();
+        test_missing_class_is_not_found();
+        System.out.println("All class loader tests passed");
+    }
+
+    static void test_bootstrap_classes_have_no_loader() {
+        System.out.println(String.class.getClassLoader() == null);
+        System.out.println(int[].class.getClassLoader() == null);
+    }
+
+    static void test_loader_delegates_to_bootstrap() throws ClassNotFoundException {
+        PluginLoader loader = new PluginLoader();
+        System.out.println(loader.loadClass("java.lang.String") == String.class);
+        System.out.println(loader.loadClass("java.util.ArrayList") == java.util.ArrayList.class);
+    }
+
+    static void test_defined_class_belongs_to_its_loader() throws ClassNotFoundException {
+        PluginLoader loader = new PluginLoader();
+        Class<?> plugin = loader.loadClass(PLUGIN_NAME);
+        System.out.println(plugin.getName());
+        System.out.println(plugin.getClassLoader() == loader);
+        System.out.println(plugin.getSuperclass() == Object.class);
+    }
+
+    static void test_loaded_class_is_found_again() throws ClassNotFoundException {
+        PluginLoader loader = new PluginLoader();
+        Class<?> plugin = loader.loadClass(PLUGIN_NAME);
+        System.out.println(loader.loadClass(PLUGIN_NAME) == plugin);
+        System.out.println(loader.defined);
+    }
+
+    static void test_same_name_in_two_loaders() throws ClassNotFoundException {
+        Class<?> first = new PluginLoader().loadClass(PLUGIN_NAME);
+        Class<?> second = new PluginLoader().loadClass(PLUGIN_NAME);
+        System.out.println(first != second);
+        System.out.println(first.getName().equals(second.getName()));
+        System.out.println(first.getClassLoader() != second.getClassLoader());
+    }
+
+    static void test_static_state_is_per_loader() throws Throwable {
+        MethodHandle first = nextMethod(new PluginLoader().loadClass(PLUGIN_NAME));
+        MethodHandle second = nextMethod(new PluginLoader().loadClass(PLUGIN_NAME));
+        int a = (int) first.invokeExact();
+        int b = (int) first.invokeExact();
+        int c = (int) second.invokeExact();
+        System.out.println(a + " " + b + " " + c);
+    }
+
+    static void test_duplicate_definition_fails() throws ClassNotFoundException {
+        PluginLoader loader = new PluginLoader();
+        loader.loadClass(PLUGIN_NAME);
+        try {
+            loader.define(PLUGIN_NAME);
+            System.out.println("no exception");
+        } catch (LinkageError e) {
+            System.out.println(e.getClass().getName());
+        }
+    }
+
+    static void test_wrong_name_fails() {
+        try {
+            new PluginLoader().define("plugin.Other");
+            System.out.println("no exception");
+        } catch (NoClassDefFoundError e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static void test_missing_class_is_not_found() {
+        try {
+            new PluginLoader().loadClass("plugin.Missing");
+            System.out.println("no exception");
+        } catch (ClassNotFoundException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static MethodHandle nextMethod(Class<?> plugin) throws ReflectiveOperationException {
+        return MethodHandles.lookup().findStatic(plugin, "next", MethodType.methodType(int.class));
+    }
+
+    // child-first for the plugin, everything else comes from the bootstrap loader
+    static class PluginLoader extends ClassLoader {
+        int defined;
+
+        PluginLoader() {
+            super(null);
+        }
+
+        @Override
+        protected Class<?> loadClass(String name, boolean resolve) throws ClassNotFoundException {
+            synchronized (getClassLoadingLock(name)) {
+                Class<?> c = findLoadedClass(name);
+                if (c == null && name.equals(PLUGIN_NAME)) {
+                    c = define(name);
+                }
+                if (c == null) {
+                    c = super.loadClass(name, resolve);
+                }
+                return c;
+            }
+        }
+
+        Class<?> define(String name) {
+            defined++;
+            return defineClass(name, PLUGIN_CLASS, 0, PLUGIN_CLASS.length);
+        }
+    }
+}
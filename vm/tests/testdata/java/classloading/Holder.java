@@ -0,0 +1,6 @@
+package classloading.app;
+
+public class Holder {
+    public static void accept(Shared shared) {
+    }
+}
@@ -0,0 +1,33 @@
+package classloading.modules;
+
+import java.sql.Types;
+import java.util.logging.Level;
+import sun.misc.Unsafe;
+
+public class ModulesOkMain {
+    // parent is the bootstrap loader, only sees the modules the JDK defines to it
+    static class BootDelegatingLoader extends ClassLoader {
+        BootDelegatingLoader() {
+            super(null);
+        }
+    }
+
+    public static void main(String[] args) throws Exception {
+        System.out.println("java.logging: " + Level.WARNING.getName() + " " + Level.WARNING.intValue());
+        System.out.println("java.logging boot loader: " + (Level.class.getClassLoader() == null));
+        System.out.println("jdk.unsupported: " + Unsafe.class.getName());
+        System.out.println("java.sql: " + Types.class.getName() + " " + Types.VARCHAR);
+        System.out.println("java.sql platform loader: "
+                + (Types.class.getClassLoader() == ClassLoader.getPlatformClassLoader()));
+
+        ClassLoader loader = new BootDelegatingLoader();
+        Class<?> level = loader.loadClass("java.util.logging.Level");
+        System.out.println("boot parent finds Level: " + (level == Level.class));
+        try {
+            loader.loadClass("java.sql.Types");
+            System.out.println("boot parent finds Types");
+        } catch (ClassNotFoundException e) {
+            System.out.println("boot parent misses Types: " + e.getMessage());
+        }
+    }
+}
@@ -0,0 +1,4 @@
+package classloading.app;
+
+public class Shared {
+}
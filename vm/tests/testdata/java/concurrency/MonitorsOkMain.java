@@ -0,0 +1,81 @@
+package concurrency.monitors;
+
+public class MonitorsOkMain {
+    private static int staticCounter = 0;
+    private int counter = 0;
+
+    public static void main(String[] args) {
+        test_reentrant_blocks();
+        test_synchronized_methods();
+        test_exception_releases_monitor();
+        test_exception_in_synchronized_method();
+        System.out.println("All monitor tests passed");
+    }
+
+    static void test_reentrant_blocks() {
+        Object lock = new Object();
+        int depth = 0;
+        synchronized (lock) {
+            depth++;
+            synchronized (lock) {
+                depth++;
+                synchronized (lock) {
+                    depth++;
+                }
+            }
+        }
+        System.out.println(depth);
+    }
+
+    static void test_synchronized_methods() {
+        MonitorsOkMain main = new MonitorsOkMain();
+        for (int i = 0; i < 3; i++) {
+            main.increment();
+            incrementStatic();
+        }
+        System.out.println(main.counter);
+        System.out.println(staticCounter);
+    }
+
+    synchronized void increment() {
+        // reentering the monitor held by the synchronized method
+        synchronized (this) {
+            counter++;
+        }
+    }
+
+    static synchronized void incrementStatic() {
+        synchronized (MonitorsOkMain.class) {
+            staticCounter++;
+        }
+    }
+
+    static void test_exception_releases_monitor() {
+        Object lock = new Object();
+        try {
+            synchronized (lock) {
+                throw new IllegalStateException("thrown while holding the lock");
+            }
+        } catch (IllegalStateException e) {
+            System.out.println(e.getMessage());
+        }
+        synchronized (lock) {
+            System.out.println("Lock reacquired");
+        }
+    }
+
+    static void test_exception_in_synchronized_method() {
+        MonitorsOkMain main = new MonitorsOkMain();
+        try {
+            main.failWhileLocked();
+        } catch (IllegalStateException e) {
+            System.out.println(e.getMessage());
+        }
+        main.increment();
+        System.out.println(main.counter);
+    }
+
+    synchronized void failWhileLocked() {
+        throw new IllegalStateException("thrown from synchronized method");
+    }
+}
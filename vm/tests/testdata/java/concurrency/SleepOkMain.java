@@ -0,0 +1,73 @@
+package concurrency.sleep;
+
+public class SleepOkMain {
+    private static final Object lock = new Object();
+
+    public static void main(String[] args) throws InterruptedException {
+        test_sleep_on_worker();
+        test_interrupt_sleeping_worker();
+        test_sleep_with_pending_interrupt();
+        test_yield();
+        test_holds_lock();
+        System.out.println("All sleep tests passed");
+    }
+
+    static void test_sleep_on_worker() throws InterruptedException {
+        long[] elapsed = new long[1];
+        Thread worker = new Thread(() -> {
+            long start = System.nanoTime();
+            try {
+                Thread.sleep(50);
+            } catch (InterruptedException e) {
+                throw new RuntimeException(e);
+            }
+            elapsed[0] = System.nanoTime() - start;
+        });
+        worker.start();
+        worker.join();
+        System.out.println(elapsed[0] >= 50_000_000L);
+    }
+
+    static void test_interrupt_sleeping_worker() throws InterruptedException {
+        String[] result = new String[1];
+        boolean[] interruptedAfter = new boolean[1];
+        Thread worker = new Thread(() -> {
+            try {
+                Thread.sleep(60_000);
+                result[0] = "slept";
+            } catch (InterruptedException e) {
+                result[0] = e.getMessage();
+                interruptedAfter[0] = Thread.currentThread().isInterrupted();
+            }
+        });
+        worker.start();
+        worker.interrupt();
+        worker.join();
+        System.out.println(result[0]);
+        System.out.println(interruptedAfter[0]);
+    }
+
+    static void test_sleep_with_pending_interrupt() {
+        Thread.currentThread().interrupt();
+        try {
+            Thread.sleep(60_000);
+            System.out.println("slept");
+        } catch (InterruptedException e) {
+            System.out.println("interrupted before sleep");
+        }
+        System.out.println(Thread.interrupted());
+    }
+
+    static void test_yield() {
+        Thread.yield();
+        System.out.println("yielded");
+    }
+
+    static void test_holds_lock() {
+        System.out.println(Thread.holdsLock(lock));
+        synchronized (lock) {
+            System.out.println(Thread.holdsLock(lock));
+        }
+        System.out.println(Thread.holdsLock(lock));
+    }
+}
@@ -0,0 +1,72 @@
+package concurrency.threads;
+
+public class ThreadsOkMain {
+    private static final Object lock = new Object();
+    private static int counter = 0;
+    private static String message = null;
+
+    public static void main(String[] args) throws InterruptedException {
+        test_join();
+        test_shared_counter();
+        test_current_thread();
+        test_wait_notify_between_threads();
+        System.out.println("All thread tests passed");
+    }
+
+    static void test_join() throws InterruptedException {
+        int[] result = new int[1];
+        Thread worker = new Thread(() -> result[0] = 42);
+        System.out.println(worker.isAlive());
+        worker.start();
+        worker.join();
+        System.out.println(worker.isAlive());
+        System.out.println(result[0]);
+        System.out.println(worker.getState());
+    }
+
+    static void test_shared_counter() throws InterruptedException {
+        Thread[] workers = new Thread[4];
+        for (int i = 0; i < workers.length; i++) {
+            workers[i] = new Thread(() -> {
+                for (int j = 0; j < 1000; j++) {
+                    synchronized (lock) {
+                        counter++;
+                    }
+                }
+            });
+        }
+        for (Thread worker : workers) {
+            worker.start();
+        }
+        for (Thread worker : workers) {
+            worker.join();
+        }
+        System.out.println(counter);
+    }
+
+    static void test_current_thread() throws InterruptedException {
+        String[] names = new String[1];
+        Thread worker = new Thread(() -> names[0] = Thread.currentThread().getName(), "worker-1");
+        worker.start();
+        worker.join();
+        System.out.println(names[0]);
+        System.out.println(Thread.currentThread().getName());
+    }
+
+    static void test_wait_notify_between_threads() throws InterruptedException {
+        Thread producer = new Thread(() -> {
+            synchronized (lock) {
+                message = "produced";
+                lock.notifyAll();
+            }
+        });
+        synchronized (lock) {
+            producer.start();
+            while (message == null) {
+                lock.wait();
+            }
+        }
+        producer.join();
+        System.out.println(message);
+    }
+}
@@ -0,0 +1,88 @@
+package concurrency.wait_notify;
+
+public class WaitNotifyOkMain {
+    public static void main(String[] args) throws InterruptedException {
+        test_timed_wait();
+        test_wait_keeps_recursion();
+        test_notify_without_waiters();
+        test_not_owner();
+        test_interrupted_before_wait();
+        test_interrupt_not_alive();
+        System.out.println("All wait/notify tests passed");
+    }
+
+    static void test_timed_wait() throws InterruptedException {
+        Object lock = new Object();
+        synchronized (lock) {
+            lock.wait(10);
+        }
+        System.out.println("Timed wait returned");
+    }
+
+    static void test_wait_keeps_recursion() throws InterruptedException {
+        Object lock = new Object();
+        synchronized (lock) {
+            synchronized (lock) {
+                lock.wait(1);
+            }
+            // still owned once after the inner block
+            lock.notify();
+        }
+        System.out.println("Recursion restored");
+    }
+
+    static void test_notify_without_waiters() {
+        Object lock = new Object();
+        synchronized (lock) {
+            lock.notify();
+            lock.notifyAll();
+        }
+        System.out.println("Notified nobody");
+    }
+
+    static void test_not_owner() throws InterruptedException {
+        Object lock = new Object();
+        try {
+            lock.notify();
+        } catch (IllegalMonitorStateException e) {
+            System.out.println("notify: " + e.getMessage());
+        }
+        try {
+            lock.notifyAll();
+        } catch (IllegalMonitorStateException e) {
+            System.out.println("notifyAll: " + e.getMessage());
+        }
+        try {
+            lock.wait(1);
+        } catch (IllegalMonitorStateException e) {
+            System.out.println("wait: " + e.getMessage());
+        }
+    }
+
+    static void test_interrupted_before_wait() {
+        Object lock = new Object();
+        Thread.currentThread().interrupt();
+        System.out.println(Thread.currentThread().isInterrupted());
+        try {
+            synchronized (lock) {
+                lock.wait();
+            }
+            System.out.println("Not interrupted");
+        } catch (InterruptedException e) {
+            System.out.println("Interrupted");
+        }
+        // the interrupt status is cleared when InterruptedException is thrown
+        System.out.println(Thread.interrupted());
+    }
+
+    static void test_interrupt_not_alive() throws InterruptedException {
+        Thread unstarted = new Thread(() -> {});
+        unstarted.interrupt();
+        System.out.println("Unstarted interrupted: " + unstarted.isInterrupted());
+        Thread terminated = new Thread(() -> {});
+        terminated.start();
+        terminated.join();
+        terminated.interrupt();
+        System.out.println("Terminated interrupted: " + terminated.isInterrupted());
+    }
+}
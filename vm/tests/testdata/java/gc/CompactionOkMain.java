@@ -0,0 +1,101 @@
+package gc.compaction;
+
+public class CompactionOkMain {
+    static class Node {
+        final int value;
+        Node next;
+
+        Node(int value) {
+            this.value = value;
+        }
+    }
+
+    public static void main(String[] args) {
+        test_survivors_between_garbage();
+        test_locked_object_moves();
+        test_monitors_of_dead_objects_are_freed();
+        test_interned_strings_stay_identical();
+        test_class_mirrors_stay_identical();
+        System.out.println("All compaction tests passed");
+    }
+
+    static void test_survivors_between_garbage() {
+        // every tenth node survives, the others leave holes the survivors slide into
+        Node[] survivors = new Node[100];
+        for (int i = 0; i < 1000; i++) {
+            Node node = new Node(i);
+            if (i % 10 == 0) {
+                survivors[i / 10] = node;
+            }
+        }
+        System.gc();
+        int sum = 0;
+        for (int i = 0; i < survivors.length; i++) {
+            sum += survivors[i].value;
+        }
+        System.out.println(sum);
+    }
+
+    static void test_locked_object_moves() {
+        garbage();
+        Object lock = new Object();
+        synchronized (lock) {
+            System.gc();
+            // throws IllegalMonitorStateException if the monitor got lost
+            lock.notifyAll();
+        }
+        System.out.println("lock held");
+    }
+
+    static void test_monitors_of_dead_objects_are_freed() {
+        Object kept = new Object();
+        synchronized (kept) {
+            kept.notifyAll();
+        }
+        for (int i = 0; i < 1000; i++) {
+            Object dead = new Object();
+            synchronized (dead) {
+                dead.notify();
+            }
+        }
+        System.gc();
+        // the new objects get the ids of the freed monitors, the survivor keeps its own
+        Object[] fresh = new Object[1000];
+        for (int i = 0; i < fresh.length; i++) {
+            fresh[i] = new Object();
+            synchronized (fresh[i]) {
+                fresh[i].notify();
+            }
+        }
+        System.gc();
+        synchronized (kept) {
+            // throws IllegalMonitorStateException if the monitor went to another object
+            kept.notifyAll();
+        }
+        synchronized (fresh[0]) {
+            fresh[0].notifyAll();
+        }
+        System.out.println("monitors freed");
+    }
+
+    static void test_interned_strings_stay_identical() {
+        String before = literal();
+        System.gc();
+        System.out.println(before == literal());
+    }
+
+    static void test_class_mirrors_stay_identical() {
+        Class<?> before = new Node(1).getClass();
+        System.gc();
+        System.out.println(before == Node.class);
+    }
+
+    static void garbage() {
+        int[] garbage = new int[1024];
+        garbage[0] = 1;
+    }
+
+    static String literal() {
+        return "compacted";
+    }
+}
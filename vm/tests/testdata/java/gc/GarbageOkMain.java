@@ -0,0 +1,89 @@
+package gc.garbage;
+
+public class GarbageOkMain {
+    private static Node retained;
+
+    static class Node {
+        final int value;
+        Node next;
+
+        Node(int value) {
+            this.value = value;
+        }
+    }
+
+    public static void main(String[] args) {
+        test_garbage_is_reclaimed();
+        test_static_field_roots();
+        test_local_roots();
+        test_array_elements_survive();
+        test_explicit_gc();
+        System.out.println("All gc tests passed");
+    }
+
+    static void test_garbage_is_reclaimed() {
+        // 8MB in total, many times the nursery
+        int total = 0;
+        for (int i = 0; i < 2000; i++) {
+            int[] garbage = new int[1024];
+            garbage[i % 1024] = i;
+            total += garbage[i % 1024];
+        }
+        System.out.println(total);
+    }
+
+    static void test_static_field_roots() {
+        retained = buildList(100);
+        churn();
+        System.out.println(sum(retained));
+    }
+
+    static void test_local_roots() {
+        Node list = buildList(50);
+        churn();
+        System.out.println(sum(list));
+    }
+
+    static void test_array_elements_survive() {
+        String[] strings = new String[100];
+        for (int i = 0; i < strings.length; i++) {
+            strings[i] = "s" + i;
+        }
+        churn();
+        int length = 0;
+        for (int i = 0; i < strings.length; i++) {
+            length += strings[i].length();
+        }
+        System.out.println(length);
+    }
+
+    static void test_explicit_gc() {
+        Node list = buildList(10);
+        System.gc();
+        System.out.println(sum(list));
+    }
+
+    static void churn() {
+        for (int i = 0; i < 1000; i++) {
+            buildList(100);
+        }
+    }
+
+    static Node buildList(int length) {
+        Node head = null;
+        for (int i = length - 1; i >= 0; i--) {
+            Node node = new Node(i);
+            node.next = head;
+            head = node;
+        }
+        return head;
+    }
+
+    static int sum(Node list) {
+        int sum = 0;
+        for (Node node = list; node != null; node = node.next) {
+            sum += node.value;
+        }
+        return sum;
+    }
+}
@@ -0,0 +1,81 @@
+package gc.generations;
+
+public class GenerationsOkMain {
+    static class Holder {
+        Object value;
+    }
+
+    public static void main(String[] args) {
+        test_old_object_references_young();
+        test_old_array_references_young();
+        test_arraycopy_into_old_array();
+        test_short_lived_boxes();
+        System.out.println("All generation tests passed");
+    }
+
+    static void test_old_object_references_young() {
+        Holder holder = new Holder();
+        tenure();
+        holder.value = new int[] {42};
+        churn();
+        System.out.println(((int[]) holder.value)[0]);
+    }
+
+    static void test_old_array_references_young() {
+        // too big for the nursery, allocated in the old space directly
+        Object[] array = new Object[10000];
+        for (int i = 0; i < 100; i++) {
+            array[i] = Integer.valueOf(1000 + i);
+        }
+        churn();
+        int sum = 0;
+        for (int i = 0; i < 100; i++) {
+            sum += (Integer) array[i];
+        }
+        System.out.println(sum);
+    }
+
+    static void test_arraycopy_into_old_array() {
+        Object[] array = new Object[10000];
+        Object[] strings = new Object[10];
+        for (int i = 0; i < strings.length; i++) {
+            strings[i] = "s" + i;
+        }
+        System.arraycopy(strings, 0, array, 0, strings.length);
+        strings = null;
+        churn();
+        int length = 0;
+        for (int i = 0; i < 10; i++) {
+            length += ((String) array[i]).length();
+        }
+        System.out.println(length);
+    }
+
+    static void test_short_lived_boxes() {
+        long sum = 0;
+        for (int i = 0; i < 100000; i++) {
+            Long boxed = Long.valueOf(i);
+            sum += boxed;
+        }
+        System.out.println(sum);
+    }
+
+    // survivors are promoted to the old space after a few collections
+    static void tenure() {
+        for (int i = 0; i < 5; i++) {
+            System.gc();
+        }
+    }
+
+    static void churn() {
+        int total = 0;
+        for (int i = 0; i < 20000; i++) {
+            int[] garbage = new int[16];
+            garbage[i % 16] = 1;
+            total += garbage[i % 16];
+        }
+        if (total != 20000) {
+            throw new AssertionError(total);
+        }
+    }
+}
@@ -0,0 +1,19 @@
+package gc.heap_dump;
+
+import java.io.File;
+
+// not an OkMain, it runs with -XX:+HeapDumpOnCtrlBreak and gets a SIGQUIT, see integration_test.rs
+public class HeapDumpMain {
+    public static void main(String[] args) throws InterruptedException {
+        File dump = new File(args[0]);
+        Object lock = new Object();
+        System.out.println("ready");
+        // the dump is written with the world stopped, the file is complete once it's there
+        for (int i = 0; i < 1000 && !dump.exists(); i++) {
+            synchronized (lock) {
+                lock.wait(10);
+            }
+        }
+        System.out.println(dump.exists());
+    }
+}
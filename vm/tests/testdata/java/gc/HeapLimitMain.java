@@ -0,0 +1,31 @@
+package gc.heap_limit;
+
+import java.util.ArrayList;
+import java.util.List;
+
+// not an OkMain, it runs with a small heap, see integration_test.rs
+public class HeapLimitMain {
+    public static void main(String[] args) {
+        Runtime runtime = Runtime.getRuntime();
+        System.out.println(runtime.maxMemory() / (1024 * 1024));
+        System.out.println(runtime.totalMemory() <= runtime.maxMemory());
+        System.out.println(runtime.freeMemory() <= runtime.totalMemory());
+
+        int count = fillHeap();
+        System.out.println(count > 0);
+    }
+
+    static int fillHeap() {
+        List<long[]> retained = new ArrayList<>();
+        try {
+            while (true) {
+                retained.add(new long[64 * 1024]);
+            }
+        } catch (OutOfMemoryError e) {
+            int count = retained.size();
+            retained = null;
+            System.out.println(e.getMessage());
+            return count;
+        }
+    }
+}
@@ -0,0 +1,20 @@
+package gc.histogram;
+
+// not an OkMain, it runs with -XX:+PrintClassHistogramAtExit, see integration_test.rs
+public class HistogramMain {
+    static class Node {
+        int value;
+        Node next;
+    }
+
+    static Node[] nodes;
+
+    public static void main(String[] args) {
+        nodes = new Node[1000];
+        for (int i = 0; i < nodes.length; i++) {
+            nodes[i] = new Node();
+            nodes[i].value = i;
+        }
+        System.out.println(nodes.length);
+    }
+}
@@ -0,0 +1,123 @@
+package gc.references;
+
+import java.lang.ref.PhantomReference;
+import java.lang.ref.Reference;
+import java.lang.ref.ReferenceQueue;
+import java.lang.ref.SoftReference;
+import java.lang.ref.WeakReference;
+import java.util.WeakHashMap;
+
+public class ReferencesOkMain {
+    public static void main(String[] args) {
+        test_weak_referent_is_cleared();
+        test_strongly_reachable_referent_is_kept();
+        test_referents_of_promoted_references();
+        test_soft_referent_survives_gc();
+        test_cleared_reference_is_enqueued();
+        test_phantom_reference_is_enqueued();
+        test_weak_hash_map_expunges_entries();
+        test_clear_and_enqueue();
+        System.out.println("All reference tests passed");
+    }
+
+    static void test_weak_referent_is_cleared() {
+        WeakReference<Object> ref = new WeakReference<>(new Object());
+        System.gc();
+        System.out.println(ref.get() == null);
+        System.out.println(ref.refersTo(null));
+    }
+
+    static void test_strongly_reachable_referent_is_kept() {
+        String referent = new StringBuilder("kept").append(1).toString();
+        WeakReference<String> ref = new WeakReference<>(referent);
+        System.gc();
+        System.gc();
+        System.out.println(ref.get() == referent);
+        System.out.println(ref.refersTo(referent));
+    }
+
+    static void test_referents_of_promoted_references() {
+        WeakReference<int[]> dead = new WeakReference<>(new int[] {1});
+        int[] live = new int[] {2};
+        WeakReference<int[]> kept = new WeakReference<>(live);
+        // survivors are promoted to the old space after a few collections
+        for (int i = 0; i < 5; i++) {
+            System.gc();
+        }
+        System.out.println(dead.get() == null);
+        System.out.println(kept.get() == live);
+    }
+
+    static void test_soft_referent_survives_gc() {
+        SoftReference<int[]> ref = new SoftReference<>(new int[] {42});
+        System.gc();
+        System.out.println(ref.get()[0]);
+    }
+
+    static void test_cleared_reference_is_enqueued() {
+        ReferenceQueue<Object> queue = new ReferenceQueue<>();
+        WeakReference<Object> ref = new WeakReference<>(new Object(), queue);
+        System.gc();
+        System.out.println(awaitEnqueued(queue) == ref);
+        System.out.println(queue.poll() == null);
+    }
+
+    static void test_phantom_reference_is_enqueued() {
+        ReferenceQueue<Object> queue = new ReferenceQueue<>();
+        PhantomReference<Object> ref = new PhantomReference<>(new Object(), queue);
+        System.out.println(ref.get() == null);
+        System.out.println(ref.refersTo(null));
+        System.gc();
+        System.out.println(awaitEnqueued(queue) == ref);
+        System.out.println(ref.refersTo(null));
+    }
+
+    static void test_weak_hash_map_expunges_entries() {
+        WeakHashMap<String, Integer> map = new WeakHashMap<>();
+        String key = new StringBuilder("key").append(1).toString();
+        map.put(key, 1);
+        map.put(new StringBuilder("key").append(2).toString(), 2);
+        System.gc();
+        // the map drops the entries once their references are enqueued
+        for (int i = 0; map.size() != 1; i++) {
+            pause(i, "the entry of the collected key was not expunged");
+        }
+        System.out.println(map.get(key));
+    }
+
+    static void test_clear_and_enqueue() {
+        ReferenceQueue<Object> queue = new ReferenceQueue<>();
+        Object referent = new Object();
+        WeakReference<Object> ref = new WeakReference<>(referent, queue);
+        ref.clear();
+        System.out.println(ref.get() == null);
+        System.out.println(ref.enqueue());
+        System.out.println(ref.enqueue());
+        System.out.println(queue.poll() == ref);
+    }
+
+    // the Reference Handler thread enqueues the cleared references after the collection
+    static Reference<?> awaitEnqueued(ReferenceQueue<Object> queue) {
+        Reference<?> ref;
+        for (int i = 0; (ref = queue.poll()) == null; i++) {
+            pause(i, "the cleared reference was not enqueued");
+        }
+        return ref;
+    }
+
+    // waits up to 5 seconds in all, a broken reference processing fails the test instead of
+    // hanging it. Not queue.remove(timeout), its timeout is measured with System.nanoTime
+    static void pause(int attempt, String failure) {
+        if (attempt >= 500) {
+            throw new AssertionError(failure);
+        }
+        Object lock = new Object();
+        synchronized (lock) {
+            try {
+                lock.wait(10);
+            } catch (InterruptedException e) {
+                throw new AssertionError(e);
+            }
+        }
+    }
+}
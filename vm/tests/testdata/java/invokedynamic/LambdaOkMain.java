@@ -0,0 +1,40 @@
+package invokedynamic.lambda;
+
+import java.util.function.Function;
+import java.util.function.IntBinaryOperator;
+import java.util.function.Supplier;
+
+public class LambdaOkMain {
+    public static void main(String[] args) {
+        test_non_capturing_lambda();
+        test_capturing_lambda();
+        test_method_reference();
+        test_call_site_is_linked_once();
+        System.out.println("All lambda tests passed");
+    }
+
+    static void test_non_capturing_lambda() {
+        IntBinaryOperator add = (a, b) -> a + b;
+        System.out.println(add.applyAsInt(40, 2));
+    }
+
+    static void test_capturing_lambda() {
+        String greeting = "Hello from lambda";
+        Supplier<String> supplier = () -> greeting;
+        System.out.println(supplier.get());
+    }
+
+    static void test_method_reference() {
+        Function<String, String> upper = String::toUpperCase;
+        System.out.println(upper.apply("method reference"));
+    }
+
+    static void test_call_site_is_linked_once() {
+        int sum = 0;
+        for (int i = 0; i < 5; i++) {
+            IntBinaryOperator mul = (a, b) -> a * b;
+            sum = sum + mul.applyAsInt(i, i);
+        }
+        System.out.println(sum);
+    }
+}
@@ -0,0 +1,36 @@
+package invokedynamic.static_field;
+
+import java.lang.invoke.MethodHandle;
+import java.lang.invoke.MethodHandles;
+
+public class StaticFieldHandleOkMain {
+    static int counter = 42;
+    static String label = "initial";
+
+    public static void main(String[] args) throws Throwable {
+        test_static_int_getter_and_setter();
+        test_static_reference_getter_and_setter();
+        System.out.println("All static field handle tests passed");
+    }
+
+    static void test_static_int_getter_and_setter() throws Throwable {
+        MethodHandles.Lookup lookup = MethodHandles.lookup();
+        MethodHandle getter = lookup.findStaticGetter(StaticFieldHandleOkMain.class, "counter", int.class);
+        MethodHandle setter = lookup.findStaticSetter(StaticFieldHandleOkMain.class, "counter", int.class);
+        System.out.println((int) getter.invokeExact());
+        setter.invokeExact(7);
+        System.out.println((int) getter.invokeExact());
+        System.out.println(counter);
+    }
+
+    static void test_static_reference_getter_and_setter() throws Throwable {
+        MethodHandles.Lookup lookup = MethodHandles.lookup();
+        MethodHandle getter = lookup.findStaticGetter(StaticFieldHandleOkMain.class, "label", String.class);
+        MethodHandle setter = lookup.findStaticSetter(StaticFieldHandleOkMain.class, "label", String.class);
+        System.out.println((String) getter.invokeExact());
+        setter.invokeExact("updated");
+        System.out.println((String) getter.invokeExact());
+        setter.invokeExact((String) null);
+        System.out.println(label);
+    }
+}
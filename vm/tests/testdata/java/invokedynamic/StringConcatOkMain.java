@@ -0,0 +1,82 @@
+package invokedynamic.string_concat;
+
+public class StringConcatOkMain {
+    static final String CONSTANT = "const";
+
+    public static void main(String[] args) {
+        test_primitives();
+        test_references();
+        test_floating_point();
+        test_non_latin1();
+        test_lone_surrogates();
+        test_call_site_is_parsed_once();
+        System.out.println("All string concat tests passed");
+    }
+
+    static void test_primitives() {
+        int i = -42;
+        long l = 9000000000L;
+        char c = 'x';
+        boolean b = true;
+        byte by = 7;
+        short s = 300;
+        System.out.println("int=" + i + ", long=" + l + ", char=" + c + ", boolean=" + b);
+        System.out.println("byte=" + by + ", short=" + s);
+    }
+
+    static void test_references() {
+        String name = "lagertha";
+        String missing = null;
+        Object obj = new Point(1, 2);
+        System.out.println("name=" + name + ", missing=" + missing + ", obj=" + obj);
+        System.out.println(CONSTANT + "\u0001" + name + "\u0002");
+    }
+
+    static void test_floating_point() {
+        float f = 1.0f;
+        double d = 1e10;
+        System.out.println("float=" + f + ", double=" + d);
+    }
+
+    static void test_non_latin1() {
+        String greek = "αβγ";
+        System.out.println("greek=" + greek + 'δ');
+    }
+
+    static void test_lone_surrogates() {
+        char high = '\uD800';
+        String lone = "" + high;
+        System.out.println("lone char: length=" + lone.length() + ", unit=" + (int) lone.charAt(0));
+        char lowSurrogate = '\uDC00';
+        String low = "a" + lowSurrogate;
+        String joined = low + high;
+        System.out.println("lone string: units=" + (int) joined.charAt(1) + "," + (int) joined.charAt(2));
+        char pairHigh = '\uD83D';
+        char pairLow = '\uDE00';
+        String pair = "" + pairHigh + pairLow;
+        System.out.println("pair: code point=" + pair.codePointAt(0));
+    }
+
+    static void test_call_site_is_parsed_once() {
+        String result = "";
+        for (int i = 0; i < 3; i++) {
+            result = result + i + ";";
+        }
+        System.out.println(result);
+    }
+
+    static class Point {
+        final int x;
+        final int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        @Override
+        public String toString() {
+            return "Point(" + x + ", " + y + ")";
+        }
+    }
+}
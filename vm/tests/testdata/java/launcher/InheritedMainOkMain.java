@@ -0,0 +1,16 @@
+package launcher.inherited_main;
+
+public class InheritedMainOkMain extends Base {
+    @Override
+    String name() {
+        return "InheritedMainOkMain";
+    }
+}
+
+abstract class Base {
+    abstract String name();
+
+    void main() {
+        System.out.println("Inherited main of " + name());
+    }
+}
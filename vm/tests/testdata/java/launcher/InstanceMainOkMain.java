@@ -0,0 +1,13 @@
+package launcher.instance_main;
+
+public class InstanceMainOkMain {
+    private final String greeting;
+
+    InstanceMainOkMain() {
+        greeting = "Hello from instance main";
+    }
+
+    void main() {
+        System.out.println(greeting);
+    }
+}
@@ -0,0 +1,12 @@
+package launcher.instance_main_with_args;
+
+class InstanceMainWithArgsOkMain {
+    // main(String[]) is preferred over main()
+    void main() {
+        System.out.println("main() must not be selected");
+    }
+
+    void main(String[] args) {
+        System.out.println("Instance main with " + args.length + " args");
+    }
+}
@@ -0,0 +1,41 @@
+package launcher.jar;
+
+import java.util.ArrayList;
+import java.util.List;
+
+public class JarMain {
+    public static void main(String[] args) {
+        List<Greeter> greeters = new ArrayList<>();
+        greeters.add(new Greeter("jar"));
+        greeters.add(new Greeter.Loud("archive"));
+        for (Greeter greeter : greeters) {
+            System.out.println(greeter.greet());
+        }
+        for (String arg : args) {
+            System.out.println(arg);
+        }
+    }
+}
+
+class Greeter {
+    private final String name;
+
+    Greeter(String name) {
+        this.name = name;
+    }
+
+    String greet() {
+        return "Hello from " + name;
+    }
+
+    static class Loud extends Greeter {
+        Loud(String name) {
+            super(name);
+        }
+
+        @Override
+        String greet() {
+            return super.greet().toUpperCase() + "!";
+        }
+    }
+}
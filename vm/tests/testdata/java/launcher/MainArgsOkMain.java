@@ -0,0 +1,10 @@
+package launcher.main_args;
+
+public class MainArgsOkMain {
+    public static void main(String[] args) {
+        System.out.println(args.length);
+        for (String arg : args) {
+            System.out.println(arg);
+        }
+    }
+}
@@ -0,0 +1,11 @@
+package launcher.no_main;
+
+public class NoMainErrMain {
+    private static void main(String[] args) {
+        System.out.println("private main must not be selected");
+    }
+
+    public void run() {
+        System.out.println("not a main method");
+    }
+}
@@ -0,0 +1,7 @@
+package launcher.static_no_args_main;
+
+class StaticNoArgsMainOkMain {
+    static void main() {
+        System.out.println("Static main without args");
+    }
+}
@@ -0,0 +1,9 @@
+package lifecycle.exit_code;
+
+public class ExitCodeErrMain {
+    public static void main(String[] args) {
+        Runtime.getRuntime().addShutdownHook(new Thread(() -> System.out.println("Shutdown hook ran")));
+        System.out.println("Exiting with 3");
+        System.exit(3);
+    }
+}
@@ -0,0 +1,18 @@
+package lifecycle.exit;
+
+public class ExitOkMain {
+    public static void main(String[] args) {
+        Runtime.getRuntime().addShutdownHook(new Thread(() -> System.out.println("Shutdown hook ran")));
+        System.out.println("Before exit");
+        try {
+            exit();
+        } finally {
+            System.out.println("Finally must not run");
+        }
+    }
+
+    static void exit() {
+        System.exit(0);
+        System.out.println("After exit");
+    }
+}
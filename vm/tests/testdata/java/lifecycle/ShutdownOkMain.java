@@ -0,0 +1,36 @@
+package lifecycle.shutdown;
+
+public class ShutdownOkMain {
+    private static final Object lock = new Object();
+
+    public static void main(String[] args) {
+        Runtime.getRuntime().addShutdownHook(new Thread(() -> System.out.println("Shutdown hook ran")));
+
+        Thread daemon = new Thread(() -> {
+            synchronized (lock) {
+                try {
+                    // never notified, must not keep the VM alive
+                    lock.wait();
+                } catch (InterruptedException e) {
+                    System.out.println("Daemon interrupted");
+                }
+            }
+        });
+        daemon.setDaemon(true);
+        daemon.start();
+
+        Thread worker = new Thread(() -> {
+            Object pause = new Object();
+            synchronized (pause) {
+                try {
+                    pause.wait(50);
+                } catch (InterruptedException e) {
+                    System.out.println("Worker interrupted");
+                }
+            }
+            System.out.println("Worker finished after main");
+        });
+        worker.start();
+        System.out.println("Main finished");
+    }
+}
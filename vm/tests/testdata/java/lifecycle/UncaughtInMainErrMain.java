@@ -0,0 +1,20 @@
+package lifecycle.uncaught_in_main;
+
+public class UncaughtInMainErrMain {
+    public static void main(String[] args) {
+        Runtime.getRuntime().addShutdownHook(new Thread(() -> System.out.println("Shutdown hook ran")));
+        Thread worker = new Thread(() -> {
+            Object pause = new Object();
+            synchronized (pause) {
+                try {
+                    pause.wait(50);
+                } catch (InterruptedException e) {
+                    System.out.println("Worker interrupted");
+                }
+            }
+            System.out.println("Worker finished after main");
+        });
+        worker.start();
+        throw new IllegalStateException("main failed");
+    }
+}
@@ -0,0 +1,155 @@
+package natives.object.clone;
+
+import java.util.ArrayList;
+import java.util.HashMap;
+import java.util.List;
+import java.util.Map;
+
+public class CloneOkMain {
+    public static void main(String[] args) throws Exception {
+        test_clone_is_shallow_copy();
+        test_subclass_fields_are_copied();
+        test_not_cloneable_throws();
+        test_clone_has_own_identity_hash();
+        test_clone_of_locked_object_is_unlocked();
+        test_clone_of_throwable_subclass();
+        test_collections_clone();
+        test_array_clone();
+        System.out.println("All clone tests passed");
+    }
+
+    static void test_clone_is_shallow_copy() {
+        Point point = new Point(1, 2, new int[] {3});
+        Point copy = point.clone();
+        System.out.println(copy != point);
+        System.out.println(copy.x + " " + copy.y);
+        System.out.println(copy.payload == point.payload);
+        copy.x = 10;
+        System.out.println(point.x);
+    }
+
+    static void test_subclass_fields_are_copied() {
+        Point3 point = new Point3(1, 2, 3);
+        Point copy = point.clone();
+        System.out.println(copy.getClass() == Point3.class);
+        System.out.println(copy.x + " " + copy.y + " " + ((Point3) copy).z);
+    }
+
+    static void test_not_cloneable_throws() {
+        try {
+            new Plain().copy();
+            System.out.println("no exception");
+        } catch (CloneNotSupportedException e) {
+            System.out.println(e.getMessage());
+        }
+    }
+
+    static void test_clone_has_own_identity_hash() {
+        Point point = new Point(1, 2, null);
+        int hash = point.hashCode();
+        Point copy = point.clone();
+        System.out.println(copy.hashCode() != hash);
+        System.out.println(point.hashCode() == hash);
+    }
+
+    static void test_clone_of_locked_object_is_unlocked() {
+        Point point = new Point(1, 2, null);
+        Point copy;
+        synchronized (point) {
+            copy = point.clone();
+            // the lock is held on the original only
+            point.notify();
+            try {
+                copy.notify();
+                System.out.println("no exception");
+            } catch (IllegalMonitorStateException e) {
+                System.out.println("clone unlocked");
+            }
+        }
+        synchronized (copy) {
+            copy.notify();
+        }
+        System.out.println("clone lockable");
+    }
+
+    static void test_clone_of_throwable_subclass() {
+        CloneableException exception = new CloneableException("boom");
+        CloneableException copy = exception.clone();
+        System.out.println(copy.getMessage());
+        System.out.println(copy.getStackTrace().length == exception.getStackTrace().length);
+    }
+
+    static void test_collections_clone() {
+        ArrayList<String> list = new ArrayList<>(List.of("a", "b"));
+        @SuppressWarnings("unchecked")
+        ArrayList<String> listCopy = (ArrayList<String>) list.clone();
+        listCopy.add("c");
+        System.out.println(list + " " + listCopy);
+
+        HashMap<String, Integer> map = new HashMap<>(Map.of("one", 1));
+        @SuppressWarnings("unchecked")
+        HashMap<String, Integer> mapCopy = (HashMap<String, Integer>) map.clone();
+        mapCopy.put("two", 2);
+        System.out.println(map.size() + " " + mapCopy.size() + " " + mapCopy.get("one"));
+    }
+
+    static void test_array_clone() {
+        int[] ints = {1, 2, 3};
+        int[] intsCopy = ints.clone();
+        intsCopy[0] = 9;
+        System.out.println(ints[0] + " " + intsCopy[0]);
+        String[] strings = {"x"};
+        System.out.println(strings.clone()[0] == strings[0]);
+    }
+
+    static class Point implements Cloneable {
+        int x;
+        int y;
+        int[] payload;
+
+        Point(int x, int y, int[] payload) {
+            this.x = x;
+            this.y = y;
+            this.payload = payload;
+        }
+
+        @Override
+        public Point clone() {
+            try {
+                return (Point) super.clone();
+            } catch (CloneNotSupportedException e) {
+                throw new AssertionError(e);
+            }
+        }
+    }
+
+    static class Point3 extends Point {
+        int z;
+
+        Point3(int x, int y, int z) {
+            super(x, y, null);
+            this.z = z;
+        }
+    }
+
+    static class Plain {
+        Object copy() throws CloneNotSupportedException {
+            return super.clone();
+        }
+    }
+
+    static class CloneableException extends RuntimeException implements Cloneable {
+        CloneableException(String message) {
+            super(message);
+        }
+
+        @Override
+        public CloneableException clone() {
+            try {
+                return (CloneableException) super.clone();
+            } catch (CloneNotSupportedException e) {
+                throw new AssertionError(e);
+            }
+        }
+    }
+}
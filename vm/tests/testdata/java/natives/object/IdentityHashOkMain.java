@@ -0,0 +1,72 @@
+package natives.object.identity_hash;
+
+import java.util.HashMap;
+import java.util.IdentityHashMap;
+import java.util.Map;
+
+public class IdentityHashOkMain {
+    public static void main(String[] args) {
+        test_hash_code_matches_identity_hash_code();
+        test_hash_is_stable_across_collections();
+        test_distinct_objects_have_distinct_hashes();
+        test_identity_keys_survive_collections();
+        System.out.println(System.identityHashCode(null));
+        System.out.println("All identity hash tests passed");
+    }
+
+    static void test_hash_code_matches_identity_hash_code() {
+        Object object = new Object();
+        int[] array = new int[4];
+        System.out.println(object.hashCode() == System.identityHashCode(object));
+        System.out.println(array.hashCode() == System.identityHashCode(array));
+        System.out.println(object.hashCode() == object.hashCode());
+    }
+
+    static void test_hash_is_stable_across_collections() {
+        Object young = new Object();
+        int youngHash = young.hashCode();
+        Object[] holder = new Object[] {new Object()};
+        int heldHash = System.identityHashCode(holder[0]);
+        // the objects are copied out of the nursery, then compacted in the old space
+        for (int i = 0; i < 6; i++) {
+            System.gc();
+        }
+        System.out.println(young.hashCode() == youngHash);
+        System.out.println(holder[0].hashCode() == heldHash);
+    }
+
+    static void test_distinct_objects_have_distinct_hashes() {
+        int collisions = 0;
+        int previous = new Object().hashCode();
+        for (int i = 0; i < 1000; i++) {
+            int hash = new Object().hashCode();
+            if (hash == previous || hash == 0) {
+                collisions++;
+            }
+            previous = hash;
+        }
+        System.out.println(collisions);
+    }
+
+    static void test_identity_keys_survive_collections() {
+        Object[] keys = new Object[100];
+        Map<Object, Integer> map = new HashMap<>();
+        Map<Object, Integer> identityMap = new IdentityHashMap<>();
+        for (int i = 0; i < keys.length; i++) {
+            keys[i] = new Object();
+            map.put(keys[i], i);
+            identityMap.put(keys[i], i);
+        }
+        for (int i = 0; i < 6; i++) {
+            System.gc();
+        }
+        int found = 0;
+        for (int i = 0; i < keys.length; i++) {
+            if (map.get(keys[i]) == i && identityMap.get(keys[i]) == i) {
+                found++;
+            }
+        }
+        System.out.println(found);
+        System.out.println(identityMap.containsKey(new Object()));
+    }
+}